@@ -0,0 +1,308 @@
+//! Mock Claude CLI for offline development and tests
+//!
+//! Accepts the flags CodePod passes to `claude` (`-p`, `--output-format`,
+//! `--resume`, `--continue`, `--model`, `--verbose`, `--version`) and replays
+//! a stream-json fixture instead of calling the API. Point CodePod at it with
+//! `CODEPOD_CLAUDE_BIN=/path/to/mock-claude`.
+//!
+//! Behaviour is controlled per invocation by directives in the prompt, or
+//! globally by environment variables:
+//!
+//! - `[mock:fixture=PATH]` / `MOCK_CLAUDE_FIXTURE` - stream-json file to replay
+//! - `[mock:delay=MS]` / `MOCK_CLAUDE_DELAY_MS` - delay between lines (default 25)
+//! - `[mock:error=KIND]` / `MOCK_CLAUDE_ERROR` - fail with `overloaded`,
+//!   `rate_limit` or `auth`
+//! - `[mock:fail-first=N]` - only fail the first N runs of the same prompt
+//! - `[mock:crash]` - exit abruptly halfway through the stream
+//! - `[mock:hang]` - stop producing output after the first line
+//!
+//! Fixture lines may carry a `_delay_ms` field, which overrides the delay
+//! before that line and is stripped from the output.
+
+use serde_json::{json, Value};
+use std::collections::hash_map::DefaultHasher;
+use std::env;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::process;
+use std::thread;
+use std::time::Duration;
+
+const DEFAULT_DELAY_MS: u64 = 25;
+
+#[derive(Debug, Default)]
+struct Args {
+    print: bool,
+    prompt: Option<String>,
+    output_format: String,
+    model: Option<String>,
+    resume: Option<String>,
+    version: bool,
+}
+
+#[derive(Debug, Default)]
+struct Directives {
+    fixture: Option<String>,
+    delay_ms: Option<u64>,
+    error: Option<String>,
+    fail_first: Option<u32>,
+    crash: bool,
+    hang: bool,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        output_format: "text".to_string(),
+        ..Default::default()
+    };
+    let mut iter = env::args().skip(1);
+
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| {
+            iter.next()
+                .ok_or_else(|| format!("error: option '{}' requires a value", name))
+        };
+
+        match arg.as_str() {
+            // The prompt is positional; `-p` only selects print mode
+            "-p" | "--print" => args.print = true,
+            "--output-format" => args.output_format = value(&arg)?,
+            "--model" => args.model = Some(value(&arg)?),
+            "-r" | "--resume" => args.resume = Some(value(&arg)?),
+            "-c" | "--continue" | "--verbose" => {}
+            "-v" | "--version" => args.version = true,
+            other if other.starts_with('-') => {
+                return Err(format!("error: unknown option '{}'", other));
+            }
+            other => args.prompt = Some(other.to_string()),
+        }
+    }
+
+    if !matches!(args.output_format.as_str(), "text" | "json" | "stream-json") {
+        return Err(format!(
+            "error: invalid output format '{}'",
+            args.output_format
+        ));
+    }
+
+    Ok(args)
+}
+
+fn parse_directives(prompt: &str) -> Directives {
+    let mut directives = Directives {
+        fixture: env::var("MOCK_CLAUDE_FIXTURE").ok(),
+        delay_ms: env::var("MOCK_CLAUDE_DELAY_MS")
+            .ok()
+            .and_then(|v| v.parse().ok()),
+        error: env::var("MOCK_CLAUDE_ERROR").ok(),
+        ..Default::default()
+    };
+
+    let mut rest = prompt;
+    while let Some(start) = rest.find("[mock:") {
+        let Some(len) = rest[start..].find(']') else {
+            break;
+        };
+        let directive = &rest[start + 6..start + len];
+        rest = &rest[start + len + 1..];
+
+        let (key, value) = directive.split_once('=').unwrap_or((directive, ""));
+        match key.trim() {
+            "fixture" => directives.fixture = Some(value.trim().to_string()),
+            "delay" => directives.delay_ms = value.trim().parse().ok(),
+            "error" => directives.error = Some(value.trim().to_string()),
+            "fail-first" => directives.fail_first = value.trim().parse().ok(),
+            "crash" => directives.crash = true,
+            "hang" => directives.hang = true,
+            _ => {}
+        }
+    }
+
+    directives
+}
+
+/// Count runs of a prompt across processes so `fail-first` can stop failing
+fn run_number(prompt: &str) -> u32 {
+    let mut hasher = DefaultHasher::new();
+    prompt.hash(&mut hasher);
+    let path = env::temp_dir().join(format!("mock-claude-{:x}.count", hasher.finish()));
+
+    let count = fs::read_to_string(&path)
+        .ok()
+        .and_then(|c| c.trim().parse::<u32>().ok())
+        .unwrap_or(0)
+        + 1;
+    let _ = fs::write(&path, count.to_string());
+    count
+}
+
+fn default_fixture(prompt: &str, model: &str, session_id: &str) -> Vec<Value> {
+    let reply = format!("Mock reply to: {}", prompt);
+    vec![
+        json!({
+            "type": "system",
+            "subtype": "init",
+            "session_id": session_id,
+            "model": model,
+            "tools": [],
+        }),
+        json!({
+            "type": "assistant",
+            "session_id": session_id,
+            "message": {
+                "id": "msg_mock",
+                "role": "assistant",
+                "model": model,
+                "content": [{ "type": "text", "text": reply }],
+                "usage": { "input_tokens": 12, "output_tokens": 8 },
+            },
+        }),
+        json!({
+            "type": "result",
+            "subtype": "success",
+            "is_error": false,
+            "duration_ms": 50,
+            "num_turns": 1,
+            "result": reply,
+            "session_id": session_id,
+            "total_cost_usd": 0.0012,
+            "usage": {
+                "input_tokens": 12,
+                "output_tokens": 8,
+                "cache_read_input_tokens": 0,
+                "cache_creation_input_tokens": 0,
+            },
+        }),
+    ]
+}
+
+fn load_fixture(path: &str) -> Result<Vec<Value>, String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read fixture {}: {}", path, e))?;
+
+    content
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| serde_json::from_str(l).map_err(|e| format!("Invalid fixture line: {}", e)))
+        .collect()
+}
+
+fn error_result(kind: &str, session_id: &str) -> (Value, String) {
+    let message = match kind {
+        "overloaded" => {
+            r#"API Error: 529 {"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#
+        }
+        "rate_limit" => {
+            r#"API Error: 429 {"type":"error","error":{"type":"rate_limit_error","message":"Rate limit exceeded"}}"#
+        }
+        "auth" => "Invalid API key · Please run /login",
+        _ => "API Error: mock failure",
+    };
+
+    let result = json!({
+        "type": "result",
+        "subtype": "error_during_execution",
+        "is_error": true,
+        "duration_ms": 10,
+        "num_turns": 0,
+        "result": message,
+        "session_id": session_id,
+        "total_cost_usd": 0.0,
+    });
+    (result, message.to_string())
+}
+
+fn emit(line: &Value) {
+    let mut stdout = std::io::stdout().lock();
+    let _ = writeln!(stdout, "{}", line);
+    let _ = stdout.flush();
+}
+
+fn run() -> Result<i32, String> {
+    let args = parse_args()?;
+
+    if args.version {
+        println!("1.0.0 (Mock Claude Code)");
+        return Ok(0);
+    }
+
+    if !args.print {
+        return Err("error: interactive mode is not supported by the mock; pass -p".to_string());
+    }
+    let prompt = args.prompt.clone().ok_or("error: no prompt given")?;
+    let directives = parse_directives(&prompt);
+    let session_id = args
+        .resume
+        .clone()
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let model = args
+        .model
+        .clone()
+        .unwrap_or_else(|| "claude-mock".to_string());
+    let delay = directives.delay_ms.unwrap_or(DEFAULT_DELAY_MS);
+
+    let failing = directives.error.as_deref().filter(|_| {
+        directives
+            .fail_first
+            .map_or(true, |n| run_number(&prompt) <= n)
+    });
+
+    let mut lines = match &directives.fixture {
+        Some(path) => load_fixture(path)?,
+        None => default_fixture(&prompt, &model, &session_id),
+    };
+
+    if let Some(kind) = failing {
+        let (result, message) = error_result(kind, &session_id);
+        lines.truncate(1);
+        lines.push(result);
+        eprintln!("{}", message);
+    }
+
+    let streaming = args.output_format == "stream-json";
+    let crash_at = directives.crash.then_some(lines.len() / 2);
+
+    for (index, mut line) in lines.iter().cloned().enumerate() {
+        if crash_at == Some(index) {
+            eprintln!("mock-claude: simulated crash");
+            return Ok(2);
+        }
+
+        let line_delay = line
+            .as_object_mut()
+            .and_then(|o| o.remove("_delay_ms"))
+            .and_then(|v| v.as_u64())
+            .unwrap_or(delay);
+        thread::sleep(Duration::from_millis(line_delay));
+
+        let is_result = line.get("type").and_then(|v| v.as_str()) == Some("result");
+        if streaming {
+            emit(&line);
+        } else if is_result {
+            if args.output_format == "json" {
+                emit(&line);
+            } else if let Some(text) = line.get("result").and_then(|v| v.as_str()) {
+                println!("{}", text);
+            }
+        }
+
+        if directives.hang {
+            loop {
+                thread::sleep(Duration::from_secs(60));
+            }
+        }
+    }
+
+    Ok(if failing.is_some() { 1 } else { 0 })
+}
+
+fn main() {
+    match run() {
+        Ok(code) => process::exit(code),
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,321 @@
+//! Safe config writes
+//!
+//! Config files are written through a temp file that is synced and renamed
+//! over the original, so a crash never leaves a truncated file. The previous
+//! version is copied to a rotating backup under `~/.codepod/backups` first.
+//! Callers can pass the version they read to reject the write if the file
+//! changed in the meantime, e.g. because the Claude CLI rewrote it.
+
+use super::jobs::now_millis;
+use super::{codepod_dir, CommandResult};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tauri::command;
+
+/// Backups kept per file
+const MAX_BACKUPS: usize = 10;
+
+/// Version reported for a file that does not exist
+pub const MISSING_VERSION: &str = "missing";
+
+// Serializes check-and-write sequences
+lazy_static::lazy_static! {
+    static ref WRITE_LOCK: Mutex<()> = Mutex::new(());
+}
+
+/// A saved copy of a config file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigBackup {
+    pub id: String,
+    /// File the backup was taken from
+    pub path: String,
+    pub created_at: u64,
+    pub size: u64,
+    pub version: String,
+}
+
+/// File content together with the version to pass back when writing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionedContent {
+    pub content: String,
+    pub version: String,
+}
+
+/// Version of some file content
+pub fn content_version(content: &[u8]) -> String {
+    Sha256::digest(content)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Current version of a file, or `MISSING_VERSION`
+pub fn file_version(path: &Path) -> Result<String, String> {
+    match fs::read(path) {
+        Ok(content) => Ok(content_version(&content)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(MISSING_VERSION.to_string()),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+/// Backup directory for one config file
+fn backup_dir(path: &Path) -> PathBuf {
+    let key = content_version(path.to_string_lossy().as_bytes());
+    codepod_dir().join("backups").join(&key[..16])
+}
+
+/// Follow a symlinked file, e.g. a config kept in a dotfiles repo
+fn resolve_link(path: &Path) -> std::io::Result<PathBuf> {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_symlink() => fs::canonicalize(path),
+        _ => Ok(path.to_path_buf()),
+    }
+}
+
+/// Set unix permission bits; backups may hold API keys
+#[cfg(unix)]
+fn restrict(path: &Path, mode: u32) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+}
+
+#[cfg(not(unix))]
+fn restrict(_path: &Path, _mode: u32) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// Replace a file's content via a synced temp file and a rename
+///
+/// Symlinks are resolved first so the link is kept and its target updated.
+pub fn write_file_atomic(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    let path = &resolve_link(path)?;
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    fs::create_dir_all(dir)?;
+
+    let name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("config");
+    let temp = dir.join(format!(".{}.{}.tmp", name, uuid::Uuid::new_v4()));
+
+    let result = (|| {
+        let mut file = File::create(&temp)?;
+        file.write_all(content)?;
+        if let Ok(metadata) = fs::metadata(path) {
+            file.set_permissions(metadata.permissions())?;
+        }
+        file.sync_all()?;
+        fs::rename(&temp, path)
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result?;
+
+    // Persist the rename itself
+    #[cfg(unix)]
+    if let Ok(dir) = File::open(dir) {
+        let _ = dir.sync_all();
+    }
+    Ok(())
+}
+
+fn list_backups(path: &Path) -> Vec<ConfigBackup> {
+    let dir = backup_dir(path);
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return vec![];
+    };
+
+    let mut backups: Vec<ConfigBackup> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let file = entry.path();
+            let stem = file
+                .file_name()?
+                .to_str()?
+                .strip_suffix(".bak")?
+                .to_string();
+            let created_at = stem.parse().ok()?;
+            let content = fs::read(&file).ok()?;
+            Some(ConfigBackup {
+                id: stem,
+                path: path.to_string_lossy().to_string(),
+                created_at,
+                size: content.len() as u64,
+                version: content_version(&content),
+            })
+        })
+        .collect();
+
+    backups.sort_by_key(|b| std::cmp::Reverse(b.created_at));
+    backups
+}
+
+/// Copy the current file into its backup directory and prune old backups
+///
+/// Nothing is copied when the file is missing or matches the newest backup.
+pub(crate) fn backup_file(path: &Path) -> Result<(), String> {
+    let content = match fs::read(path) {
+        Ok(c) => c,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+
+    let backups = list_backups(path);
+    if backups
+        .first()
+        .is_some_and(|b| b.version == content_version(&content))
+    {
+        return Ok(());
+    }
+
+    let dir = backup_dir(path);
+    fs::create_dir_all(&dir)
+        .and_then(|()| restrict(&codepod_dir().join("backups"), 0o700))
+        .and_then(|()| restrict(&dir, 0o700))
+        .map_err(|e| format!("Failed to create backup directory: {}", e))?;
+
+    // Keep ids unique and ordered even for writes within one millisecond
+    let created_at = now_millis().max(backups.first().map_or(0, |b| b.created_at + 1));
+    let id = created_at.to_string();
+    let backup = dir.join(format!("{}.bak", id));
+    write_file_atomic(&backup, &content)
+        .and_then(|()| restrict(&backup, 0o600))
+        .map_err(|e| format!("Failed to back up {}: {}", path.display(), e))?;
+
+    for old in backups.iter().skip(MAX_BACKUPS - 1) {
+        let _ = fs::remove_file(dir.join(format!("{}.bak", old.id)));
+    }
+    Ok(())
+}
+
+/// Back up and atomically replace a config file
+///
+/// With `expected_version`, the write is rejected if the file no longer has
+/// that version. Returns the version of the new content.
+pub fn safe_write(
+    path: &Path,
+    content: &[u8],
+    expected_version: Option<&str>,
+) -> Result<String, String> {
+    let _guard = WRITE_LOCK.lock();
+
+    if let Some(expected) = expected_version {
+        let current = file_version(path)?;
+        if current != expected {
+            return Err(format!(
+                "{} was changed by another program since it was read; reload and try again",
+                path.display()
+            ));
+        }
+    }
+
+    backup_file(path)?;
+    write_file_atomic(path, content)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(content_version(content))
+}
+
+/// List the backups of a config file, newest first
+#[command]
+pub async fn list_config_backups(path: String) -> CommandResult<Vec<ConfigBackup>> {
+    CommandResult::ok(list_backups(&super::config::expand_path(&path)))
+}
+
+/// Restore a config file from a backup, or from the newest one
+///
+/// The current content is backed up first, so a restore can be undone.
+/// Returns the version of the restored content.
+#[command]
+pub async fn restore_config_backup(
+    path: String,
+    backup_id: Option<String>,
+) -> CommandResult<String> {
+    let expanded = super::config::expand_path(&path);
+    let backups = list_backups(&expanded);
+
+    let backup = match &backup_id {
+        Some(id) => backups.iter().find(|b| &b.id == id),
+        None => backups.first(),
+    };
+    let Some(backup) = backup else {
+        return CommandResult::err(format!("No backup found for {}", path));
+    };
+
+    let content = match fs::read(backup_dir(&expanded).join(format!("{}.bak", backup.id))) {
+        Ok(c) => c,
+        Err(e) => return CommandResult::err(format!("Failed to read backup: {}", e)),
+    };
+
+    match safe_write(&expanded, &content, None) {
+        Ok(version) => CommandResult::ok(version),
+        Err(e) => CommandResult::err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch() -> PathBuf {
+        std::env::temp_dir().join(format!("codepod-backups-{}", uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn test_write_file_atomic_replaces_content() {
+        let dir = scratch();
+        let path = dir.join("settings.json");
+
+        write_file_atomic(&path, b"{}").unwrap();
+        write_file_atomic(&path, b"{\"model\":\"opus\"}").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{\"model\":\"opus\"}");
+
+        // No temp files are left behind
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_file_atomic_keeps_symlink() {
+        let dir = scratch();
+        let target = dir.join("dotfiles").join("settings.json");
+        let link = dir.join("settings.json");
+        fs::create_dir_all(target.parent().unwrap()).unwrap();
+        fs::write(&target, b"{}").unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        write_file_atomic(&link, b"{\"model\":\"opus\"}").unwrap();
+        assert!(fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "{\"model\":\"opus\"}");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_safe_write_rejects_stale_version() {
+        let dir = scratch();
+        let path = dir.join("settings.json");
+
+        let v1 = safe_write(&path, b"one", Some(MISSING_VERSION)).unwrap();
+        assert_eq!(v1, file_version(&path).unwrap());
+
+        // Someone else rewrites the file
+        fs::write(&path, b"other").unwrap();
+        assert!(safe_write(&path, b"two", Some(&v1)).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "other");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
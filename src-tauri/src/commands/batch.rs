@@ -0,0 +1,326 @@
+//! Fan-out batch runs
+//!
+//! Runs the same prompt with `claude -p` across several project directories
+//! with bounded parallelism. Each repository goes through the job queue, and
+//! progress is emitted as `claude-batch-{batch_id}` events.
+
+use super::claude::{self, ClaudeOptions};
+use super::git::{self, GitStatus};
+use super::jobs::{self, JobState};
+use super::CommandResult;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+use tauri::{command, AppHandle, Emitter};
+use tokio::sync::{Notify, Semaphore};
+use tokio::task::JoinSet;
+
+/// Parallelism used when the caller does not specify one
+const DEFAULT_PARALLELISM: usize = 2;
+
+/// Cancellation signal shared by every repository of a batch
+#[derive(Default)]
+struct BatchControl {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+impl BatchControl {
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    async fn cancelled(&self) {
+        let notified = self.notify.notified();
+        tokio::pin!(notified);
+        notified.as_mut().enable();
+        if self.is_cancelled() {
+            return;
+        }
+        notified.await;
+    }
+}
+
+// Batches currently running
+lazy_static::lazy_static! {
+    static ref BATCHES: Arc<Mutex<HashMap<String, Arc<BatchControl>>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RepoRunStatus {
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+/// Outcome of the prompt in one repository
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RepoReport {
+    pub project: String,
+    pub status: RepoRunStatus,
+    /// Final result text reported by Claude
+    pub result: Option<String>,
+    pub cost_usd: Option<f64>,
+    pub exit_code: Option<i32>,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+    /// Repository status after the run
+    pub git_status: Option<GitStatus>,
+}
+
+/// Report for a whole batch, in the order the projects were given
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatchReport {
+    pub batch_id: String,
+    pub cancelled: bool,
+    pub total_cost_usd: f64,
+    pub reports: Vec<RepoReport>,
+}
+
+fn cancelled_report(project: &str, started: Instant) -> RepoReport {
+    RepoReport {
+        project: project.to_string(),
+        status: RepoRunStatus::Cancelled,
+        result: None,
+        cost_usd: None,
+        exit_code: None,
+        error: Some("Batch cancelled".to_string()),
+        duration_ms: started.elapsed().as_millis() as u64,
+        git_status: None,
+    }
+}
+
+/// Run the prompt in one repository, honouring batch cancellation
+async fn run_repo(
+    app: AppHandle,
+    job_id: String,
+    project: String,
+    prompt: Arc<String>,
+    mut options: ClaudeOptions,
+    semaphore: Arc<Semaphore>,
+    control: Arc<BatchControl>,
+) -> RepoReport {
+    let started = Instant::now();
+    options.cwd = Some(project.clone());
+    options.continue_session = false;
+    options.session_id = None;
+
+    let _permit = tokio::select! {
+        permit = semaphore.acquire_owned() => match permit {
+            Ok(p) => p,
+            Err(_) => return cancelled_report(&project, started),
+        },
+        _ = control.cancelled() => return cancelled_report(&project, started),
+    };
+
+    let ticket = match jobs::enqueue(&app, &job_id, Some(project.clone()), options.priority) {
+        Ok(t) => t,
+        Err(e) => {
+            return RepoReport {
+                status: RepoRunStatus::Failed,
+                error: Some(e),
+                ..cancelled_report(&project, started)
+            }
+        }
+    };
+
+    // Dropping the ticket or the invocation future cancels the queued job or
+    // kills the running CLI process
+    let job = tokio::select! {
+        job = ticket.wait() => match job {
+            Ok(j) => j,
+            Err(_) => return cancelled_report(&project, started),
+        },
+        _ = control.cancelled() => return cancelled_report(&project, started),
+    };
+
+    let result = tokio::select! {
+        result = claude::execute_claude(&prompt, &options) => result,
+        _ = job.cancelled() => {
+            // Cancelled on its own, e.g. by a budget running out
+            let message = job.cancel_message();
+            job.finish(JobState::Cancelled, Some(message.clone()));
+            return RepoReport {
+                error: Some(message),
+                ..cancelled_report(&project, started)
+            };
+        }
+        _ = control.cancelled() => {
+            job.finish(JobState::Cancelled, None);
+            return cancelled_report(&project, started);
+        }
+    };
+
+    claude::enforce_after_invocation(&app, &job, &options, &result);
+    let state = if result.success {
+        JobState::Completed
+    } else {
+        JobState::Failed
+    };
+    job.finish(state, result.error.clone());
+
+    let invocation = result.data.unwrap_or_default();
+    let output: Option<serde_json::Value> = invocation
+        .output
+        .as_deref()
+        .and_then(|o| serde_json::from_str(o.trim()).ok());
+
+    RepoReport {
+        project: project.clone(),
+        status: if result.success {
+            RepoRunStatus::Succeeded
+        } else {
+            RepoRunStatus::Failed
+        },
+        result: output
+            .as_ref()
+            .and_then(|o| o.get("result"))
+            .and_then(|v| v.as_str())
+            .map(String::from),
+        cost_usd: output
+            .as_ref()
+            .and_then(|o| o.get("total_cost_usd"))
+            .and_then(|v| v.as_f64()),
+        exit_code: invocation.attempts.last().and_then(|a| a.exit_code),
+        error: result.error,
+        duration_ms: started.elapsed().as_millis() as u64,
+        git_status: tokio::task::spawn_blocking(move || git::get_git_status(project).data)
+            .await
+            .ok()
+            .flatten(),
+    }
+}
+
+/// Run a prompt in every project directory and report per repository
+///
+/// Resolves once every repository has finished or the batch was cancelled.
+#[command]
+pub async fn run_claude_batch(
+    app: AppHandle,
+    batch_id: String,
+    prompt: String,
+    projects: Vec<String>,
+    options: Option<ClaudeOptions>,
+    parallelism: Option<usize>,
+) -> CommandResult<BatchReport> {
+    if projects.is_empty() {
+        return CommandResult::err("No projects given");
+    }
+
+    let control = Arc::new(BatchControl::default());
+    {
+        let mut batches = BATCHES.lock();
+        if batches.contains_key(&batch_id) {
+            return CommandResult::err(format!("Batch already running: {}", batch_id));
+        }
+        batches.insert(batch_id.clone(), control.clone());
+    }
+
+    let options = options.unwrap_or_default();
+    let prompt = Arc::new(prompt);
+    let semaphore = Arc::new(Semaphore::new(
+        parallelism.unwrap_or(DEFAULT_PARALLELISM).max(1),
+    ));
+    let event = format!("claude-batch-{}", batch_id);
+
+    let mut set = JoinSet::new();
+    for (index, project) in projects.iter().enumerate() {
+        let run = run_repo(
+            app.clone(),
+            format!("{}:{}", batch_id, index),
+            project.clone(),
+            prompt.clone(),
+            options.clone(),
+            semaphore.clone(),
+            control.clone(),
+        );
+        set.spawn(async move { (index, run.await) });
+    }
+
+    let mut reports: Vec<Option<RepoReport>> = projects.iter().map(|_| None).collect();
+    while let Some(joined) = set.join_next().await {
+        match joined {
+            Ok((index, report)) => {
+                let _ = app.emit(&event, &report);
+                reports[index] = Some(report);
+            }
+            Err(e) => log::error!("Batch task failed: {}", e),
+        }
+    }
+
+    BATCHES.lock().remove(&batch_id);
+
+    let reports: Vec<RepoReport> = reports
+        .into_iter()
+        .zip(&projects)
+        .map(|(report, project)| {
+            report.unwrap_or_else(|| RepoReport {
+                status: RepoRunStatus::Failed,
+                error: Some("Batch task panicked".to_string()),
+                ..cancelled_report(project, Instant::now())
+            })
+        })
+        .collect();
+
+    CommandResult::ok(BatchReport {
+        batch_id,
+        cancelled: control.is_cancelled(),
+        total_cost_usd: reports.iter().filter_map(|r| r.cost_usd).sum(),
+        reports,
+    })
+}
+
+/// Cancel a running batch, killing any in-flight Claude processes
+#[command]
+pub async fn cancel_claude_batch(batch_id: String) -> CommandResult<()> {
+    match BATCHES.lock().get(&batch_id) {
+        Some(control) => {
+            control.cancel();
+            CommandResult::ok(())
+        }
+        None => CommandResult::err(format!("Batch not found: {}", batch_id)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_batch_control_wakes_all_waiters() {
+        let control = Arc::new(BatchControl::default());
+
+        let waiters: Vec<_> = (0..3)
+            .map(|_| {
+                let c = control.clone();
+                tokio::spawn(async move { c.cancelled().await })
+            })
+            .collect();
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        control.cancel();
+
+        for waiter in waiters {
+            tokio::time::timeout(Duration::from_secs(1), waiter)
+                .await
+                .expect("waiter woke up")
+                .unwrap();
+        }
+
+        // Late waiters return immediately
+        tokio::time::timeout(Duration::from_secs(1), control.cancelled())
+            .await
+            .expect("late waiter returned");
+    }
+}
@@ -0,0 +1,376 @@
+//! Spend budgets
+//!
+//! Budgets are checked against the usage ledger before a Claude run starts
+//! and again whenever a run reports its cost. Crossing a soft limit emits a
+//! `claude-budget` warning; reaching a hard limit refuses new runs and
+//! cancels running jobs in the same scope.
+
+use super::jobs::{self, JobInfo};
+use super::usage::{self, UsageFilter};
+use super::{codepod_dir, CommandResult};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use tauri::{command, AppHandle, Emitter};
+
+/// Soft and hard spend limits in USD
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BudgetLimit {
+    pub soft_usd: Option<f64>,
+    pub hard_usd: Option<f64>,
+}
+
+/// Budget configuration persisted in `~/.codepod/budgets.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BudgetSettings {
+    /// Monthly limits keyed by project path
+    pub projects: HashMap<String, BudgetLimit>,
+    /// Limit on total spend per calendar day
+    pub daily: Option<BudgetLimit>,
+    /// Limit on spend within a single session
+    pub session: Option<BudgetLimit>,
+}
+
+/// Scope a budget applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BudgetScope {
+    Project,
+    Daily,
+    Session,
+}
+
+/// How close spend is to a budget's limits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BudgetLevel {
+    Ok,
+    Warning,
+    Exceeded,
+}
+
+/// Current spend against one configured budget
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BudgetStatus {
+    pub scope: BudgetScope,
+    /// Project path or session id, depending on scope
+    pub key: Option<String>,
+    pub spent_usd: f64,
+    pub soft_usd: Option<f64>,
+    pub hard_usd: Option<f64>,
+    pub level: BudgetLevel,
+}
+
+fn budgets_path() -> PathBuf {
+    codepod_dir().join("budgets.json")
+}
+
+fn load_settings() -> Result<BudgetSettings, String> {
+    match fs::read_to_string(budgets_path()) {
+        Ok(content) => {
+            serde_json::from_str(&content).map_err(|e| format!("Invalid budget settings: {}", e))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BudgetSettings::default()),
+        Err(e) => Err(format!("Failed to read budget settings: {}", e)),
+    }
+}
+
+fn level_for(spent: f64, limit: &BudgetLimit) -> BudgetLevel {
+    if limit.hard_usd.is_some_and(|hard| spent >= hard) {
+        BudgetLevel::Exceeded
+    } else if limit.soft_usd.is_some_and(|soft| spent >= soft) {
+        BudgetLevel::Warning
+    } else {
+        BudgetLevel::Ok
+    }
+}
+
+fn status_for(
+    scope: BudgetScope,
+    key: Option<&str>,
+    limit: &BudgetLimit,
+    filter: UsageFilter,
+) -> Result<BudgetStatus, String> {
+    let spent = usage::usage_totals(&filter)
+        .map_err(|e| format!("Failed to read usage ledger: {}", e))?
+        .cost_usd;
+
+    Ok(BudgetStatus {
+        scope,
+        key: key.map(String::from),
+        spent_usd: spent,
+        soft_usd: limit.soft_usd,
+        hard_usd: limit.hard_usd,
+        level: level_for(spent, limit),
+    })
+}
+
+/// Evaluate every budget that applies to a project and session
+pub fn budget_status(
+    project: Option<&str>,
+    session_id: Option<&str>,
+) -> Result<Vec<BudgetStatus>, String> {
+    let settings = load_settings()?;
+    let now = chrono::Local::now();
+    let today = now.format("%Y-%m-%d").to_string();
+    let month_start = now.format("%Y-%m-01").to_string();
+    let mut statuses = Vec::new();
+
+    if let Some(limit) = project.and_then(|p| settings.projects.get(p)) {
+        let filter = UsageFilter {
+            from_day: Some(month_start),
+            project: project.map(String::from),
+            ..Default::default()
+        };
+        statuses.push(status_for(BudgetScope::Project, project, limit, filter)?);
+    }
+
+    if let Some(limit) = &settings.daily {
+        let filter = UsageFilter {
+            from_day: Some(today.clone()),
+            to_day: Some(today),
+            ..Default::default()
+        };
+        statuses.push(status_for(BudgetScope::Daily, None, limit, filter)?);
+    }
+
+    if let (Some(limit), Some(session)) = (&settings.session, session_id) {
+        let filter = UsageFilter {
+            session_id: Some(session.to_string()),
+            ..Default::default()
+        };
+        statuses.push(status_for(
+            BudgetScope::Session,
+            Some(session),
+            limit,
+            filter,
+        )?);
+    }
+
+    Ok(statuses)
+}
+
+fn emit_budget(app: &AppHandle, statuses: &[BudgetStatus]) {
+    for status in statuses.iter().filter(|s| s.level != BudgetLevel::Ok) {
+        let _ = app.emit("claude-budget", status);
+    }
+}
+
+fn exceeded_message(status: &BudgetStatus) -> String {
+    let scope = match status.scope {
+        BudgetScope::Project => "Project",
+        BudgetScope::Daily => "Daily",
+        BudgetScope::Session => "Session",
+    };
+    format!(
+        "{} budget exceeded: spent ${:.2} of ${:.2}",
+        scope,
+        status.spent_usd,
+        status.hard_usd.unwrap_or_default()
+    )
+}
+
+/// Check budgets before starting a run
+///
+/// Emits warnings when an app handle is available and refuses the run when
+/// any hard limit has been reached. Budgets that can't be evaluated, e.g.
+/// because `budgets.json` is corrupt, are logged and don't block the run.
+pub fn check_before_start(
+    app: Option<&AppHandle>,
+    project: Option<&str>,
+    session_id: Option<&str>,
+) -> Result<(), String> {
+    let statuses = match budget_status(project, session_id) {
+        Ok(s) => s,
+        Err(e) => {
+            log::error!("Not enforcing budgets: {}", e);
+            return Ok(());
+        }
+    };
+    if let Some(app) = app {
+        emit_budget(app, &statuses);
+    }
+
+    match statuses.iter().find(|s| s.level == BudgetLevel::Exceeded) {
+        Some(status) => Err(exceeded_message(status)),
+        None => Ok(()),
+    }
+}
+
+/// Whether a running job counts against an exceeded budget
+fn in_scope(status: &BudgetStatus, job: &JobInfo, current_job: &str) -> bool {
+    match status.scope {
+        BudgetScope::Project => job.id != current_job && job.project == status.key,
+        BudgetScope::Daily => job.id != current_job,
+        BudgetScope::Session => status.key.is_some() && job.session_id == status.key,
+    }
+}
+
+/// Re-check budgets after a run reported its cost
+///
+/// Running jobs that fall into an exceeded scope are cancelled. For project
+/// and daily limits `current_job` is spared since its run already finished;
+/// an exceeded session budget also stops the session's own job.
+pub fn enforce_after_usage(
+    app: &AppHandle,
+    current_job: &str,
+    project: Option<&str>,
+    session_id: Option<&str>,
+) {
+    let statuses = match budget_status(project, session_id) {
+        Ok(s) => s,
+        Err(e) => {
+            log::error!("{}", e);
+            return;
+        }
+    };
+    emit_budget(app, &statuses);
+
+    for status in statuses.iter().filter(|s| s.level == BudgetLevel::Exceeded) {
+        let message = exceeded_message(status);
+        let cancelled = jobs::cancel_running(|job| in_scope(status, job, current_job), &message);
+        if !cancelled.is_empty() {
+            log::warn!("{}; cancelled jobs: {}", message, cancelled.join(", "));
+        }
+    }
+}
+
+/// Get the budget configuration
+#[command]
+pub async fn get_budget_settings() -> CommandResult<BudgetSettings> {
+    match load_settings() {
+        Ok(settings) => CommandResult::ok(settings),
+        Err(e) => CommandResult::err(e),
+    }
+}
+
+/// Replace the budget configuration
+#[command]
+pub async fn set_budget_settings(settings: BudgetSettings) -> CommandResult<()> {
+    let limits = settings
+        .projects
+        .values()
+        .chain(settings.daily.iter())
+        .chain(settings.session.iter());
+    for limit in limits {
+        if let (Some(soft), Some(hard)) = (limit.soft_usd, limit.hard_usd) {
+            if soft > hard {
+                return CommandResult::err("Soft limit must not exceed hard limit");
+            }
+        }
+    }
+
+    let path = budgets_path();
+    if let Some(parent) = path.parent() {
+        if let Err(e) = fs::create_dir_all(parent) {
+            return CommandResult::err(format!("Failed to create directory: {}", e));
+        }
+    }
+
+    let content = match serde_json::to_string_pretty(&settings) {
+        Ok(c) => c,
+        Err(e) => return CommandResult::err(format!("Failed to serialize budgets: {}", e)),
+    };
+
+    match fs::write(&path, content) {
+        Ok(()) => CommandResult::ok(()),
+        Err(e) => CommandResult::err(format!("Failed to write budget settings: {}", e)),
+    }
+}
+
+/// Get current spend against the budgets applying to a project and session
+#[command]
+pub async fn get_budget_status(
+    project: Option<String>,
+    session_id: Option<String>,
+) -> CommandResult<Vec<BudgetStatus>> {
+    match budget_status(project.as_deref(), session_id.as_deref()) {
+        Ok(statuses) => CommandResult::ok(statuses),
+        Err(e) => CommandResult::err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::jobs::JobState;
+
+    #[test]
+    fn test_level_for() {
+        let limit = BudgetLimit {
+            soft_usd: Some(5.0),
+            hard_usd: Some(10.0),
+        };
+        assert_eq!(level_for(1.0, &limit), BudgetLevel::Ok);
+        assert_eq!(level_for(5.0, &limit), BudgetLevel::Warning);
+        assert_eq!(level_for(12.0, &limit), BudgetLevel::Exceeded);
+
+        let soft_only = BudgetLimit {
+            soft_usd: Some(1.0),
+            hard_usd: None,
+        };
+        assert_eq!(level_for(100.0, &soft_only), BudgetLevel::Warning);
+    }
+
+    #[test]
+    fn test_budget_settings_deserialize_partial() {
+        let settings: BudgetSettings =
+            serde_json::from_str(r#"{"daily":{"soft_usd":1.5,"hard_usd":3}}"#).unwrap();
+        assert!(settings.projects.is_empty());
+        assert!(settings.session.is_none());
+        assert_eq!(settings.daily.unwrap().hard_usd, Some(3.0));
+    }
+
+    #[test]
+    fn test_exceeded_message() {
+        let status = BudgetStatus {
+            scope: BudgetScope::Project,
+            key: Some("/repo".to_string()),
+            spent_usd: 12.345,
+            soft_usd: None,
+            hard_usd: Some(10.0),
+            level: BudgetLevel::Exceeded,
+        };
+        assert_eq!(
+            exceeded_message(&status),
+            "Project budget exceeded: spent $12.35 of $10.00"
+        );
+    }
+
+    #[test]
+    fn test_in_scope_session_matches_own_job() {
+        let status = BudgetStatus {
+            scope: BudgetScope::Session,
+            key: Some("abc".to_string()),
+            spent_usd: 3.0,
+            soft_usd: None,
+            hard_usd: Some(2.0),
+            level: BudgetLevel::Exceeded,
+        };
+        let job = |id: &str, session: Option<&str>| JobInfo {
+            id: id.to_string(),
+            project: Some("/repo".to_string()),
+            session_id: session.map(String::from),
+            priority: 0,
+            state: JobState::Running,
+            queued_at: 0,
+            started_at: None,
+            finished_at: None,
+            error: None,
+        };
+
+        assert!(in_scope(&status, &job("a", Some("abc")), "a"));
+        assert!(!in_scope(&status, &job("b", Some("xyz")), "a"));
+        assert!(!in_scope(&status, &job("c", None), "a"));
+
+        let project = BudgetStatus {
+            scope: BudgetScope::Project,
+            key: Some("/repo".to_string()),
+            ..status
+        };
+        assert!(!in_scope(&project, &job("a", Some("abc")), "a"));
+        assert!(in_scope(&project, &job("b", None), "a"));
+    }
+}
@@ -0,0 +1,1189 @@
+//! Config bundles for onboarding
+//!
+//! A bundle is a single JSON file holding selected commands, agents, skills,
+//! MCP servers and permission rules. Every env value and header of an MCP
+//! server is replaced by a `${VAR}` placeholder on export, so each person
+//! supplies their own through the environment; args and URLs are kept but
+//! reported for review. An import is previewed for conflicts first and then
+//! written completely or not at all.
+
+use super::backups;
+use super::definitions::{
+    definition_path, definition_root, locate, scope_dir, validate_name, DefinitionKind,
+    DefinitionScope,
+};
+use super::frontmatter::parse_frontmatter;
+use super::jobs::now_millis;
+use super::mcp::{
+    object_at, read_json_versioned, read_servers, server_state, servers_location, validate_server,
+    validate_server_name, McpServerConfig,
+};
+use super::settings::{self, Permissions, SettingsScope};
+use super::skills::SKIPPED_DIRS;
+use super::CommandResult;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use tauri::command;
+
+const BUNDLE_FORMAT: &str = "codepod-config-bundle";
+/// Newest bundle version this build reads and the one it writes
+const BUNDLE_VERSION: u32 = 1;
+/// Permission rule lists carried by bundles
+const RULE_LISTS: [&str; 3] = ["allow", "deny", "ask"];
+
+/// A command, agent or skill in a bundle
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundledDefinition {
+    pub kind: DefinitionKind,
+    pub name: String,
+    /// Markdown with frontmatter; `SKILL.md` for skills
+    pub content: String,
+    /// Other files of a skill, by `/`-separated path inside its folder
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub files: BTreeMap<String, String>,
+}
+
+/// A placeholder put in place of a secret on export
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleVariable {
+    pub name: String,
+    pub server: String,
+    /// `env.<NAME>` or `headers.<Name>`
+    pub field: String,
+}
+
+/// An MCP server arg or URL exported as it is
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UntemplatedField {
+    pub server: String,
+    /// `args.<index>` or `url`
+    pub field: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigBundle {
+    pub format: String,
+    pub version: u32,
+    pub created_at: u64,
+    #[serde(default)]
+    pub definitions: Vec<BundledDefinition>,
+    #[serde(default)]
+    pub mcp_servers: BTreeMap<String, McpServerConfig>,
+    /// Only the `allow`, `deny` and `ask` rules are carried
+    #[serde(default)]
+    pub permissions: Permissions,
+    #[serde(default)]
+    pub variables: Vec<BundleVariable>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DefinitionRef {
+    pub kind: DefinitionKind,
+    pub scope: DefinitionScope,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct McpServerRef {
+    pub scope: SettingsScope,
+    pub name: String,
+}
+
+/// Items to put in a bundle
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct BundleSelection {
+    #[serde(default)]
+    pub definitions: Vec<DefinitionRef>,
+    #[serde(default)]
+    pub mcp_servers: Vec<McpServerRef>,
+    /// Scopes whose permission rules are merged into the bundle
+    #[serde(default)]
+    pub permission_scopes: Vec<SettingsScope>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BundleExport {
+    pub path: String,
+    pub definitions: usize,
+    pub mcp_servers: usize,
+    pub permission_rules: usize,
+    pub variables: Vec<BundleVariable>,
+    /// Args and URLs that may still hold secrets
+    pub untemplated: Vec<UntemplatedField>,
+    /// Files left out of the bundle
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportItemKind {
+    Command,
+    Agent,
+    Skill,
+    McpServer,
+    Permission,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportStatus {
+    /// Not there yet
+    New,
+    /// Already there exactly as in the bundle; never written
+    Identical,
+    /// There with different content; written only when chosen
+    Conflict,
+}
+
+/// One item of a bundle against the target scope
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ImportItem {
+    /// Identifies the item when choosing which conflicts to overwrite
+    pub key: String,
+    pub kind: ImportItemKind,
+    pub name: String,
+    pub status: ImportStatus,
+    /// File the item is written to
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportPreview {
+    pub items: Vec<ImportItem>,
+    pub variables: Vec<BundleVariable>,
+    /// Placeholder variables not set in CodePod's environment
+    pub missing_variables: Vec<String>,
+}
+
+/// A file's text; `None` when it is not UTF-8
+fn read_text_opt(path: &Path) -> Result<Option<String>, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Ok(String::from_utf8(bytes).ok())
+}
+
+fn read_text(path: &Path) -> Result<String, String> {
+    read_text_opt(path)?.ok_or_else(|| {
+        format!(
+            "{} is not a text file; bundles only hold text",
+            path.display()
+        )
+    })
+}
+
+/// Text files below `dir`, keyed by `/`-separated path relative to `root`
+///
+/// Binary files can't be bundled; their keys go to `skipped`. Folders such as
+/// `.git` and `node_modules` and symlinked folders are left out.
+fn collect_files(
+    root: &Path,
+    dir: &Path,
+    files: &mut BTreeMap<String, String>,
+    skipped: &mut Vec<String>,
+) -> Result<(), String> {
+    let entries =
+        fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            let name = entry.file_name();
+            if !SKIPPED_DIRS.contains(&name.to_string_lossy().as_ref()) {
+                collect_files(root, &path, files, skipped)?;
+            }
+            continue;
+        }
+        // Following a symlinked folder could leave the skill or loop forever
+        if file_type.is_symlink() && path.is_dir() {
+            continue;
+        }
+        let relative = path.strip_prefix(root).unwrap_or(&path);
+        let key: Vec<String> = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        match read_text_opt(&path)? {
+            Some(text) => {
+                files.insert(key.join("/"), text);
+            }
+            None => skipped.push(key.join("/")),
+        }
+    }
+    Ok(())
+}
+
+/// Whether a bundled skill file path stays inside the skill folder
+fn is_safe_relative(path: &str) -> bool {
+    !path.is_empty()
+        && Path::new(path)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+}
+
+/// A definition and the binary files of it left out
+fn read_definition(
+    reference: &DefinitionRef,
+    project_path: Option<&str>,
+) -> Result<(BundledDefinition, Vec<String>), String> {
+    validate_name(reference.kind, &reference.name)?;
+    let base = scope_dir(reference.scope, project_path)?;
+    let (path, _) = locate(&base, reference.kind, &reference.name)?;
+
+    let mut files = BTreeMap::new();
+    let mut skipped = Vec::new();
+    if reference.kind == DefinitionKind::Skill {
+        let root = definition_root(reference.kind, &path);
+        collect_files(&root, &root, &mut files, &mut skipped)?;
+        files.remove("SKILL.md");
+    }
+    let definition = BundledDefinition {
+        kind: reference.kind,
+        name: reference.name.clone(),
+        content: read_text(&path)?,
+        files,
+    };
+    Ok((definition, skipped))
+}
+
+/// Variable name for an env value or header, e.g. `GITHUB_AUTHORIZATION`
+///
+/// The server name keeps two servers' `API_KEY`s apart.
+fn variable_name(server: &str, key: &str) -> String {
+    format!("{}_{}", server, key)
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Replace env values and headers with placeholders
+///
+/// Any of them may be a secret whatever its name, e.g. `DATABASE_URL` with a
+/// password in it, so all are replaced. Values that already use placeholders
+/// are left alone. An auth scheme such as `Bearer` is kept in front of the
+/// placeholder.
+fn template_secrets(server: &str, config: &mut McpServerConfig) -> Vec<BundleVariable> {
+    let mut variables = Vec::new();
+
+    for (key, value) in config.env.iter_mut() {
+        if value.is_empty() || value.contains("${") {
+            continue;
+        }
+        let name = variable_name(server, key);
+        *value = format!("${{{}}}", name);
+        variables.push(BundleVariable {
+            name,
+            server: server.to_string(),
+            field: format!("env.{}", key),
+        });
+    }
+
+    for (header, value) in config.headers.iter_mut() {
+        if value.is_empty() || value.contains("${") {
+            continue;
+        }
+        let name = variable_name(server, header);
+        *value = match value.split_once(' ') {
+            Some((scheme, _))
+                if ["bearer", "basic", "token"].contains(&scheme.to_ascii_lowercase().as_str()) =>
+            {
+                format!("{} ${{{}}}", scheme, name)
+            }
+            _ => format!("${{{}}}", name),
+        };
+        variables.push(BundleVariable {
+            name,
+            server: server.to_string(),
+            field: format!("headers.{}", header),
+        });
+    }
+
+    variables
+}
+
+/// Args and the URL without placeholders, which are exported as they are
+fn untemplated_fields(server: &str, config: &McpServerConfig) -> Vec<UntemplatedField> {
+    let args = config
+        .args
+        .iter()
+        .enumerate()
+        .map(|(index, arg)| (format!("args.{}", index), arg));
+    args.chain(config.url.iter().map(|url| ("url".to_string(), url)))
+        .filter(|(_, value)| !value.is_empty() && !value.contains("${"))
+        .map(|(field, value)| UntemplatedField {
+            server: server.to_string(),
+            field,
+            value: value.clone(),
+        })
+        .collect()
+}
+
+fn read_server(
+    reference: &McpServerRef,
+    project_path: Option<&str>,
+) -> Result<McpServerConfig, String> {
+    let Some(enabled) = server_state(reference.scope, project_path, &reference.name)? else {
+        return Err(format!("MCP server '{}' does not exist", reference.name));
+    };
+    let (path, servers) = read_servers(reference.scope, project_path, enabled)?;
+    let value = servers.get(&reference.name).cloned().unwrap_or_default();
+    serde_json::from_value(value).map_err(|e| {
+        format!(
+            "Invalid MCP server '{}' in {}: {}",
+            reference.name,
+            path.display(),
+            e
+        )
+    })
+}
+
+/// Permission rules of a scope's settings
+fn read_permissions(
+    scope: SettingsScope,
+    project_path: Option<&str>,
+) -> Result<Permissions, String> {
+    let path = settings::settings_path(scope, project_path)?;
+    let settings = settings::read_settings(&path)?;
+    match settings.get("permissions") {
+        Some(value) => serde_json::from_value(value.clone())
+            .map_err(|e| format!("Invalid permissions in {}: {}", path.display(), e)),
+        None => Ok(Permissions::default()),
+    }
+}
+
+fn rule_list_mut<'a>(permissions: &'a mut Permissions, list: &str) -> &'a mut Vec<String> {
+    match list {
+        "deny" => &mut permissions.deny,
+        "ask" => &mut permissions.ask,
+        _ => &mut permissions.allow,
+    }
+}
+
+/// The bundle of a selection and warnings about files left out of it
+fn build_bundle(
+    selection: &BundleSelection,
+    project_path: Option<&str>,
+) -> Result<(ConfigBundle, Vec<String>), String> {
+    let mut bundle = ConfigBundle {
+        format: BUNDLE_FORMAT.to_string(),
+        version: BUNDLE_VERSION,
+        created_at: now_millis(),
+        definitions: Vec::new(),
+        mcp_servers: BTreeMap::new(),
+        permissions: Permissions::default(),
+        variables: Vec::new(),
+    };
+    let mut warnings = Vec::new();
+
+    for reference in &selection.definitions {
+        let (definition, skipped) = read_definition(reference, project_path)?;
+        warnings.extend(
+            skipped
+                .into_iter()
+                .map(|file| format!("Skipped {} of '{}': not a text file", file, definition.name)),
+        );
+        if bundle
+            .definitions
+            .iter()
+            .any(|d| d.kind == definition.kind && d.name == definition.name)
+        {
+            return Err(format!("'{}' is selected more than once", definition.name));
+        }
+        bundle.definitions.push(definition);
+    }
+
+    for reference in &selection.mcp_servers {
+        if bundle.mcp_servers.contains_key(&reference.name) {
+            return Err(format!(
+                "MCP server '{}' is selected more than once",
+                reference.name
+            ));
+        }
+        let mut config = read_server(reference, project_path)?;
+        bundle
+            .variables
+            .extend(template_secrets(&reference.name, &mut config));
+        bundle.mcp_servers.insert(reference.name.clone(), config);
+    }
+
+    for scope in &selection.permission_scopes {
+        let mut permissions = read_permissions(*scope, project_path)?;
+        for list in RULE_LISTS {
+            let rules = std::mem::take(rule_list_mut(&mut permissions, list));
+            let bundled = rule_list_mut(&mut bundle.permissions, list);
+            for rule in rules {
+                if !bundled.contains(&rule) {
+                    bundled.push(rule);
+                }
+            }
+        }
+    }
+
+    Ok((bundle, warnings))
+}
+
+/// Check a bundle read from disk before anything is compared or written
+fn validate_bundle(bundle: &ConfigBundle) -> Result<(), String> {
+    if bundle.format != BUNDLE_FORMAT {
+        return Err("Not a CodePod config bundle".to_string());
+    }
+    if bundle.version > BUNDLE_VERSION {
+        return Err(format!(
+            "Bundle version {} is newer than this version of CodePod supports",
+            bundle.version
+        ));
+    }
+
+    let mut seen = BTreeSet::new();
+    for definition in &bundle.definitions {
+        validate_name(definition.kind, &definition.name)?;
+        if !seen.insert((definition.kind.dir_name(), definition.name.as_str())) {
+            return Err(format!("'{}' appears more than once", definition.name));
+        }
+        parse_frontmatter(&definition.content)
+            .map_err(|e| format!("Invalid '{}': {}", definition.name, e))?;
+        if definition.kind != DefinitionKind::Skill && !definition.files.is_empty() {
+            return Err(format!(
+                "Only skills can have extra files, not '{}'",
+                definition.name
+            ));
+        }
+        if let Some(path) = definition
+            .files
+            .keys()
+            .find(|p| !is_safe_relative(p) || p.as_str() == "SKILL.md")
+        {
+            return Err(format!(
+                "Skill '{}' has an invalid file path '{}'",
+                definition.name, path
+            ));
+        }
+    }
+
+    for (name, config) in &bundle.mcp_servers {
+        validate_server_name(name)?;
+        validate_server(config).map_err(|e| format!("MCP server '{}': {}", name, e))?;
+    }
+
+    let p = &bundle.permissions;
+    for (list, rules) in RULE_LISTS.into_iter().zip([&p.allow, &p.deny, &p.ask]) {
+        if rules.iter().any(|r| r.trim().is_empty()) {
+            return Err(format!("Bundle has an empty '{}' permission rule", list));
+        }
+    }
+    Ok(())
+}
+
+fn load_bundle(path: &Path) -> Result<ConfigBundle, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let bundle: ConfigBundle = serde_json::from_str(&content)
+        .map_err(|e| format!("Invalid bundle {}: {}", path.display(), e))?;
+    validate_bundle(&bundle)?;
+    Ok(bundle)
+}
+
+/// How an item would be written
+enum Action {
+    /// Definition by index in the bundle, written at `path`
+    Definition {
+        index: usize,
+        path: PathBuf,
+    },
+    /// Server written to its scope's enabled or disabled servers
+    McpServer {
+        name: String,
+        value: Value,
+        enabled: bool,
+    },
+    Permission {
+        list: &'static str,
+        rule: String,
+    },
+}
+
+struct PlannedItem {
+    item: ImportItem,
+    action: Action,
+}
+
+fn definition_scope(scope: SettingsScope) -> DefinitionScope {
+    match scope {
+        SettingsScope::User => DefinitionScope::User,
+        SettingsScope::Project | SettingsScope::Local => DefinitionScope::Project,
+    }
+}
+
+fn item_kind(kind: DefinitionKind) -> ImportItemKind {
+    match kind {
+        DefinitionKind::Command => ImportItemKind::Command,
+        DefinitionKind::Agent => ImportItemKind::Agent,
+        DefinitionKind::Skill => ImportItemKind::Skill,
+    }
+}
+
+fn planned(
+    kind: ImportItemKind,
+    key: String,
+    name: String,
+    status: ImportStatus,
+    path: &Path,
+    action: Action,
+) -> PlannedItem {
+    PlannedItem {
+        item: ImportItem {
+            key,
+            kind,
+            name,
+            status,
+            path: path.to_string_lossy().into_owned(),
+        },
+        action,
+    }
+}
+
+/// Compare each bundled item with what the target scope already has
+fn plan_import(
+    bundle: &ConfigBundle,
+    scope: SettingsScope,
+    project_path: Option<&str>,
+) -> Result<Vec<PlannedItem>, String> {
+    let mut plan = Vec::new();
+
+    let base = scope_dir(definition_scope(scope), project_path)?;
+    for (index, definition) in bundle.definitions.iter().enumerate() {
+        let (path, status) = match locate(&base, definition.kind, &definition.name) {
+            Ok((path, _)) => {
+                let mut files = BTreeMap::new();
+                let mut skipped = Vec::new();
+                if definition.kind == DefinitionKind::Skill {
+                    let root = definition_root(definition.kind, &path);
+                    collect_files(&root, &root, &mut files, &mut skipped)?;
+                    files.remove("SKILL.md");
+                }
+                // Binary files there aren't in the bundle, so it differs
+                let identical = skipped.is_empty()
+                    && read_text_opt(&path)?.as_ref() == Some(&definition.content)
+                    && files == definition.files;
+                let status = if identical {
+                    ImportStatus::Identical
+                } else {
+                    ImportStatus::Conflict
+                };
+                (path, status)
+            }
+            Err(_) => (
+                definition_path(&base, definition.kind, &definition.name),
+                ImportStatus::New,
+            ),
+        };
+        let kind = item_kind(definition.kind);
+        let key = format!("{}:{}", definition.kind.dir_name(), definition.name);
+        let action = Action::Definition {
+            index,
+            path: path.clone(),
+        };
+        plan.push(planned(
+            kind,
+            key,
+            definition.name.clone(),
+            status,
+            &path,
+            action,
+        ));
+    }
+
+    for (name, config) in &bundle.mcp_servers {
+        let value = serde_json::to_value(config)
+            .map_err(|e| format!("Failed to serialize server: {}", e))?;
+        let (status, enabled) = match server_state(scope, project_path, name)? {
+            Some(enabled) => {
+                let existing = read_servers(scope, project_path, enabled)?.1;
+                let status = if existing.get(name) == Some(&value) {
+                    ImportStatus::Identical
+                } else {
+                    ImportStatus::Conflict
+                };
+                (status, enabled)
+            }
+            None => (ImportStatus::New, true),
+        };
+        let (path, _) = servers_location(scope, project_path, enabled)?;
+        let key = format!("mcp:{}", name);
+        let action = Action::McpServer {
+            name: name.clone(),
+            value,
+            enabled,
+        };
+        plan.push(planned(
+            ImportItemKind::McpServer,
+            key,
+            name.clone(),
+            status,
+            &path,
+            action,
+        ));
+    }
+
+    let path = settings::settings_path(scope, project_path)?;
+    let mut existing = read_permissions(scope, project_path)?;
+    let mut bundled = bundle.permissions.clone();
+    for list in RULE_LISTS {
+        let present = rule_list_mut(&mut existing, list).clone();
+        for rule in rule_list_mut(&mut bundled, list).drain(..) {
+            let status = if present.contains(&rule) {
+                ImportStatus::Identical
+            } else {
+                ImportStatus::New
+            };
+            let name = format!("{}: {}", list, rule);
+            let key = format!("permission:{}:{}", list, rule);
+            let action = Action::Permission { list, rule };
+            plan.push(planned(
+                ImportItemKind::Permission,
+                key,
+                name,
+                status,
+                &path,
+                action,
+            ));
+        }
+    }
+
+    Ok(plan)
+}
+
+/// A change to undo if a later write of the import fails
+enum Undo {
+    Restore(PathBuf, Vec<u8>),
+    RemoveFile(PathBuf),
+    RemoveDir(PathBuf),
+    MoveBack { from: PathBuf, to: PathBuf },
+}
+
+/// Undo log making an import all or nothing
+#[derive(Default)]
+struct ImportTransaction {
+    undo: Vec<Undo>,
+    /// Replaced skill folders, deleted once the import succeeds
+    aside: Vec<PathBuf>,
+}
+
+impl ImportTransaction {
+    /// Write a file; with `expected_version`, only if it is unchanged since read
+    fn write(
+        &mut self,
+        path: &Path,
+        content: &[u8],
+        expected_version: Option<&str>,
+    ) -> Result<(), String> {
+        let undo = match fs::read(path) {
+            Ok(previous) => Undo::Restore(path.to_path_buf(), previous),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Undo::RemoveFile(path.to_path_buf())
+            }
+            Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+        };
+
+        // Directories are removed again, deepest first, on rollback
+        let missing: Vec<PathBuf> = path
+            .ancestors()
+            .skip(1)
+            .take_while(|dir| !dir.as_os_str().is_empty() && !dir.exists())
+            .map(Path::to_path_buf)
+            .collect();
+        self.undo
+            .extend(missing.into_iter().rev().map(Undo::RemoveDir));
+
+        backups::safe_write(path, content, expected_version)?;
+        self.undo.push(undo);
+        Ok(())
+    }
+
+    /// Move a folder out of the way so it can be written afresh
+    fn replace_dir(&mut self, dir: &Path) -> Result<(), String> {
+        let name = dir
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let aside = dir.with_file_name(format!(".{}.{}.import", name, uuid::Uuid::new_v4()));
+        fs::rename(dir, &aside)
+            .map_err(|e| format!("Failed to replace {}: {}", dir.display(), e))?;
+        self.undo.push(Undo::MoveBack {
+            from: aside.clone(),
+            to: dir.to_path_buf(),
+        });
+        self.aside.push(aside);
+        Ok(())
+    }
+
+    fn commit(self) {
+        for dir in self.aside {
+            if let Err(e) = fs::remove_dir_all(&dir) {
+                log::warn!("Failed to remove {}: {}", dir.display(), e);
+            }
+        }
+    }
+
+    fn rollback(self) {
+        for undo in self.undo.into_iter().rev() {
+            let result = match &undo {
+                Undo::Restore(path, content) => backups::write_file_atomic(path, content),
+                Undo::RemoveFile(path) => fs::remove_file(path),
+                Undo::RemoveDir(dir) => fs::remove_dir(dir),
+                Undo::MoveBack { from, to } => {
+                    let _ = fs::remove_dir_all(to);
+                    fs::rename(from, to)
+                }
+            };
+            if let Err(e) = result {
+                log::error!("Failed to undo part of a bundle import: {}", e);
+            }
+        }
+    }
+}
+
+fn pretty_json(value: &Value) -> Result<Vec<u8>, String> {
+    let mut content =
+        serde_json::to_string_pretty(value).map_err(|e| format!("Failed to serialize: {}", e))?;
+    content.push('\n');
+    Ok(content.into_bytes())
+}
+
+/// Write the chosen items, undoing every write if one fails
+fn apply_import(
+    bundle: &ConfigBundle,
+    items: &[PlannedItem],
+    scope: SettingsScope,
+    project_path: Option<&str>,
+) -> Result<(), String> {
+    let mut tx = ImportTransaction::default();
+
+    let result = (|| {
+        let mut servers: BTreeMap<bool, Vec<(&str, &Value)>> = BTreeMap::new();
+        let mut rules: Vec<(&str, &str)> = Vec::new();
+
+        for planned in items {
+            match &planned.action {
+                Action::Definition { index, path } => {
+                    let definition = &bundle.definitions[*index];
+                    if definition.kind == DefinitionKind::Skill {
+                        let root = definition_root(definition.kind, path);
+                        if root.exists() {
+                            tx.replace_dir(&root)?;
+                        }
+                        for (file, content) in &definition.files {
+                            tx.write(&root.join(file), content.as_bytes(), None)?;
+                        }
+                    }
+                    tx.write(path, definition.content.as_bytes(), None)?;
+                }
+                Action::McpServer {
+                    name,
+                    value,
+                    enabled,
+                } => servers
+                    .entry(*enabled)
+                    .or_default()
+                    .push((name.as_str(), value)),
+                Action::Permission { list, rule } => rules.push((*list, rule.as_str())),
+            }
+        }
+
+        for (enabled, entries) in servers {
+            let (path, keys) = servers_location(scope, project_path, enabled)?;
+            let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+            // `~/.claude.json` is rewritten by the CLI too, so don't clobber
+            // a change made since it was read
+            let (mut root, version) = read_json_versioned(&path)?;
+            let map = object_at(&mut root, &keys)?;
+            for (name, value) in entries {
+                map.insert(name.to_string(), value.clone());
+            }
+            tx.write(&path, &pretty_json(&root)?, Some(&version))?;
+        }
+
+        if !rules.is_empty() {
+            let path = settings::settings_path(scope, project_path)?;
+            let mut settings = settings::read_settings(&path)?;
+            let permissions = object_at(&mut settings, &["permissions"])?;
+            for (list, rule) in rules {
+                let entries = permissions
+                    .entry(list)
+                    .or_insert_with(|| Value::Array(vec![]));
+                match entries {
+                    Value::Array(entries) => entries.push(Value::String(rule.to_string())),
+                    _ => {
+                        return Err(format!(
+                            "'permissions.{}' in {} is not a list",
+                            list,
+                            path.display()
+                        ))
+                    }
+                }
+            }
+            tx.write(&path, &pretty_json(&settings)?, None)?;
+        }
+        Ok(())
+    })();
+
+    match result {
+        Ok(()) => {
+            tx.commit();
+            Ok(())
+        }
+        Err(e) => {
+            tx.rollback();
+            Err(e)
+        }
+    }
+}
+
+fn missing_variables(bundle: &ConfigBundle) -> Vec<String> {
+    let names: BTreeSet<&str> = bundle
+        .variables
+        .iter()
+        .map(|v| v.name.as_str())
+        .filter(|name| std::env::var_os(name).is_none())
+        .collect();
+    names.into_iter().map(str::to_string).collect()
+}
+
+fn import_bundle(
+    bundle: &ConfigBundle,
+    scope: SettingsScope,
+    project_path: Option<&str>,
+    overwrite: &[String],
+) -> Result<Vec<ImportItem>, String> {
+    let chosen: Vec<PlannedItem> = plan_import(bundle, scope, project_path)?
+        .into_iter()
+        .filter(|p| match p.item.status {
+            ImportStatus::New => true,
+            ImportStatus::Identical => false,
+            ImportStatus::Conflict => overwrite.contains(&p.item.key),
+        })
+        .collect();
+    apply_import(bundle, &chosen, scope, project_path)?;
+    Ok(chosen.into_iter().map(|p| p.item).collect())
+}
+
+/// Write the selected items to a bundle file
+#[command]
+pub async fn export_config_bundle(
+    selection: BundleSelection,
+    project_path: Option<String>,
+    output_path: String,
+) -> CommandResult<BundleExport> {
+    let result =
+        build_bundle(&selection, project_path.as_deref()).and_then(|(bundle, warnings)| {
+            let content = serde_json::to_value(&bundle)
+                .map_err(|e| format!("Failed to serialize bundle: {}", e))
+                .and_then(|value| pretty_json(&value))?;
+            backups::write_file_atomic(Path::new(&output_path), &content)
+                .map_err(|e| format!("Failed to write {}: {}", output_path, e))?;
+
+            let p = &bundle.permissions;
+            let untemplated = bundle
+                .mcp_servers
+                .iter()
+                .flat_map(|(name, config)| untemplated_fields(name, config))
+                .collect();
+            Ok(BundleExport {
+                path: output_path.clone(),
+                definitions: bundle.definitions.len(),
+                mcp_servers: bundle.mcp_servers.len(),
+                permission_rules: p.allow.len() + p.deny.len() + p.ask.len(),
+                variables: bundle.variables,
+                untemplated,
+                warnings,
+            })
+        });
+
+    match result {
+        Ok(export) => CommandResult::ok(export),
+        Err(e) => CommandResult::err(e),
+    }
+}
+
+/// Compare a bundle with what a scope already has
+#[command]
+pub async fn preview_config_bundle(
+    bundle_path: String,
+    scope: SettingsScope,
+    project_path: Option<String>,
+) -> CommandResult<ImportPreview> {
+    let result = load_bundle(Path::new(&bundle_path)).and_then(|bundle| {
+        let plan = plan_import(&bundle, scope, project_path.as_deref())?;
+        Ok(ImportPreview {
+            items: plan.into_iter().map(|p| p.item).collect(),
+            missing_variables: missing_variables(&bundle),
+            variables: bundle.variables,
+        })
+    });
+
+    match result {
+        Ok(preview) => CommandResult::ok(preview),
+        Err(e) => CommandResult::err(e),
+    }
+}
+
+/// Import a bundle into a scope, returning the items written
+///
+/// New items are always written; conflicts only when their key is in
+/// `overwrite`. Either every item is written or, on failure, none is.
+#[command]
+pub async fn import_config_bundle(
+    bundle_path: String,
+    scope: SettingsScope,
+    project_path: Option<String>,
+    overwrite: Vec<String>,
+) -> CommandResult<Vec<ImportItem>> {
+    match load_bundle(Path::new(&bundle_path))
+        .and_then(|bundle| import_bundle(&bundle, scope, project_path.as_deref(), &overwrite))
+    {
+        Ok(items) => CommandResult::ok(items),
+        Err(e) => CommandResult::err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::mcp::McpTransport;
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("codepod-bundle-{}", uuid::Uuid::new_v4()))
+    }
+
+    fn write(path: &Path, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    fn statuses(plan: &[PlannedItem]) -> Vec<(&str, ImportStatus)> {
+        plan.iter()
+            .map(|p| (p.item.key.as_str(), p.item.status))
+            .collect()
+    }
+
+    #[test]
+    fn test_template_secrets() {
+        let mut config = McpServerConfig {
+            transport: McpTransport::Http,
+            url: Some("https://api.example.com/mcp".to_string()),
+            headers: BTreeMap::from([
+                ("Authorization".to_string(), "Bearer abc123".to_string()),
+                ("X-Api-Key".to_string(), "${MY_KEY}".to_string()),
+                ("Accept".to_string(), "application/json".to_string()),
+            ]),
+            ..Default::default()
+        };
+        config
+            .env
+            .insert("GITHUB_TOKEN".to_string(), "ghp_secret".to_string());
+        config.env.insert(
+            "DATABASE_URL".to_string(),
+            "postgres://u:pass@db/app".to_string(),
+        );
+
+        let variables = template_secrets("git-hub", &mut config);
+
+        assert_eq!(config.env["GITHUB_TOKEN"], "${GIT_HUB_GITHUB_TOKEN}");
+        assert_eq!(config.env["DATABASE_URL"], "${GIT_HUB_DATABASE_URL}");
+        assert_eq!(
+            config.headers["Authorization"],
+            "Bearer ${GIT_HUB_AUTHORIZATION}"
+        );
+        assert_eq!(config.headers["X-Api-Key"], "${MY_KEY}");
+        assert_eq!(config.headers["Accept"], "${GIT_HUB_ACCEPT}");
+        let names: Vec<&str> = variables.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "GIT_HUB_DATABASE_URL",
+                "GIT_HUB_GITHUB_TOKEN",
+                "GIT_HUB_ACCEPT",
+                "GIT_HUB_AUTHORIZATION"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_untemplated_fields() {
+        let config = McpServerConfig {
+            transport: McpTransport::Http,
+            args: vec!["--token=${TOKEN}".to_string(), "--pat=ghp_1".to_string()],
+            url: Some("https://api.example.com/mcp?key=abc".to_string()),
+            ..Default::default()
+        };
+        let fields: Vec<(String, String)> = untemplated_fields("api", &config)
+            .into_iter()
+            .map(|f| (f.field, f.value))
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                ("args.1".to_string(), "--pat=ghp_1".to_string()),
+                (
+                    "url".to_string(),
+                    "https://api.example.com/mcp?key=abc".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_export_and_import_between_projects() {
+        let dir = temp_dir();
+        let (source, target) = (dir.join("source"), dir.join("target"));
+        write(
+            &source.join(".claude/commands/git/commit.md"),
+            "---\ndescription: Commit\n---\nCommit the staged changes.\n",
+        );
+        write(
+            &source.join(".claude/skills/pdf/SKILL.md"),
+            "---\nname: pdf\ndescription: Work with PDFs\n---\nUse the script.\n",
+        );
+        write(
+            &source.join(".claude/skills/pdf/scripts/fill.sh"),
+            "echo fill\n",
+        );
+        write(
+            &source.join(".claude/skills/pdf/node_modules/dep/index.js"),
+            "module.exports = {}\n",
+        );
+        // A folder linking back to the skill would otherwise recurse forever
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(
+            source.join(".claude/skills/pdf"),
+            source.join(".claude/skills/pdf/scripts/self"),
+        )
+        .unwrap();
+        fs::write(
+            source.join(".claude/skills/pdf/logo.png"),
+            [0x89, 0xff, 0x00],
+        )
+        .unwrap();
+        write(
+            &source.join(".mcp.json"),
+            r#"{"mcpServers": {"search": {"command": "npx", "args": ["search-mcp"], "env": {"API_KEY": "k-123"}}}}"#,
+        );
+        write(
+            &source.join(".claude/settings.json"),
+            r#"{"permissions": {"allow": ["Bash(npm test)"], "deny": ["Read(./.env)"]}}"#,
+        );
+        // Already in the target exactly as bundled
+        write(
+            &target.join(".claude/settings.json"),
+            r#"{"permissions": {"deny": ["Read(./.env)"]}}"#,
+        );
+
+        let (source_path, target_path) = (
+            source.to_string_lossy().into_owned(),
+            target.to_string_lossy().into_owned(),
+        );
+        let selection = BundleSelection {
+            definitions: vec![
+                DefinitionRef {
+                    kind: DefinitionKind::Command,
+                    scope: DefinitionScope::Project,
+                    name: "git:commit".to_string(),
+                },
+                DefinitionRef {
+                    kind: DefinitionKind::Skill,
+                    scope: DefinitionScope::Project,
+                    name: "pdf".to_string(),
+                },
+            ],
+            mcp_servers: vec![McpServerRef {
+                scope: SettingsScope::Project,
+                name: "search".to_string(),
+            }],
+            permission_scopes: vec![SettingsScope::Project],
+        };
+        let (bundle, warnings) = build_bundle(&selection, Some(&source_path)).unwrap();
+        validate_bundle(&bundle).unwrap();
+        assert_eq!(warnings, ["Skipped logo.png of 'pdf': not a text file"]);
+        assert_eq!(
+            bundle.mcp_servers["search"].env["API_KEY"],
+            "${SEARCH_API_KEY}"
+        );
+        assert_eq!(
+            bundle.definitions[1].files.keys().collect::<Vec<_>>(),
+            ["scripts/fill.sh"]
+        );
+
+        let plan = plan_import(&bundle, SettingsScope::Project, Some(&target_path)).unwrap();
+        assert_eq!(
+            statuses(&plan),
+            [
+                ("commands:git:commit", ImportStatus::New),
+                ("skills:pdf", ImportStatus::New),
+                ("mcp:search", ImportStatus::New),
+                ("permission:allow:Bash(npm test)", ImportStatus::New),
+                ("permission:deny:Read(./.env)", ImportStatus::Identical),
+            ]
+        );
+
+        // A file where the skills folder belongs makes the skill write fail
+        // after the command was written
+        write(&target.join(".claude/skills"), "");
+        let error = import_bundle(&bundle, SettingsScope::Project, Some(&target_path), &[]);
+        assert!(error.unwrap_err().contains("skills"));
+        assert!(!target.join(".claude/commands").exists());
+        assert!(!target.join(".mcp.json").exists());
+
+        // Overwriting settings would back them up in the real data directory
+        fs::remove_file(target.join(".claude/skills")).unwrap();
+        fs::remove_file(target.join(".claude/settings.json")).unwrap();
+        let written =
+            import_bundle(&bundle, SettingsScope::Project, Some(&target_path), &[]).unwrap();
+        assert_eq!(written.len(), 5);
+        assert_eq!(
+            fs::read_to_string(target.join(".claude/skills/pdf/scripts/fill.sh")).unwrap(),
+            "echo fill\n"
+        );
+
+        let plan = plan_import(&bundle, SettingsScope::Project, Some(&target_path)).unwrap();
+        assert!(plan
+            .iter()
+            .all(|p| p.item.status == ImportStatus::Identical));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_validate_bundle_rejects_escaping_paths() {
+        let mut bundle = ConfigBundle {
+            format: BUNDLE_FORMAT.to_string(),
+            version: BUNDLE_VERSION,
+            created_at: 0,
+            definitions: vec![BundledDefinition {
+                kind: DefinitionKind::Skill,
+                name: "pdf".to_string(),
+                content: "---\nname: pdf\ndescription: PDFs\n---\n".to_string(),
+                files: BTreeMap::from([("../../evil.sh".to_string(), String::new())]),
+            }],
+            mcp_servers: BTreeMap::new(),
+            permissions: Permissions::default(),
+            variables: vec![],
+        };
+        assert!(validate_bundle(&bundle)
+            .unwrap_err()
+            .contains("invalid file path"));
+
+        bundle.definitions.clear();
+        bundle.version = BUNDLE_VERSION + 1;
+        assert!(validate_bundle(&bundle).unwrap_err().contains("newer"));
+    }
+}
@@ -0,0 +1,234 @@
+//! Stream capture and replay
+//!
+//! A streaming invocation can tee every raw stdout and stderr line of the CLI
+//! into a JSONL capture file, with the time each line arrived. Replaying a
+//! capture re-emits the same `claude-stream-{request_id}` events without
+//! running the CLI, so rendering problems can be reproduced.
+
+use super::claude::{self, ClaudeOptions};
+use super::jobs::{now_millis, JobState};
+use super::CommandResult;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tauri::{command, AppHandle};
+
+/// Longest pause between replayed records, however slow the replay
+const MAX_REPLAY_DELAY: Duration = Duration::from_secs(60 * 60);
+
+/// One line of a capture file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CaptureRecord {
+    /// Written once before the CLI is spawned
+    Start {
+        started_at: u64,
+        prompt: String,
+        options: ClaudeOptions,
+    },
+    Stdout {
+        elapsed_ms: u64,
+        line: String,
+    },
+    Stderr {
+        elapsed_ms: u64,
+        line: String,
+    },
+    /// How the invocation ended
+    Exit {
+        elapsed_ms: u64,
+        state: JobState,
+        error: Option<String>,
+    },
+}
+
+/// Appends records to a capture file as lines arrive
+pub struct CaptureWriter {
+    file: Mutex<File>,
+    started: Instant,
+}
+
+impl CaptureWriter {
+    /// Create (or truncate) a capture file and write its `start` record
+    pub fn create(path: &Path, prompt: &str, options: &ClaudeOptions) -> std::io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let writer = Self {
+            file: Mutex::new(File::create(path)?),
+            started: Instant::now(),
+        };
+        writer.write(&CaptureRecord::Start {
+            started_at: now_millis(),
+            prompt: prompt.to_string(),
+            options: options.clone(),
+        });
+        Ok(writer)
+    }
+
+    fn elapsed_ms(&self) -> u64 {
+        self.started.elapsed().as_millis() as u64
+    }
+
+    pub fn stdout(&self, line: &str) {
+        self.write(&CaptureRecord::Stdout {
+            elapsed_ms: self.elapsed_ms(),
+            line: line.to_string(),
+        });
+    }
+
+    pub fn stderr(&self, line: &str) {
+        self.write(&CaptureRecord::Stderr {
+            elapsed_ms: self.elapsed_ms(),
+            line: line.to_string(),
+        });
+    }
+
+    pub fn exit(&self, state: JobState, error: Option<&str>) {
+        self.write(&CaptureRecord::Exit {
+            elapsed_ms: self.elapsed_ms(),
+            state,
+            error: error.map(String::from),
+        });
+    }
+
+    // A failed write must not break the invocation being captured
+    fn write(&self, record: &CaptureRecord) {
+        let Ok(line) = serde_json::to_string(record) else {
+            return;
+        };
+        if let Err(e) = writeln!(self.file.lock(), "{}", line) {
+            log::warn!("Failed to write stream capture: {}", e);
+        }
+    }
+}
+
+/// Read every record of a capture file
+pub fn load_capture(path: &Path) -> Result<Vec<CaptureRecord>, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read capture {}: {}", path.display(), e))?;
+
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line)
+                .map_err(|e| format!("Invalid capture record on line {}: {}", index + 1, e))
+        })
+        .collect()
+}
+
+/// Delay before replaying a record captured `elapsed_ms` into the run
+///
+/// A `speed` of 2.0 replays twice as fast; zero or less replays instantly.
+fn replay_delay(previous_ms: u64, elapsed_ms: u64, speed: f64) -> Duration {
+    if speed.is_nan() || speed <= 0.0 {
+        return Duration::ZERO;
+    }
+    let gap = elapsed_ms.saturating_sub(previous_ms) as f64;
+    Duration::try_from_secs_f64(gap / speed / 1000.0)
+        .map_or(MAX_REPLAY_DELAY, |delay| delay.min(MAX_REPLAY_DELAY))
+}
+
+/// Re-emit a capture's events as `claude-stream-{request_id}`
+///
+/// Stdout lines become `stream` events exactly as in a live run, followed by
+/// `error` (if the run failed) and `done`. Returns once the capture has been
+/// read; events are emitted in the background.
+#[command]
+pub async fn replay_claude_capture(
+    app: AppHandle,
+    request_id: String,
+    path: String,
+    speed: Option<f64>,
+) -> CommandResult<()> {
+    let records = match load_capture(Path::new(&path)) {
+        Ok(r) => r,
+        Err(e) => return CommandResult::err(e),
+    };
+    let speed = speed.unwrap_or(1.0);
+
+    tokio::spawn(async move {
+        let mut previous_ms = 0;
+        let mut ended = false;
+
+        for record in records {
+            match record {
+                CaptureRecord::Stdout { elapsed_ms, line } => {
+                    tokio::time::sleep(replay_delay(previous_ms, elapsed_ms, speed)).await;
+                    previous_ms = elapsed_ms;
+                    if let Some(data) = claude::parse_stream_line(&line) {
+                        claude::emit_stream_event(&app, &request_id, "stream", data);
+                    }
+                }
+                CaptureRecord::Exit {
+                    elapsed_ms,
+                    state,
+                    error,
+                } => {
+                    tokio::time::sleep(replay_delay(previous_ms, elapsed_ms, speed)).await;
+                    if let Some(message) = error {
+                        claude::emit_stream_event(
+                            &app,
+                            &request_id,
+                            "error",
+                            serde_json::json!({ "message": message, "state": state }),
+                        );
+                    }
+                    ended = true;
+                }
+                CaptureRecord::Start { .. } | CaptureRecord::Stderr { .. } => {}
+            }
+        }
+
+        if !ended {
+            log::warn!("Capture {} has no exit record", path);
+        }
+        claude::emit_stream_event(&app, &request_id, "done", serde_json::json!({}));
+    });
+
+    CommandResult::ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture_round_trip() {
+        let path = std::env::temp_dir().join(format!("capture-{}.jsonl", uuid::Uuid::new_v4()));
+        let writer = CaptureWriter::create(&path, "hello", &ClaudeOptions::default()).unwrap();
+        writer.stdout(r#"{"type":"system"}"#);
+        writer.stderr("warning: something");
+        writer.exit(JobState::Failed, Some("boom"));
+
+        let records = load_capture(&path).unwrap();
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(records.len(), 4);
+        assert!(matches!(&records[0], CaptureRecord::Start { prompt, .. } if prompt == "hello"));
+        assert!(
+            matches!(&records[1], CaptureRecord::Stdout { line, .. } if line == r#"{"type":"system"}"#)
+        );
+        assert!(matches!(&records[2], CaptureRecord::Stderr { .. }));
+        assert!(matches!(
+            &records[3],
+            CaptureRecord::Exit { state: JobState::Failed, error: Some(e), .. } if e == "boom"
+        ));
+    }
+
+    #[test]
+    fn test_replay_delay_scales_with_speed() {
+        assert_eq!(replay_delay(100, 300, 1.0), Duration::from_millis(200));
+        assert_eq!(replay_delay(100, 300, 2.0), Duration::from_millis(100));
+        assert_eq!(replay_delay(100, 300, 0.0), Duration::ZERO);
+        assert_eq!(replay_delay(300, 100, 1.0), Duration::ZERO);
+        assert_eq!(replay_delay(100, 300, 1e-320), MAX_REPLAY_DELAY);
+        assert_eq!(replay_delay(100, 300, f64::NAN), Duration::ZERO);
+    }
+}
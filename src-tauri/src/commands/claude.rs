@@ -1,17 +1,105 @@
+use super::budget;
+use super::capture::CaptureWriter;
+use super::jobs::{self, JobGuard, JobState};
+use super::sessions;
+use super::usage;
 use super::CommandResult;
 use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::path::Path;
 use std::process::Stdio;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tauri::{command, AppHandle, Emitter};
-use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
 use tokio::process::Command;
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ClaudeOptions {
     pub model: Option<String>,
     pub session_id: Option<String>,
     #[serde(default)]
     pub continue_session: bool,
     pub cwd: Option<String>,
+    /// Queue priority; higher values start first
+    #[serde(default)]
+    pub priority: i32,
+    /// Wall-clock limit for a single non-interactive attempt, in seconds
+    pub timeout_secs: Option<u64>,
+    /// Abort an attempt when the CLI produces no output for this long, in seconds
+    pub idle_timeout_secs: Option<u64>,
+    /// Retry transient failures of non-interactive calls
+    pub retry: Option<RetryPolicy>,
+    /// Tee raw stream output into this capture file for later replay
+    pub capture_path: Option<String>,
+}
+
+/// Backoff policy for retrying transient Claude failures
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Total attempts, including the first one
+    pub max_attempts: u32,
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+    pub multiplier: f64,
+    /// Also retry attempts that hit a timeout
+    pub retry_on_timeout: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff_ms: 1000,
+            max_backoff_ms: 30_000,
+            multiplier: 2.0,
+            retry_on_timeout: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before the given retry (1 = first retry)
+    fn backoff(&self, retry: u32) -> Duration {
+        let factor = self
+            .multiplier
+            .max(1.0)
+            .powi(retry.saturating_sub(1) as i32);
+        let delay = (self.initial_backoff_ms as f64 * factor).min(self.max_backoff_ms as f64);
+        Duration::from_millis(delay as u64)
+    }
+}
+
+/// How a single non-interactive attempt ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttemptOutcome {
+    Success,
+    Error,
+    Timeout,
+    IdleTimeout,
+    SpawnError,
+}
+
+/// Report of one attempt of a non-interactive invocation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClaudeAttempt {
+    pub attempt: u32,
+    pub outcome: AttemptOutcome,
+    pub duration_ms: u64,
+    pub exit_code: Option<i32>,
+    pub error: Option<String>,
+    /// Whether the failure looked transient (overloaded, rate limited)
+    pub transient: bool,
+}
+
+/// Result of a non-interactive invocation, including every attempt made
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClaudeInvocation {
+    /// Raw `--output-format json` stdout of the successful attempt
+    pub output: Option<String>,
+    pub attempts: Vec<ClaudeAttempt>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,10 +108,24 @@ pub struct StreamEvent {
     pub data: serde_json::Value,
 }
 
+/// Claude CLI executable
+///
+/// Overridable with `CODEPOD_CLAUDE_BIN`, e.g. to run against `mock-claude`.
+pub fn claude_binary() -> String {
+    std::env::var("CODEPOD_CLAUDE_BIN")
+        .ok()
+        .filter(|bin| !bin.is_empty())
+        .unwrap_or_else(|| "claude".to_string())
+}
+
 /// Get Claude CLI version
 #[command]
 pub async fn get_claude_version() -> CommandResult<String> {
-    match Command::new("claude").arg("--version").output().await {
+    match Command::new(claude_binary())
+        .arg("--version")
+        .output()
+        .await
+    {
         Ok(output) => {
             if output.status.success() {
                 let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
@@ -36,7 +138,94 @@ pub async fn get_claude_version() -> CommandResult<String> {
     }
 }
 
+/// Build a `claude -p` command for the given prompt and options
+fn claude_command(prompt: &str, output_format: &str, options: &ClaudeOptions) -> Command {
+    let mut cmd = Command::new(claude_binary());
+    cmd.arg("-p").arg(prompt);
+    cmd.arg("--output-format").arg(output_format);
+
+    if let Some(model) = &options.model {
+        cmd.arg("--model").arg(model);
+    }
+
+    if options.continue_session {
+        cmd.arg("--continue");
+    } else if let Some(session_id) = &options.session_id {
+        cmd.arg("--resume").arg(session_id);
+    }
+
+    if let Some(cwd) = &options.cwd {
+        cmd.current_dir(cwd);
+    }
+
+    cmd
+}
+
+pub(crate) fn emit_stream_event(
+    app: &AppHandle,
+    request_id: &str,
+    event_type: &str,
+    data: serde_json::Value,
+) {
+    let event = StreamEvent {
+        event_type: event_type.to_string(),
+        data,
+    };
+    let _ = app.emit(&format!("claude-stream-{}", request_id), &event);
+}
+
+/// What a run reported before its `result`
+#[derive(Default)]
+struct RunUsage {
+    model: Option<String>,
+    /// Assistant messages since the last `result`
+    message_ids: Vec<String>,
+}
+
+/// Record usage and session lineage from `result` messages, remembering the
+/// model from `init` and the ids of assistant messages
+fn track_result(
+    message: &serde_json::Value,
+    options: &ClaudeOptions,
+    run: &mut RunUsage,
+    source: &str,
+) {
+    match message.get("type").and_then(|v| v.as_str()) {
+        Some("system") => {
+            if let Some(m) = message.get("model").and_then(|v| v.as_str()) {
+                run.model = Some(m.to_string());
+            }
+        }
+        Some("assistant") => {
+            let id = message.pointer("/message/id").and_then(|v| v.as_str());
+            if let Some(id) = id.filter(|id| !run.message_ids.iter().any(|m| m == id)) {
+                run.message_ids.push(id.to_string());
+            }
+        }
+        Some("result") => {
+            usage::record_result(
+                message,
+                options.cwd.as_deref(),
+                run.model.as_deref(),
+                source,
+                &std::mem::take(&mut run.message_ids),
+            );
+
+            let session_id = message.get("session_id").and_then(|v| v.as_str());
+            if let (Some(parent), Some(child), false) =
+                (&options.session_id, session_id, options.continue_session)
+            {
+                sessions::record_resume(parent, child, options.cwd.as_deref());
+            }
+        }
+        _ => {}
+    }
+}
+
 /// Invoke Claude CLI with streaming output
+///
+/// The request is queued as a job and only spawns the CLI once the job queue
+/// has a free slot for it.
 #[command]
 pub async fn invoke_claude_stream(
     app: AppHandle,
@@ -46,107 +235,521 @@ pub async fn invoke_claude_stream(
 ) -> CommandResult<()> {
     let options = options.unwrap_or_default();
 
-    let mut cmd = Command::new("claude");
-    cmd.arg("-p").arg(&prompt);
-    cmd.arg("--output-format").arg("stream-json");
+    if let Err(e) = budget::check_before_start(
+        Some(&app),
+        options.cwd.as_deref(),
+        options.session_id.as_deref(),
+    ) {
+        return CommandResult::err(e);
+    }
 
-    if let Some(model) = &options.model {
-        cmd.arg("--model").arg(model);
+    let ticket = match jobs::enqueue(&app, &request_id, options.cwd.clone(), options.priority) {
+        Ok(t) => t,
+        Err(e) => return CommandResult::err(e),
+    };
+
+    tokio::spawn(async move {
+        let (state, error) = match ticket.wait().await {
+            Ok(job) => {
+                let (state, error) = run_stream(&app, &request_id, &prompt, &options, &job).await;
+                job.finish(state, error.clone());
+                (state, error)
+            }
+            Err(e) => (JobState::Cancelled, Some(e)),
+        };
+
+        if let Some(message) = error {
+            emit_stream_event(
+                &app,
+                &request_id,
+                "error",
+                serde_json::json!({ "message": message, "state": state }),
+            );
+        }
+
+        // Send completion event
+        emit_stream_event(&app, &request_id, "done", serde_json::json!({}));
+    });
+
+    CommandResult::ok(())
+}
+
+/// Run a streaming invocation to completion, emitting each stdout line
+async fn run_stream(
+    app: &AppHandle,
+    request_id: &str,
+    prompt: &str,
+    options: &ClaudeOptions,
+    job: &JobGuard,
+) -> (JobState, Option<String>) {
+    // Spend may have crossed a limit while the job was queued
+    if let Err(e) =
+        budget::check_before_start(None, options.cwd.as_deref(), options.session_id.as_deref())
+    {
+        return (JobState::Failed, Some(e));
     }
 
-    if options.continue_session {
-        cmd.arg("--continue");
-    } else if let Some(session_id) = &options.session_id {
-        cmd.arg("--resume").arg(session_id);
+    if let Some(session_id) = &options.session_id {
+        job.set_session(session_id);
     }
 
-    if let Some(cwd) = &options.cwd {
-        cmd.current_dir(cwd);
+    let mut run = RunUsage {
+        model: options.model.clone(),
+        ..Default::default()
+    };
+    let (state, error) = stream_claude(prompt, options, job.cancelled(), |data| {
+        track_result(&data, options, &mut run, "stream");
+        let session_id = data.get("session_id").and_then(|v| v.as_str());
+        if let Some(session_id) = session_id {
+            job.set_session(session_id);
+        }
+        if data.get("type").and_then(|v| v.as_str()) == Some("result") {
+            budget::enforce_after_usage(app, job.id(), options.cwd.as_deref(), session_id);
+        }
+        emit_stream_event(app, request_id, "stream", data);
+    })
+    .await;
+
+    match state {
+        JobState::Cancelled => (state, Some(job.cancel_message())),
+        _ => (state, error),
     }
+}
 
+/// Parse one stdout line of a stream-json run
+///
+/// Non-JSON lines are wrapped as `{"raw": line}`; blank lines are skipped.
+pub(crate) fn parse_stream_line(line: &str) -> Option<serde_json::Value> {
+    if line.trim().is_empty() {
+        return None;
+    }
+    Some(serde_json::from_str(line).unwrap_or_else(|_| serde_json::json!({ "raw": line })))
+}
+
+/// Spawn a stream-json invocation and pass each stdout line to `on_message`
+///
+/// The CLI is killed if `cancelled` resolves before it exits. With
+/// `options.capture_path` set, the raw output is also written to a capture.
+pub async fn stream_claude(
+    prompt: &str,
+    options: &ClaudeOptions,
+    cancelled: impl Future<Output = ()>,
+    on_message: impl FnMut(serde_json::Value),
+) -> (JobState, Option<String>) {
+    let capture = match &options.capture_path {
+        Some(path) => match CaptureWriter::create(Path::new(path), prompt, options) {
+            Ok(writer) => Some(Arc::new(writer)),
+            Err(e) => {
+                return (
+                    JobState::Failed,
+                    Some(format!("Failed to create capture {}: {}", path, e)),
+                )
+            }
+        },
+        None => None,
+    };
+
+    let (state, error) =
+        stream_process(prompt, options, cancelled, on_message, capture.clone()).await;
+    if let Some(capture) = capture {
+        capture.exit(state, error.as_deref());
+    }
+    (state, error)
+}
+
+async fn stream_process(
+    prompt: &str,
+    options: &ClaudeOptions,
+    cancelled: impl Future<Output = ()>,
+    mut on_message: impl FnMut(serde_json::Value),
+    capture: Option<Arc<CaptureWriter>>,
+) -> (JobState, Option<String>) {
+    let mut cmd = claude_command(prompt, "stream-json", options);
     cmd.stdout(Stdio::piped());
     cmd.stderr(Stdio::piped());
+    cmd.kill_on_drop(true);
 
     let mut child = match cmd.spawn() {
         Ok(c) => c,
-        Err(e) => return CommandResult::err(format!("Failed to spawn Claude: {}", e)),
+        Err(e) => {
+            return (
+                JobState::Failed,
+                Some(format!("Failed to spawn Claude: {}", e)),
+            )
+        }
     };
 
-    let stdout = match child.stdout.take() {
-        Some(s) => s,
-        None => return CommandResult::err("Failed to capture stdout"),
+    let (stdout, stderr) = match (child.stdout.take(), child.stderr.take()) {
+        (Some(out), Some(err)) => (out, err),
+        _ => {
+            return (
+                JobState::Failed,
+                Some("Failed to capture stdout".to_string()),
+            )
+        }
     };
 
-    let app_clone = app.clone();
-    let request_id_clone = request_id.clone();
+    // Drain stderr in the background so the CLI never blocks on a full pipe
+    let stderr_capture = capture.clone();
+    let stderr_task = tokio::spawn(async move {
+        let mut buf = String::new();
+        let mut lines = BufReader::new(stderr).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            if let Some(capture) = &stderr_capture {
+                capture.stderr(&line);
+            }
+            buf.push_str(&line);
+            buf.push('\n');
+        }
+        buf
+    });
 
-    // Spawn task to read stdout
-    tokio::spawn(async move {
-        let reader = BufReader::new(stdout);
-        let mut lines = reader.lines();
+    let mut lines = BufReader::new(stdout).lines();
+    tokio::pin!(cancelled);
 
-        while let Ok(Some(line)) = lines.next_line().await {
-            if !line.trim().is_empty() {
-                let event = StreamEvent {
-                    event_type: "stream".to_string(),
-                    data: serde_json::from_str(&line)
-                        .unwrap_or_else(|_| serde_json::json!({ "raw": line })),
-                };
-                let _ = app_clone.emit(&format!("claude-stream-{}", request_id_clone), &event);
+    loop {
+        tokio::select! {
+            line = lines.next_line() => match line {
+                Ok(Some(line)) => {
+                    if let Some(capture) = &capture {
+                        capture.stdout(&line);
+                    }
+                    if let Some(data) = parse_stream_line(&line) {
+                        on_message(data);
+                    }
+                }
+                _ => break,
+            },
+            _ = &mut cancelled => {
+                let _ = child.kill().await;
+                return (JobState::Cancelled, Some("Cancelled".to_string()));
             }
         }
+    }
 
-        // Send completion event
-        let done_event = StreamEvent {
-            event_type: "done".to_string(),
-            data: serde_json::json!({}),
-        };
-        let _ = app_clone.emit(&format!("claude-stream-{}", request_id_clone), &done_event);
-    });
+    let stderr = stderr_task.await.unwrap_or_default();
 
-    CommandResult::ok(())
+    match child.wait().await {
+        Ok(status) if status.success() => (JobState::Completed, None),
+        Ok(status) => {
+            let message = if stderr.trim().is_empty() {
+                format!("Claude exited with {}", status)
+            } else {
+                stderr.trim().to_string()
+            };
+            (JobState::Failed, Some(message))
+        }
+        Err(e) => (
+            JobState::Failed,
+            Some(format!("Failed to wait for Claude: {}", e)),
+        ),
+    }
 }
 
-/// Invoke Claude CLI (non-streaming)
-#[command]
-pub async fn invoke_claude(
-    prompt: String,
-    options: Option<ClaudeOptions>,
-) -> CommandResult<String> {
-    let options = options.unwrap_or_default();
+/// Error markers the CLI and API use for overload and rate limiting
+const TRANSIENT_ERROR_MARKERS: &[&str] = &[
+    "overloaded",
+    "rate limit",
+    "rate_limit",
+    "too many requests",
+];
 
-    let mut cmd = Command::new("claude");
-    cmd.arg("-p").arg(&prompt);
-    cmd.arg("--output-format").arg("json");
+lazy_static::lazy_static! {
+    // Rate limit and overload status codes as words, not inside numbers or paths
+    static ref TRANSIENT_STATUS: regex::Regex =
+        regex::Regex::new(r#"(?:^|[\s:(\[{"'=])(?:429|529)(?:$|[\s:)\]},"']|\.(?:\s|$))"#)
+            .unwrap();
+}
 
-    if let Some(model) = &options.model {
-        cmd.arg("--model").arg(model);
-    }
+/// Check whether an error message describes a transient failure
+fn is_transient_error(message: &str) -> bool {
+    let message = message.to_lowercase();
+    TRANSIENT_ERROR_MARKERS.iter().any(|m| message.contains(m))
+        || TRANSIENT_STATUS.is_match(&message)
+}
 
-    if options.continue_session {
-        cmd.arg("--continue");
-    } else if let Some(session_id) = &options.session_id {
-        cmd.arg("--resume").arg(session_id);
+/// Extract the error text of a `result` message reporting `is_error`
+fn result_error(stdout: &str) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_str(stdout.trim()).ok()?;
+    if value.get("is_error").and_then(|v| v.as_bool()) != Some(true) {
+        return None;
     }
 
-    if let Some(cwd) = &options.cwd {
-        cmd.current_dir(cwd);
+    let text = value
+        .get("result")
+        .and_then(|v| v.as_str())
+        .unwrap_or("Claude reported an error");
+    Some(text.to_string())
+}
+
+/// Run one attempt, enforcing the wall-clock and idle timeouts
+async fn run_attempt(
+    prompt: &str,
+    options: &ClaudeOptions,
+    attempt: u32,
+) -> (ClaudeAttempt, Option<String>) {
+    let started = Instant::now();
+    let report = |outcome, exit_code, error: Option<String>| {
+        let transient = error.as_deref().is_some_and(is_transient_error);
+        ClaudeAttempt {
+            attempt,
+            outcome,
+            duration_ms: started.elapsed().as_millis() as u64,
+            exit_code,
+            error,
+            transient,
+        }
+    };
+
+    let mut cmd = claude_command(prompt, "json", options);
+    cmd.stdout(Stdio::piped());
+    cmd.stderr(Stdio::piped());
+    cmd.kill_on_drop(true);
+
+    let mut child = match cmd.spawn() {
+        Ok(c) => c,
+        Err(e) => {
+            let error = format!("Failed to invoke Claude: {}", e);
+            return (report(AttemptOutcome::SpawnError, None, Some(error)), None);
+        }
+    };
+
+    let (mut stdout, mut stderr) = match (child.stdout.take(), child.stderr.take()) {
+        (Some(out), Some(err)) => (out, err),
+        _ => {
+            let error = "Failed to capture stdout".to_string();
+            return (report(AttemptOutcome::SpawnError, None, Some(error)), None);
+        }
+    };
+
+    let far_future = Duration::from_secs(60 * 60 * 24 * 365);
+    let deadline = tokio::time::Instant::now()
+        + options
+            .timeout_secs
+            .map(Duration::from_secs)
+            .unwrap_or(far_future);
+    let idle = options
+        .idle_timeout_secs
+        .map(Duration::from_secs)
+        .unwrap_or(far_future);
+
+    let mut out = Vec::new();
+    let mut err = Vec::new();
+    let mut out_buf = [0u8; 4096];
+    let mut err_buf = [0u8; 4096];
+    let (mut out_open, mut err_open) = (true, true);
+    let mut last_output = tokio::time::Instant::now();
+
+    while out_open || err_open {
+        tokio::select! {
+            n = stdout.read(&mut out_buf), if out_open => match n {
+                Ok(0) | Err(_) => out_open = false,
+                Ok(n) => {
+                    out.extend_from_slice(&out_buf[..n]);
+                    last_output = tokio::time::Instant::now();
+                }
+            },
+            n = stderr.read(&mut err_buf), if err_open => match n {
+                Ok(0) | Err(_) => err_open = false,
+                Ok(n) => {
+                    err.extend_from_slice(&err_buf[..n]);
+                    last_output = tokio::time::Instant::now();
+                }
+            },
+            _ = tokio::time::sleep_until(deadline) => {
+                let _ = child.kill().await;
+                let error = format!("Timed out after {}s", options.timeout_secs.unwrap_or_default());
+                return (report(AttemptOutcome::Timeout, None, Some(error)), None);
+            }
+            _ = tokio::time::sleep_until(last_output + idle) => {
+                let _ = child.kill().await;
+                let error = format!(
+                    "No output for {}s",
+                    options.idle_timeout_secs.unwrap_or_default()
+                );
+                return (report(AttemptOutcome::IdleTimeout, None, Some(error)), None);
+            }
+        }
     }
 
-    match cmd.output().await {
-        Ok(output) => {
-            if output.status.success() {
-                let result = String::from_utf8_lossy(&output.stdout).to_string();
-                CommandResult::ok(result)
+    let stdout = String::from_utf8_lossy(&out).to_string();
+    let stderr = String::from_utf8_lossy(&err).to_string();
+
+    match child.wait().await {
+        Ok(status) => {
+            let exit_code = status.code();
+            let error = if status.success() {
+                result_error(&stdout)
             } else {
-                let error = String::from_utf8_lossy(&output.stderr).to_string();
-                CommandResult::err(error)
+                // The result message usually explains more than stderr does
+                Some(result_error(&stdout).unwrap_or_else(|| stderr.trim().to_string()))
+            };
+
+            match error {
+                None => (
+                    report(AttemptOutcome::Success, exit_code, None),
+                    Some(stdout),
+                ),
+                Some(e) => (report(AttemptOutcome::Error, exit_code, Some(e)), None),
             }
         }
-        Err(e) => CommandResult::err(format!("Failed to invoke Claude: {}", e)),
+        Err(e) => {
+            let error = format!("Failed to wait for Claude: {}", e);
+            (report(AttemptOutcome::Error, None, Some(error)), None)
+        }
     }
 }
 
+/// Run a non-interactive invocation, retrying transient failures per policy
+pub async fn execute_claude(
+    prompt: &str,
+    options: &ClaudeOptions,
+) -> CommandResult<ClaudeInvocation> {
+    if let Err(e) =
+        budget::check_before_start(None, options.cwd.as_deref(), options.session_id.as_deref())
+    {
+        return CommandResult::err(e);
+    }
+
+    let policy = options.retry.clone();
+    let max_attempts = policy.as_ref().map_or(1, |p| p.max_attempts.max(1));
+    let mut attempts = Vec::new();
+
+    for attempt in 1..=max_attempts {
+        let (report, output) = run_attempt(prompt, options, attempt).await;
+
+        let retryable = match report.outcome {
+            AttemptOutcome::Error => report.transient,
+            AttemptOutcome::Timeout | AttemptOutcome::IdleTimeout => {
+                policy.as_ref().is_some_and(|p| p.retry_on_timeout)
+            }
+            _ => false,
+        };
+        attempts.push(report);
+
+        if let Some(stdout) = &output {
+            if let Ok(result) = serde_json::from_str(stdout.trim()) {
+                let mut run = RunUsage {
+                    model: options.model.clone(),
+                    ..Default::default()
+                };
+                track_result(&result, options, &mut run, "invoke");
+            }
+            return CommandResult::ok(ClaudeInvocation { output, attempts });
+        }
+
+        if !retryable || attempt == max_attempts {
+            break;
+        }
+
+        if let Some(policy) = &policy {
+            tokio::time::sleep(policy.backoff(attempt)).await;
+        }
+    }
+
+    let last_error = attempts
+        .last()
+        .and_then(|a| a.error.clone())
+        .unwrap_or_else(|| "Failed to invoke Claude".to_string());
+    let error = if attempts.len() > 1 {
+        format!("{} (after {} attempts)", last_error, attempts.len())
+    } else {
+        last_error
+    };
+
+    // Keep the attempt reports even though the call failed
+    CommandResult {
+        success: false,
+        data: Some(ClaudeInvocation {
+            output: None,
+            attempts,
+        }),
+        error: Some(error),
+    }
+}
+
+/// Run a non-interactive invocation as a queued job
+///
+/// Like streaming runs, the CLI only spawns once the job queue has a free
+/// slot, and is killed if the job is cancelled while it runs.
+pub async fn execute_claude_job(
+    app: &AppHandle,
+    job_id: &str,
+    prompt: &str,
+    options: &ClaudeOptions,
+) -> CommandResult<ClaudeInvocation> {
+    if let Err(e) = budget::check_before_start(
+        Some(app),
+        options.cwd.as_deref(),
+        options.session_id.as_deref(),
+    ) {
+        return CommandResult::err(e);
+    }
+
+    let ticket = match jobs::enqueue(app, job_id, options.cwd.clone(), options.priority) {
+        Ok(t) => t,
+        Err(e) => return CommandResult::err(e),
+    };
+    let job = match ticket.wait().await {
+        Ok(j) => j,
+        Err(e) => return CommandResult::err(e),
+    };
+
+    // Dropping the invocation future kills the running CLI process
+    let result = tokio::select! {
+        result = execute_claude(prompt, options) => result,
+        _ = job.cancelled() => {
+            let message = job.cancel_message();
+            job.finish(JobState::Cancelled, Some(message.clone()));
+            return CommandResult::err(message);
+        }
+    };
+
+    enforce_after_invocation(app, &job, options, &result);
+    let state = if result.success {
+        JobState::Completed
+    } else {
+        JobState::Failed
+    };
+    job.finish(state, result.error.clone());
+    result
+}
+
+/// Re-check budgets once an invocation run by `job` recorded its cost
+pub(crate) fn enforce_after_invocation(
+    app: &AppHandle,
+    job: &JobGuard,
+    options: &ClaudeOptions,
+    result: &CommandResult<ClaudeInvocation>,
+) {
+    let output = result
+        .data
+        .as_ref()
+        .and_then(|i| i.output.as_deref())
+        .and_then(|o| serde_json::from_str::<serde_json::Value>(o.trim()).ok());
+    let Some(output) = output else {
+        return;
+    };
+
+    let session_id = output.get("session_id").and_then(|v| v.as_str());
+    if let Some(session_id) = session_id {
+        job.set_session(session_id);
+    }
+    budget::enforce_after_usage(app, job.id(), options.cwd.as_deref(), session_id);
+}
+
+/// Invoke Claude CLI (non-streaming)
+#[command]
+pub async fn invoke_claude(
+    app: AppHandle,
+    prompt: String,
+    options: Option<ClaudeOptions>,
+) -> CommandResult<ClaudeInvocation> {
+    let options = options.unwrap_or_default();
+    let job_id = uuid::Uuid::new_v4().to_string();
+    execute_claude_job(&app, &job_id, &prompt, &options).await
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -158,6 +761,49 @@ mod tests {
         assert!(options.session_id.is_none());
         assert!(!options.continue_session);
         assert!(options.cwd.is_none());
+        assert_eq!(options.priority, 0);
+        assert!(options.timeout_secs.is_none());
+        assert!(options.retry.is_none());
+    }
+
+    #[test]
+    fn test_retry_policy_backoff() {
+        let policy = RetryPolicy {
+            initial_backoff_ms: 100,
+            max_backoff_ms: 350,
+            multiplier: 2.0,
+            ..Default::default()
+        };
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(350));
+    }
+
+    #[test]
+    fn test_is_transient_error() {
+        assert!(is_transient_error(
+            "API Error: 529 {\"type\":\"overloaded_error\"}"
+        ));
+        assert!(is_transient_error("Rate limit exceeded, retry later"));
+        assert!(!is_transient_error("Invalid API key"));
+        assert!(is_transient_error("API Error: 429"));
+        assert!(!is_transient_error("Refusing to edit 1429 files"));
+        assert!(!is_transient_error("No such file: /tmp/529/out.json"));
+        assert!(is_transient_error("Request failed with status 529."));
+    }
+
+    #[test]
+    fn test_result_error() {
+        let ok = r#"{"type":"result","is_error":false,"result":"done"}"#;
+        assert_eq!(result_error(ok), None);
+
+        let failed = r#"{"type":"result","is_error":true,"result":"API Error: Overloaded"}"#;
+        assert_eq!(
+            result_error(failed),
+            Some("API Error: Overloaded".to_string())
+        );
+
+        assert_eq!(result_error("not json"), None);
     }
 
     #[test]
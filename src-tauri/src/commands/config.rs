@@ -1,11 +1,17 @@
+use super::backups::{self, VersionedContent};
+use super::definitions::{
+    definition_sources, resolve_shadowing, DefinitionKind, DefinitionOrigin, ListedDefinition,
+};
+use super::frontmatter::{parse_frontmatter, Frontmatter};
+use super::settings::{self, SettingsDiagnostic};
 use super::CommandResult;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tauri::command;
 use tokio::fs;
 
 /// Expand ~ to home directory
-fn expand_path(path: &str) -> PathBuf {
+pub(crate) fn expand_path(path: &str) -> PathBuf {
     if let Some(stripped) = path.strip_prefix("~/") {
         if let Some(home) = dirs::home_dir() {
             return home.join(stripped);
@@ -29,21 +35,64 @@ pub async fn read_config_file(path: String) -> CommandResult<String> {
     }
 }
 
+/// Read a config file along with its version for `write_config_file`
+#[command]
+pub async fn read_config_file_versioned(path: String) -> CommandResult<VersionedContent> {
+    let expanded = expand_path(&path);
+
+    match fs::read_to_string(&expanded).await {
+        Ok(content) => CommandResult::ok(VersionedContent {
+            version: backups::content_version(content.as_bytes()),
+            content,
+        }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => CommandResult::ok(VersionedContent {
+            content: "{}".to_string(),
+            version: backups::MISSING_VERSION.to_string(),
+        }),
+        Err(e) => CommandResult::err(format!("Failed to read {}: {}", path, e)),
+    }
+}
+
+/// Outcome of `write_config_file`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WrittenConfig {
+    /// Version of the written content
+    pub version: String,
+    /// Settings problems found in the content; the file was written anyway
+    pub warnings: Vec<SettingsDiagnostic>,
+}
+
 /// Write a config file
+///
+/// The write is atomic and the previous content is backed up. With
+/// `expected_version`, the write fails if the file changed since it was
+/// read. Settings files must be a JSON object whose known keys have the
+/// right types; other schema and hook problems are returned as warnings
+/// since newer CLI versions may accept them.
 #[command]
-pub async fn write_config_file(path: String, content: String) -> CommandResult<()> {
+pub async fn write_config_file(
+    path: String,
+    content: String,
+    expected_version: Option<String>,
+) -> CommandResult<WrittenConfig> {
     let expanded = expand_path(&path);
 
-    // Ensure parent directory exists
-    if let Some(parent) = expanded.parent() {
-        if let Err(e) = fs::create_dir_all(parent).await {
-            return CommandResult::err(format!("Failed to create directory: {}", e));
+    // Refuse to write settings Claude would fail to parse
+    let mut warnings = Vec::new();
+    if settings::is_settings_file(&expanded) {
+        let value: serde_json::Value = match serde_json::from_str(&content) {
+            Ok(v) => v,
+            Err(e) => return CommandResult::err(format!("Invalid JSON in {}: {}", path, e)),
+        };
+        if let Err(e) = settings::check_structure(&value) {
+            return CommandResult::err(format!("{} in {}", e, path));
         }
+        warnings = settings::validate_settings(&value);
     }
 
-    match fs::write(&expanded, content).await {
-        Ok(()) => CommandResult::ok(()),
-        Err(e) => CommandResult::err(format!("Failed to write {}: {}", path, e)),
+    match backups::safe_write(&expanded, content.as_bytes(), expected_version.as_deref()) {
+        Ok(version) => CommandResult::ok(WrittenConfig { version, warnings }),
+        Err(e) => CommandResult::err(e),
     }
 }
 
@@ -53,6 +102,30 @@ pub struct CommandInfo {
     pub description: Option<String>,
     pub content: String,
     pub enabled: bool,
+    pub path: String,
+    pub frontmatter: Frontmatter,
+    /// Why the file could not be read or parsed
+    pub error: Option<String>,
+    #[serde(flatten)]
+    pub origin: DefinitionOrigin,
+}
+
+impl ListedDefinition for CommandInfo {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn origin(&self) -> &DefinitionOrigin {
+        &self.origin
+    }
+
+    fn origin_mut(&mut self) -> &mut DefinitionOrigin {
+        &mut self.origin
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -62,159 +135,167 @@ pub struct AgentInfo {
     pub system_prompt: String,
     pub tools: Vec<String>,
     pub enabled: bool,
+    pub path: String,
+    pub frontmatter: Frontmatter,
+    /// Why the file could not be read or parsed
+    pub error: Option<String>,
+    #[serde(flatten)]
+    pub origin: DefinitionOrigin,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct SkillInfo {
-    pub name: String,
-    pub description: Option<String>,
-    pub content: String,
-    pub enabled: bool,
-}
-
-/// List all commands from ~/.claude/commands/
-#[command]
-pub async fn list_commands() -> CommandResult<Vec<CommandInfo>> {
-    let commands_dir = expand_path("~/.claude/commands");
+impl ListedDefinition for AgentInfo {
+    fn name(&self) -> &str {
+        &self.name
+    }
 
-    if !commands_dir.exists() {
-        return CommandResult::ok(vec![]);
+    fn enabled(&self) -> bool {
+        self.enabled
     }
 
-    let mut commands = Vec::new();
+    fn origin(&self) -> &DefinitionOrigin {
+        &self.origin
+    }
 
-    match fs::read_dir(&commands_dir).await {
-        Ok(mut entries) => {
-            while let Ok(Some(entry)) = entries.next_entry().await {
-                let path = entry.path();
-                if path.extension().is_some_and(|e| e == "md") {
-                    if let Ok(content) = fs::read_to_string(&path).await {
-                        let name = path
-                            .file_stem()
-                            .and_then(|s| s.to_str())
-                            .unwrap_or("unknown")
-                            .to_string();
-
-                        // Parse frontmatter for description
-                        let (description, body) = parse_frontmatter(&content);
-
-                        commands.push(CommandInfo {
-                            name,
-                            description,
-                            content: body,
-                            enabled: true,
-                        });
-                    }
-                }
-            }
-            CommandResult::ok(commands)
-        }
-        Err(e) => CommandResult::err(format!("Failed to list commands: {}", e)),
+    fn origin_mut(&mut self) -> &mut DefinitionOrigin {
+        &mut self.origin
     }
 }
 
-/// List all agents from ~/.claude/agents/
-#[command]
-pub async fn list_agents() -> CommandResult<Vec<AgentInfo>> {
-    let agents_dir = expand_path("~/.claude/agents");
+/// A markdown definition file and its parsed frontmatter
+///
+/// Files that fail to read or parse are still returned, with `error` set and
+/// the raw content as the body.
+struct MarkdownFile {
+    /// File name without the `.md` extension
+    stem: String,
+    path: PathBuf,
+    frontmatter: Frontmatter,
+    body: String,
+    error: Option<String>,
+}
 
-    if !agents_dir.exists() {
-        return CommandResult::ok(vec![]);
+/// Read every `.md` file in a directory, sorted by name
+///
+/// With `namespaced`, subdirectories are walked too and their files are
+/// named `namespace:name`, the way Claude names nested slash commands.
+async fn read_markdown_dir(dir: &Path, namespaced: bool) -> Result<Vec<MarkdownFile>, String> {
+    if !dir.exists() {
+        return Ok(vec![]);
     }
 
-    let mut agents = Vec::new();
+    let mut files = Vec::new();
+    let mut pending = vec![(dir.to_path_buf(), String::new())];
+
+    while let Some((current, prefix)) = pending.pop() {
+        let mut entries = fs::read_dir(&current)
+            .await
+            .map_err(|e| format!("Failed to read {}: {}", current.display(), e))?;
 
-    match fs::read_dir(&agents_dir).await {
-        Ok(mut entries) => {
-            while let Ok(Some(entry)) = entries.next_entry().await {
-                let path = entry.path();
-                if path.extension().is_some_and(|e| e == "md") {
-                    if let Ok(content) = fs::read_to_string(&path).await {
-                        let name = path
-                            .file_stem()
-                            .and_then(|s| s.to_str())
-                            .unwrap_or("unknown")
-                            .to_string();
-
-                        // Parse frontmatter for description
-                        let (description, body) = parse_frontmatter(&content);
-
-                        agents.push(AgentInfo {
-                            name,
-                            description,
-                            system_prompt: body,
-                            tools: vec![],
-                            enabled: true,
-                        });
-                    }
-                }
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+            let file_name = entry.file_name().to_string_lossy().to_string();
+
+            if namespaced && path.is_dir() && !file_name.starts_with('.') {
+                pending.push((path, format!("{}{}:", prefix, file_name)));
+                continue;
+            }
+            if !path.extension().is_some_and(|e| e == "md") {
+                continue;
             }
-            CommandResult::ok(agents)
+
+            let stem = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("unknown")
+                .to_string();
+
+            let (frontmatter, body, error) = match fs::read_to_string(&path).await {
+                Ok(content) => match parse_frontmatter(&content) {
+                    Ok((frontmatter, body)) => (frontmatter, body, None),
+                    Err(e) => (Frontmatter::default(), content, Some(e)),
+                },
+                Err(e) => (
+                    Frontmatter::default(),
+                    String::new(),
+                    Some(format!("Failed to read file: {}", e)),
+                ),
+            };
+
+            files.push(MarkdownFile {
+                stem: format!("{}{}", prefix, stem),
+                path,
+                frontmatter,
+                body,
+                error,
+            });
         }
-        Err(e) => CommandResult::err(format!("Failed to list agents: {}", e)),
     }
+
+    files.sort_by(|a, b| a.stem.cmp(&b.stem));
+    Ok(files)
 }
 
-/// List all skills from ~/.claude/skills/
+/// List commands from the user, the project if given, and installed plugins
+///
+/// Disabled commands are included. Commands sharing a name are flagged, with
+/// the one Claude uses first.
 #[command]
-pub async fn list_skills() -> CommandResult<Vec<SkillInfo>> {
-    let skills_dir = expand_path("~/.claude/skills");
-
-    if !skills_dir.exists() {
-        return CommandResult::ok(vec![]);
-    }
+pub async fn list_commands(project_path: Option<String>) -> CommandResult<Vec<CommandInfo>> {
+    let mut commands = Vec::new();
 
-    let mut skills = Vec::new();
-
-    match fs::read_dir(&skills_dir).await {
-        Ok(mut entries) => {
-            while let Ok(Some(entry)) = entries.next_entry().await {
-                let path = entry.path();
-                if path.extension().is_some_and(|e| e == "md") {
-                    if let Ok(content) = fs::read_to_string(&path).await {
-                        let name = path
-                            .file_stem()
-                            .and_then(|s| s.to_str())
-                            .unwrap_or("unknown")
-                            .to_string();
-
-                        // Parse frontmatter for description
-                        let (description, body) = parse_frontmatter(&content);
-
-                        skills.push(SkillInfo {
-                            name,
-                            description,
-                            content: body,
-                            enabled: true,
-                        });
-                    }
-                }
-            }
-            CommandResult::ok(skills)
+    for source in definition_sources(project_path.as_deref()) {
+        for (dir, enabled) in source.dirs(DefinitionKind::Command) {
+            let files = match read_markdown_dir(&dir, true).await {
+                Ok(f) => f,
+                Err(e) => return CommandResult::err(format!("Failed to list commands: {}", e)),
+            };
+            commands.extend(files.into_iter().map(|file| CommandInfo {
+                name: source.qualify(file.stem),
+                description: file.frontmatter.description.clone(),
+                content: file.body,
+                enabled,
+                path: file.path.to_string_lossy().to_string(),
+                frontmatter: file.frontmatter,
+                error: file.error,
+                origin: source.origin(),
+            }));
         }
-        Err(e) => CommandResult::err(format!("Failed to list skills: {}", e)),
     }
+
+    resolve_shadowing(&mut commands);
+    CommandResult::ok(commands)
 }
 
-/// Parse YAML frontmatter from markdown content
-fn parse_frontmatter(content: &str) -> (Option<String>, String) {
-    if let Some(stripped) = content.strip_prefix("---") {
-        if let Some(end) = stripped.find("---") {
-            let frontmatter = &stripped[..end];
-            let body = stripped[end + 3..].trim().to_string();
-
-            // Simple extraction of description field
-            for line in frontmatter.lines() {
-                if let Some(desc_value) = line.strip_prefix("description:") {
-                    let desc = desc_value.trim();
-                    return (Some(desc.trim_matches('"').to_string()), body);
-                }
-            }
+/// List agents from the user, the project if given, and installed plugins
+///
+/// Disabled agents are included. Agents sharing a name are flagged, with the
+/// one Claude uses first.
+#[command]
+pub async fn list_agents(project_path: Option<String>) -> CommandResult<Vec<AgentInfo>> {
+    let mut agents = Vec::new();
 
-            return (None, body);
+    for source in definition_sources(project_path.as_deref()) {
+        for (dir, enabled) in source.dirs(DefinitionKind::Agent) {
+            let files = match read_markdown_dir(&dir, false).await {
+                Ok(f) => f,
+                Err(e) => return CommandResult::err(format!("Failed to list agents: {}", e)),
+            };
+            agents.extend(files.into_iter().map(|file| AgentInfo {
+                name: source.qualify(file.frontmatter.name.clone().unwrap_or(file.stem)),
+                description: file.frontmatter.description.clone(),
+                system_prompt: file.body,
+                tools: file.frontmatter.tools.clone().unwrap_or_default(),
+                enabled,
+                path: file.path.to_string_lossy().to_string(),
+                frontmatter: file.frontmatter,
+                error: file.error,
+                origin: source.origin(),
+            }));
         }
     }
-    (None, content.to_string())
+
+    resolve_shadowing(&mut agents);
+    CommandResult::ok(agents)
 }
 
 #[cfg(test)]
@@ -241,16 +322,49 @@ description: "Test command"
 
 Command content here"#;
 
-        let (desc, body) = parse_frontmatter(content);
-        assert_eq!(desc, Some("Test command".to_string()));
+        let (frontmatter, body) = parse_frontmatter(content).unwrap();
+        assert_eq!(frontmatter.description, Some("Test command".to_string()));
         assert_eq!(body, "Command content here");
     }
 
     #[test]
     fn test_parse_frontmatter_no_frontmatter() {
         let content = "Just plain content";
-        let (desc, body) = parse_frontmatter(content);
-        assert_eq!(desc, None);
+        let (frontmatter, body) = parse_frontmatter(content).unwrap();
+        assert_eq!(frontmatter.description, None);
         assert_eq!(body, "Just plain content");
     }
+
+    #[tokio::test]
+    async fn test_read_markdown_dir() {
+        let dir = std::env::temp_dir().join(format!("codepod-md-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).await.unwrap();
+        fs::write(dir.join("b.md"), "---\ntools: Read, Grep\n---\nBody")
+            .await
+            .unwrap();
+        fs::write(dir.join("a.md"), "---\ndescription: [oops\n---\nBody")
+            .await
+            .unwrap();
+        fs::write(dir.join("notes.txt"), "ignored").await.unwrap();
+        fs::create_dir_all(dir.join("git")).await.unwrap();
+        fs::write(dir.join("git").join("commit.md"), "Commit")
+            .await
+            .unwrap();
+
+        let files = read_markdown_dir(&dir, false).await.unwrap();
+        let namespaced = read_markdown_dir(&dir, true).await.unwrap();
+        let _ = fs::remove_dir_all(&dir).await;
+
+        let names: Vec<&str> = namespaced.iter().map(|f| f.stem.as_str()).collect();
+        assert_eq!(names, ["a", "b", "git:commit"]);
+
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].stem, "a");
+        assert!(files[0].error.is_some());
+        assert_eq!(
+            files[1].frontmatter.tools.as_deref().unwrap(),
+            ["Read", "Grep"]
+        );
+        assert_eq!(files[1].body, "Body");
+    }
 }
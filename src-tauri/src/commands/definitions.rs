@@ -0,0 +1,860 @@
+//! Create, update, rename and delete commands, agents and skills
+//!
+//! Definitions live under `~/.claude` for the user scope or a project's
+//! `.claude` directory:
+//!
+//! - commands: `commands/<namespace>/<name>.md`, named `namespace:name`
+//! - agents: `agents/<name>.md`
+//! - skills: `skills/<name>/SKILL.md`
+
+use super::backups;
+use super::frontmatter::{parse_frontmatter, render_markdown, Frontmatter};
+use super::jobs::now_millis;
+use super::plugins;
+use super::skills;
+use super::CommandResult;
+use super::{claude_dir, codepod_dir};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::command;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DefinitionKind {
+    Command,
+    Agent,
+    Skill,
+}
+
+impl DefinitionKind {
+    /// Directory holding this kind under a scope's `.claude` directory
+    pub(crate) fn dir_name(self) -> &'static str {
+        match self {
+            DefinitionKind::Command => "commands",
+            DefinitionKind::Agent => "agents",
+            DefinitionKind::Skill => "skills",
+        }
+    }
+}
+
+/// Where a definition lives
+///
+/// Plugin definitions are listed but never written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DefinitionScope {
+    User,
+    Project,
+    Plugin,
+}
+
+impl DefinitionScope {
+    /// Which definition wins when two share a name
+    fn precedence(self) -> u8 {
+        match self {
+            DefinitionScope::Project => 2,
+            DefinitionScope::User => 1,
+            DefinitionScope::Plugin => 0,
+        }
+    }
+}
+
+/// Where a listed definition comes from and how it relates to others of
+/// the same name
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DefinitionOrigin {
+    pub scope: DefinitionScope,
+    /// Plugin providing the definition
+    pub plugin: Option<String>,
+    /// Scope of the enabled definition that takes precedence over this one
+    pub shadowed_by: Option<DefinitionScope>,
+    /// More than one enabled definition has this name
+    pub conflict: bool,
+}
+
+/// A directory definitions are listed from
+pub(crate) struct DefinitionSource {
+    pub scope: DefinitionScope,
+    pub plugin: Option<String>,
+    /// `.claude` directory, or a plugin's install directory
+    pub base: PathBuf,
+    /// Whether a plugin is enabled in settings
+    pub enabled: bool,
+}
+
+impl DefinitionSource {
+    pub(crate) fn user() -> Self {
+        DefinitionSource {
+            scope: DefinitionScope::User,
+            plugin: None,
+            base: claude_dir(),
+            enabled: true,
+        }
+    }
+
+    /// Directories holding this source's definitions of a kind, and
+    /// whether those definitions are enabled
+    pub(crate) fn dirs(&self, kind: DefinitionKind) -> Vec<(PathBuf, bool)> {
+        match self.scope {
+            DefinitionScope::Plugin => vec![(self.base.join(kind.dir_name()), self.enabled)],
+            _ => kind_dirs(&self.base, kind).to_vec(),
+        }
+    }
+
+    /// Name Claude gives a definition; plugin ones are prefixed with the plugin
+    pub(crate) fn qualify(&self, name: String) -> String {
+        match &self.plugin {
+            Some(plugin) => format!("{}:{}", plugin, name),
+            None => name,
+        }
+    }
+
+    pub(crate) fn origin(&self) -> DefinitionOrigin {
+        DefinitionOrigin {
+            scope: self.scope,
+            plugin: self.plugin.clone(),
+            shadowed_by: None,
+            conflict: false,
+        }
+    }
+}
+
+/// Sources to list definitions from: the user, the project if given, and
+/// installed plugins
+pub(crate) fn definition_sources(project_path: Option<&str>) -> Vec<DefinitionSource> {
+    let mut sources = vec![DefinitionSource::user()];
+    if let Some(project) = project_path {
+        sources.push(DefinitionSource {
+            scope: DefinitionScope::Project,
+            plugin: None,
+            base: Path::new(project).join(".claude"),
+            enabled: true,
+        });
+    }
+    sources.extend(
+        plugins::installed_plugins(project_path)
+            .into_iter()
+            .map(|plugin| DefinitionSource {
+                scope: DefinitionScope::Plugin,
+                plugin: Some(plugin.name),
+                base: plugin.path,
+                enabled: plugin.enabled,
+            }),
+    );
+    sources
+}
+
+/// A listed command, agent or skill
+pub(crate) trait ListedDefinition {
+    fn name(&self) -> &str;
+    fn enabled(&self) -> bool;
+    fn origin(&self) -> &DefinitionOrigin;
+    fn origin_mut(&mut self) -> &mut DefinitionOrigin;
+}
+
+/// Sort definitions by name, the one Claude uses first, and flag the
+/// enabled ones shadowed by another of the same name
+pub(crate) fn resolve_shadowing<T: ListedDefinition>(items: &mut [T]) {
+    items.sort_by(|a, b| {
+        a.name().cmp(b.name()).then_with(|| {
+            (b.enabled(), b.origin().scope.precedence())
+                .cmp(&(a.enabled(), a.origin().scope.precedence()))
+        })
+    });
+
+    for group in items.chunk_by_mut(|a, b| a.name() == b.name()) {
+        let enabled = group.iter().filter(|i| i.enabled()).count();
+        let winner = group.iter().find(|i| i.enabled()).map(|i| i.origin().scope);
+
+        for (index, item) in group.iter_mut().enumerate() {
+            let shadowed = index > 0 && item.enabled();
+            let origin = item.origin_mut();
+            origin.conflict = enabled > 1;
+            origin.shadowed_by = if shadowed { winner } else { None };
+        }
+    }
+}
+
+/// A definition file as written to disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DefinitionFile {
+    pub kind: DefinitionKind,
+    pub scope: DefinitionScope,
+    pub name: String,
+    pub path: String,
+    /// Version to pass back when updating
+    pub version: String,
+}
+
+/// The `.claude` directory of a scope
+pub(crate) fn scope_dir(
+    scope: DefinitionScope,
+    project_path: Option<&str>,
+) -> Result<PathBuf, String> {
+    match scope {
+        DefinitionScope::User => Ok(claude_dir()),
+        DefinitionScope::Project => project_path
+            .map(|p| Path::new(p).join(".claude"))
+            .ok_or_else(|| "A project path is required for project scope".to_string()),
+        DefinitionScope::Plugin => Err("Plugin definitions are read-only".to_string()),
+    }
+}
+
+/// Directory disabled definitions are moved to
+///
+/// It sits beside `commands`, `agents` and `skills` rather than inside them,
+/// so Claude never loads what it holds.
+const DISABLED_DIR: &str = ".disabled";
+
+/// Base directory of a scope's enabled or disabled definitions
+pub(crate) fn state_dir(base: &Path, enabled: bool) -> PathBuf {
+    if enabled {
+        base.to_path_buf()
+    } else {
+        base.join(DISABLED_DIR)
+    }
+}
+
+/// Directories holding a kind's enabled and disabled definitions
+pub(crate) fn kind_dirs(base: &Path, kind: DefinitionKind) -> [(PathBuf, bool); 2] {
+    [true, false].map(|enabled| (state_dir(base, enabled).join(kind.dir_name()), enabled))
+}
+
+/// Check a name against the rules Claude applies to each kind
+///
+/// Command names are `:`-separated segments of letters, digits, `-` and `_`,
+/// each segment but the last being a subdirectory. Agent and skill names are
+/// lowercase letters, digits and hyphens.
+pub(crate) fn validate_name(kind: DefinitionKind, name: &str) -> Result<(), String> {
+    match kind {
+        DefinitionKind::Command => {
+            for segment in name.split(':') {
+                if segment.is_empty() {
+                    return Err(format!("Command name '{}' has an empty segment", name));
+                }
+                if !segment
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+                {
+                    return Err(format!(
+                        "Command name '{}' may only contain letters, numbers, '-', '_' and ':' between namespaces",
+                        name
+                    ));
+                }
+            }
+            Ok(())
+        }
+        DefinitionKind::Agent | DefinitionKind::Skill => match skills::name_issues(name).first() {
+            Some(issue) => Err(format!("Invalid name '{}': {}", name, issue)),
+            None => Ok(()),
+        },
+    }
+}
+
+/// File holding a definition's frontmatter and body
+pub(crate) fn definition_path(base: &Path, kind: DefinitionKind, name: &str) -> PathBuf {
+    let dir = base.join(kind.dir_name());
+    match kind {
+        DefinitionKind::Command => {
+            let mut path = dir;
+            path.extend(name.split(':'));
+            path.set_extension("md");
+            path
+        }
+        DefinitionKind::Agent => dir.join(format!("{}.md", name)),
+        DefinitionKind::Skill => dir.join(name).join("SKILL.md"),
+    }
+}
+
+/// Fill in and check the frontmatter a kind requires
+///
+/// Agents and skills are identified by the `name` field, so it is set to the
+/// definition name. Both need a description for Claude to pick them.
+fn prepare_frontmatter(
+    kind: DefinitionKind,
+    name: &str,
+    mut frontmatter: Frontmatter,
+) -> Result<Frontmatter, String> {
+    match kind {
+        DefinitionKind::Command => {}
+        DefinitionKind::Agent => {
+            frontmatter.name = Some(name.to_string());
+            if frontmatter
+                .description
+                .as_deref()
+                .map_or(true, |d| d.trim().is_empty())
+            {
+                return Err("Agents need a description".to_string());
+            }
+        }
+        DefinitionKind::Skill => {
+            frontmatter.name = Some(name.to_string());
+            if let Some(issue) = skills::validate_frontmatter(&frontmatter).first() {
+                return Err(issue.clone());
+            }
+        }
+    }
+    Ok(frontmatter)
+}
+
+/// Find a definition and whether it is enabled
+pub(crate) fn locate(
+    base: &Path,
+    kind: DefinitionKind,
+    name: &str,
+) -> Result<(PathBuf, bool), String> {
+    for enabled in [true, false] {
+        let path = definition_path(&state_dir(base, enabled), kind, name);
+        if path.exists() {
+            return Ok((path, enabled));
+        }
+    }
+    Err(format!("'{}' does not exist", name))
+}
+
+/// Write a definition, either creating it or replacing an existing one
+///
+/// An existing definition is updated where it is, enabled or not.
+fn write_definition(
+    base: &Path,
+    kind: DefinitionKind,
+    name: &str,
+    frontmatter: Frontmatter,
+    body: &str,
+    create: bool,
+    expected_version: Option<&str>,
+) -> Result<(PathBuf, String), String> {
+    validate_name(kind, name)?;
+    let frontmatter = prepare_frontmatter(kind, name, frontmatter)?;
+    let content = render_markdown(&frontmatter, body)?;
+
+    let path = if create {
+        if locate(base, kind, name).is_ok() {
+            return Err(format!("'{}' already exists", name));
+        }
+        definition_path(base, kind, name)
+    } else {
+        locate(base, kind, name)?.0
+    };
+
+    let version = backups::safe_write(&path, content.as_bytes(), expected_version)?;
+    Ok((path, version))
+}
+
+/// Remove directories left empty between `path` and `root`
+fn remove_empty_parents(path: &Path, root: &Path) {
+    let mut dir = path.parent();
+    while let Some(current) = dir {
+        if current == root || !current.starts_with(root) || fs::remove_dir(current).is_err() {
+            break;
+        }
+        dir = current.parent();
+    }
+}
+
+/// File or, for skills, folder making up a definition
+pub(crate) fn definition_root(kind: DefinitionKind, path: &Path) -> PathBuf {
+    match kind {
+        DefinitionKind::Skill => path.parent().unwrap_or(path).to_path_buf(),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Move a definition, pruning the namespace directories it leaves empty
+/// below `from_dir`
+fn move_definition(
+    kind: DefinitionKind,
+    from: &Path,
+    to: &Path,
+    from_dir: &Path,
+) -> Result<(), String> {
+    let (from, to) = (definition_root(kind, from), definition_root(kind, to));
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    fs::rename(&from, &to).map_err(|e| format!("Failed to move {}: {}", from.display(), e))?;
+    remove_empty_parents(&from, from_dir);
+    Ok(())
+}
+
+/// Rename a definition, moving namespaced commands between subdirectories
+fn rename_definition_in(
+    base: &Path,
+    kind: DefinitionKind,
+    name: &str,
+    new_name: &str,
+) -> Result<PathBuf, String> {
+    validate_name(kind, name)?;
+    validate_name(kind, new_name)?;
+
+    let (from, enabled) = locate(base, kind, name)?;
+    if locate(base, kind, new_name).is_ok() {
+        return Err(format!("'{}' already exists", new_name));
+    }
+    let root = state_dir(base, enabled);
+    let to = definition_path(&root, kind, new_name);
+    move_definition(kind, &from, &to, &root.join(kind.dir_name()))?;
+
+    // Keep the name in the frontmatter in step with the file
+    if kind != DefinitionKind::Command {
+        let content = fs::read_to_string(&to)
+            .map_err(|e| format!("Failed to read {}: {}", to.display(), e))?;
+        let (mut frontmatter, body) = parse_frontmatter(&content)?;
+        frontmatter.name = Some(new_name.to_string());
+        backups::write_file_atomic(&to, render_markdown(&frontmatter, &body)?.as_bytes())
+            .map_err(|e| format!("Failed to write {}: {}", to.display(), e))?;
+    }
+
+    Ok(to)
+}
+
+/// Move a definition in or out of the disabled directory
+fn set_enabled_in(
+    base: &Path,
+    kind: DefinitionKind,
+    name: &str,
+    enabled: bool,
+) -> Result<PathBuf, String> {
+    validate_name(kind, name)?;
+
+    let (from, current) = locate(base, kind, name)?;
+    if current == enabled {
+        return Ok(from);
+    }
+    let to = definition_path(&state_dir(base, enabled), kind, name);
+    if to.exists() {
+        return Err(format!("'{}' exists both enabled and disabled", name));
+    }
+    move_definition(
+        kind,
+        &from,
+        &to,
+        &state_dir(base, current).join(kind.dir_name()),
+    )?;
+    Ok(to)
+}
+
+/// Deleted skill folders, kept until the user clears them
+fn trash_dir() -> PathBuf {
+    codepod_dir().join("trash")
+}
+
+/// Copy a directory tree
+fn copy_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Move a skill folder into `trash`, returning its new location
+///
+/// Falls back to copy and remove when the trash is on another filesystem.
+fn move_to_trash(folder: &Path, trash: &Path, name: &str) -> Result<PathBuf, String> {
+    let to = trash.join(format!("{}-{}", now_millis(), name));
+    let moved = fs::create_dir_all(trash).and_then(|()| {
+        fs::rename(folder, &to).or_else(|_| {
+            copy_dir(folder, &to)?;
+            fs::remove_dir_all(folder)
+        })
+    });
+    moved.map_err(|e| format!("Failed to move '{}' to the trash: {}", name, e))?;
+    Ok(to)
+}
+
+/// Delete a definition
+///
+/// Commands and agents are backed up first; skill folders, which may hold
+/// scripts and other files, are moved to the trash.
+fn delete_definition_in(base: &Path, kind: DefinitionKind, name: &str) -> Result<(), String> {
+    validate_name(kind, name)?;
+
+    let (path, enabled) = locate(base, kind, name)?;
+    let removed = definition_root(kind, &path);
+    match kind {
+        DefinitionKind::Skill => {
+            move_to_trash(&removed, &trash_dir(), name)?;
+        }
+        _ => {
+            backups::backup_file(&path)?;
+            fs::remove_file(&removed).map_err(|e| format!("Failed to delete '{}': {}", name, e))?;
+        }
+    }
+
+    remove_empty_parents(&removed, &state_dir(base, enabled).join(kind.dir_name()));
+    Ok(())
+}
+
+/// Create a command, agent or skill
+#[command]
+pub async fn create_definition(
+    kind: DefinitionKind,
+    scope: DefinitionScope,
+    project_path: Option<String>,
+    name: String,
+    frontmatter: Frontmatter,
+    body: String,
+) -> CommandResult<DefinitionFile> {
+    let result = scope_dir(scope, project_path.as_deref())
+        .and_then(|base| write_definition(&base, kind, &name, frontmatter, &body, true, None));
+
+    match result {
+        Ok((path, version)) => CommandResult::ok(DefinitionFile {
+            kind,
+            scope,
+            name,
+            path: path.to_string_lossy().to_string(),
+            version,
+        }),
+        Err(e) => CommandResult::err(e),
+    }
+}
+
+/// Replace the frontmatter and body of an existing definition
+///
+/// With `expected_version`, the write fails if the file changed since it was
+/// read.
+#[command]
+pub async fn update_definition(
+    kind: DefinitionKind,
+    scope: DefinitionScope,
+    project_path: Option<String>,
+    name: String,
+    frontmatter: Frontmatter,
+    body: String,
+    expected_version: Option<String>,
+) -> CommandResult<DefinitionFile> {
+    let result = scope_dir(scope, project_path.as_deref()).and_then(|base| {
+        write_definition(
+            &base,
+            kind,
+            &name,
+            frontmatter,
+            &body,
+            false,
+            expected_version.as_deref(),
+        )
+    });
+
+    match result {
+        Ok((path, version)) => CommandResult::ok(DefinitionFile {
+            kind,
+            scope,
+            name,
+            path: path.to_string_lossy().to_string(),
+            version,
+        }),
+        Err(e) => CommandResult::err(e),
+    }
+}
+
+/// Rename a definition
+#[command]
+pub async fn rename_definition(
+    kind: DefinitionKind,
+    scope: DefinitionScope,
+    project_path: Option<String>,
+    name: String,
+    new_name: String,
+) -> CommandResult<DefinitionFile> {
+    let result = scope_dir(scope, project_path.as_deref())
+        .and_then(|base| rename_definition_in(&base, kind, &name, &new_name))
+        .and_then(|path| Ok((backups::file_version(&path)?, path)));
+
+    match result {
+        Ok((version, path)) => CommandResult::ok(DefinitionFile {
+            kind,
+            scope,
+            name: new_name,
+            path: path.to_string_lossy().to_string(),
+            version,
+        }),
+        Err(e) => CommandResult::err(e),
+    }
+}
+
+/// Enable or disable a definition
+///
+/// Disabled definitions are moved under the scope's `.claude/.disabled`
+/// directory and moved back when enabled.
+#[command]
+pub async fn set_definition_enabled(
+    kind: DefinitionKind,
+    scope: DefinitionScope,
+    project_path: Option<String>,
+    name: String,
+    enabled: bool,
+) -> CommandResult<DefinitionFile> {
+    let result = scope_dir(scope, project_path.as_deref())
+        .and_then(|base| set_enabled_in(&base, kind, &name, enabled))
+        .and_then(|path| Ok((backups::file_version(&path)?, path)));
+
+    match result {
+        Ok((version, path)) => CommandResult::ok(DefinitionFile {
+            kind,
+            scope,
+            name,
+            path: path.to_string_lossy().to_string(),
+            version,
+        }),
+        Err(e) => CommandResult::err(e),
+    }
+}
+
+/// Delete a definition; skills are moved to `~/.codepod/trash` with their
+/// whole folder
+#[command]
+pub async fn delete_definition(
+    kind: DefinitionKind,
+    scope: DefinitionScope,
+    project_path: Option<String>,
+    name: String,
+) -> CommandResult<()> {
+    match scope_dir(scope, project_path.as_deref())
+        .and_then(|base| delete_definition_in(&base, kind, &name))
+    {
+        Ok(()) => CommandResult::ok(()),
+        Err(e) => CommandResult::err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch() -> PathBuf {
+        std::env::temp_dir().join(format!("codepod-defs-{}", uuid::Uuid::new_v4()))
+    }
+
+    struct Listed(&'static str, bool, DefinitionOrigin);
+
+    impl ListedDefinition for Listed {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn enabled(&self) -> bool {
+            self.1
+        }
+
+        fn origin(&self) -> &DefinitionOrigin {
+            &self.2
+        }
+
+        fn origin_mut(&mut self) -> &mut DefinitionOrigin {
+            &mut self.2
+        }
+    }
+
+    #[test]
+    fn test_resolve_shadowing() {
+        let listed = |name, enabled, scope| {
+            let source = DefinitionSource {
+                scope,
+                plugin: None,
+                base: PathBuf::new(),
+                enabled: true,
+            };
+            Listed(name, enabled, source.origin())
+        };
+        let mut items = vec![
+            listed("review", true, DefinitionScope::User),
+            listed("deploy", false, DefinitionScope::Project),
+            listed("review", false, DefinitionScope::Project),
+            listed("review", true, DefinitionScope::Project),
+            listed("deploy", true, DefinitionScope::User),
+        ];
+
+        resolve_shadowing(&mut items);
+        let resolved: Vec<_> = items
+            .iter()
+            .map(|i| (i.0, i.1, i.2.scope, i.2.shadowed_by, i.2.conflict))
+            .collect();
+
+        use DefinitionScope::{Project, User};
+        assert_eq!(
+            resolved,
+            [
+                ("deploy", true, User, None, false),
+                ("deploy", false, Project, None, false),
+                ("review", true, Project, None, true),
+                ("review", true, User, Some(Project), true),
+                ("review", false, Project, None, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_name() {
+        assert!(validate_name(DefinitionKind::Command, "git:commit_all").is_ok());
+        assert!(validate_name(DefinitionKind::Command, "git::commit").is_err());
+        assert!(validate_name(DefinitionKind::Command, "../escape").is_err());
+        assert!(validate_name(DefinitionKind::Agent, "code-reviewer").is_ok());
+        assert!(validate_name(DefinitionKind::Agent, "Code Reviewer").is_err());
+        assert!(validate_name(DefinitionKind::Skill, "pdf:tools").is_err());
+
+        let base = Path::new("/base");
+        assert_eq!(
+            definition_path(base, DefinitionKind::Command, "git:commit"),
+            Path::new("/base/commands/git/commit.md")
+        );
+        assert_eq!(
+            definition_path(base, DefinitionKind::Skill, "pdf-tools"),
+            Path::new("/base/skills/pdf-tools/SKILL.md")
+        );
+    }
+
+    #[test]
+    fn test_create_and_rename_command() {
+        let base = scratch();
+        let frontmatter = Frontmatter {
+            description: Some("Commit staged changes".to_string()),
+            allowed_tools: Some(vec!["Bash(git commit:*)".to_string()]),
+            ..Default::default()
+        };
+
+        write_definition(
+            &base,
+            DefinitionKind::Command,
+            "git:commit",
+            frontmatter.clone(),
+            "Commit with $ARGUMENTS",
+            true,
+            None,
+        )
+        .unwrap();
+        assert!(write_definition(
+            &base,
+            DefinitionKind::Command,
+            "git:commit",
+            frontmatter,
+            "Again",
+            true,
+            None,
+        )
+        .is_err());
+
+        let path =
+            rename_definition_in(&base, DefinitionKind::Command, "git:commit", "ci").unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        let git_dir_left = base.join("commands").join("git").exists();
+        let _ = fs::remove_dir_all(&base);
+
+        assert_eq!(path, base.join("commands").join("ci.md"));
+        assert!(content.contains("allowed-tools: Bash(git commit:*)\n"));
+        assert!(!git_dir_left);
+    }
+
+    #[test]
+    fn test_disable_and_enable() {
+        let base = scratch();
+        write_definition(
+            &base,
+            DefinitionKind::Command,
+            "git:push",
+            Frontmatter::default(),
+            "Push",
+            true,
+            None,
+        )
+        .unwrap();
+
+        let disabled = set_enabled_in(&base, DefinitionKind::Command, "git:push", false).unwrap();
+        let enabled_dir_left = base.join("commands").join("git").exists();
+        let located = locate(&base, DefinitionKind::Command, "git:push").unwrap();
+        let recreate = write_definition(
+            &base,
+            DefinitionKind::Command,
+            "git:push",
+            Frontmatter::default(),
+            "Push",
+            true,
+            None,
+        );
+        let enabled = set_enabled_in(&base, DefinitionKind::Command, "git:push", true).unwrap();
+        let content = fs::read_to_string(&enabled).unwrap();
+        let _ = fs::remove_dir_all(&base);
+
+        assert_eq!(
+            disabled,
+            base.join(".disabled")
+                .join("commands")
+                .join("git")
+                .join("push.md")
+        );
+        assert!(!enabled_dir_left);
+        assert_eq!(located, (disabled.clone(), false));
+        assert!(recreate.is_err());
+        assert_eq!(enabled, base.join("commands").join("git").join("push.md"));
+        assert_eq!(content, "Push\n");
+    }
+
+    #[test]
+    fn test_rename_skill_updates_frontmatter() {
+        let base = scratch();
+        let frontmatter = Frontmatter {
+            description: Some("Fill PDFs".to_string()),
+            ..Default::default()
+        };
+
+        assert!(write_definition(
+            &base,
+            DefinitionKind::Skill,
+            "pdf",
+            Frontmatter::default(),
+            "Body",
+            true,
+            None,
+        )
+        .is_err());
+        write_definition(
+            &base,
+            DefinitionKind::Skill,
+            "pdf",
+            frontmatter,
+            "Body",
+            true,
+            None,
+        )
+        .unwrap();
+        fs::write(base.join("skills").join("pdf").join("fill.py"), "").unwrap();
+
+        let path = rename_definition_in(&base, DefinitionKind::Skill, "pdf", "pdf-forms").unwrap();
+        let (parsed, body) = parse_frontmatter(&fs::read_to_string(&path).unwrap()).unwrap();
+        let script_moved = path.with_file_name("fill.py").exists();
+        let old_left = base.join("skills").join("pdf").exists();
+        let _ = fs::remove_dir_all(&base);
+
+        assert_eq!(parsed.name.as_deref(), Some("pdf-forms"));
+        assert_eq!(body, "Body");
+        assert!(script_moved);
+        assert!(!old_left);
+    }
+
+    #[test]
+    fn test_move_skill_to_trash() {
+        let base = scratch();
+        let folder = base.join("skills").join("pdf");
+        fs::create_dir_all(folder.join("scripts")).unwrap();
+        fs::write(folder.join("SKILL.md"), "Body").unwrap();
+        fs::write(folder.join("scripts").join("fill.py"), "print()").unwrap();
+
+        let trashed = move_to_trash(&folder, &base.join("trash"), "pdf").unwrap();
+        let script = fs::read_to_string(trashed.join("scripts").join("fill.py")).unwrap();
+        let folder_left = folder.exists();
+        let _ = fs::remove_dir_all(&base);
+
+        assert!(trashed.starts_with(base.join("trash")));
+        assert_eq!(script, "print()");
+        assert!(!folder_left);
+    }
+}
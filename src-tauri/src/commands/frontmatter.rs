@@ -0,0 +1,316 @@
+//! YAML frontmatter of command, agent and skill markdown files
+//!
+//! ```markdown
+//! ---
+//! description: Review the staged changes
+//! allowed-tools: Bash(git diff:*), Read
+//! ---
+//!
+//! Body...
+//! ```
+
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+
+/// Fields Claude reads from frontmatter
+///
+/// Keys not modelled here are kept in `extra`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Frontmatter {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Tools an agent may use
+    #[serde(
+        default,
+        deserialize_with = "tool_list",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub tools: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// Tools a command or skill may use without asking
+    #[serde(
+        rename = "allowed-tools",
+        default,
+        deserialize_with = "tool_list",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub allowed_tools: Option<Vec<String>>,
+    #[serde(
+        rename = "argument-hint",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub argument_hint: Option<String>,
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ToolListRepr {
+    List(Vec<String>),
+    Text(String),
+}
+
+/// Accept tools either as a YAML list or as a comma-separated string
+fn tool_list<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Vec<String>>, D::Error> {
+    Ok(
+        Option::<ToolListRepr>::deserialize(deserializer)?.map(|repr| match repr {
+            ToolListRepr::List(tools) => tools,
+            ToolListRepr::Text(text) => split_tools(&text),
+        }),
+    )
+}
+
+/// Split `Bash(git add:*), Read` on the commas outside parentheses
+fn split_tools(text: &str) -> Vec<String> {
+    let mut tools = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0usize;
+
+    for c in text.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                tools.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    tools.push(current);
+
+    tools
+        .into_iter()
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+/// Read frontmatter as plain `key: value` lines when the YAML is invalid
+///
+/// Hand-written frontmatter often has unquoted colons in values, such as
+/// `description: Use when: reviewing`, which strict YAML rejects. Indented
+/// lines continue the previous value. Values opening a flow collection or a
+/// quoted string are left to the YAML error.
+fn parse_lines(yaml: &str) -> Option<Frontmatter> {
+    let mut map = serde_json::Map::new();
+    let mut last: Option<String> = None;
+
+    for line in yaml.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        if line.starts_with([' ', '\t']) {
+            let value = map.get_mut(last.as_ref()?)?;
+            let joined = match value.as_str()? {
+                "" => trimmed.to_string(),
+                text => format!("{} {}", text, trimmed),
+            };
+            *value = joined.into();
+            continue;
+        }
+
+        let (key, value) = line.split_once(':')?;
+        let valid_key = !key.is_empty()
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        let value = value.trim();
+        if !valid_key || value.starts_with(['[', '{', '"', '\'']) {
+            return None;
+        }
+        map.insert(key.to_string(), value.into());
+        last = Some(key.to_string());
+    }
+
+    serde_json::from_value(map.into()).ok()
+}
+
+/// Split markdown into its frontmatter and body
+///
+/// Content without a leading `---` line has empty frontmatter. An
+/// unterminated block is an error, as is YAML that is invalid and cannot be
+/// read as plain `key: value` lines either.
+pub fn parse_frontmatter(content: &str) -> Result<(Frontmatter, String), String> {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let mut lines = content.split_inclusive('\n');
+
+    match lines.next() {
+        Some(first) if first.trim_end() == "---" => {}
+        _ => return Ok((Frontmatter::default(), content.to_string())),
+    }
+
+    let mut yaml = String::new();
+    let mut closed = false;
+    for line in lines.by_ref() {
+        if line.trim_end() == "---" {
+            closed = true;
+            break;
+        }
+        yaml.push_str(line);
+    }
+    if !closed {
+        return Err("Frontmatter is not closed with '---'".to_string());
+    }
+
+    let body = lines.collect::<String>().trim().to_string();
+    if yaml.trim().is_empty() {
+        return Ok((Frontmatter::default(), body));
+    }
+
+    match serde_yaml::from_str(&yaml) {
+        Ok(frontmatter) => Ok((frontmatter, body)),
+        Err(e) => match parse_lines(&yaml) {
+            Some(frontmatter) => Ok((frontmatter, body)),
+            None => Err(format!("Invalid frontmatter: {}", e)),
+        },
+    }
+}
+
+/// Render frontmatter and body back into a markdown file
+///
+/// Tool lists are written as comma-separated strings, the form Claude's own
+/// files use. Empty frontmatter is omitted.
+pub fn render_markdown(frontmatter: &Frontmatter, body: &str) -> Result<String, String> {
+    let body = body.trim();
+    let mut value = serde_yaml::to_value(frontmatter)
+        .map_err(|e| format!("Failed to serialize frontmatter: {}", e))?;
+
+    if let serde_yaml::Value::Mapping(map) = &mut value {
+        if map.is_empty() {
+            return Ok(format!("{}\n", body));
+        }
+        for key in ["tools", "allowed-tools"] {
+            let joined = match map.get(key) {
+                Some(serde_yaml::Value::Sequence(items)) => items
+                    .iter()
+                    .filter_map(|i| i.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                _ => continue,
+            };
+            map.insert(key.into(), joined.into());
+        }
+    }
+
+    let yaml = serde_yaml::to_string(&value)
+        .map_err(|e| format!("Failed to serialize frontmatter: {}", e))?;
+    Ok(format!("---\n{}---\n\n{}\n", yaml, body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_frontmatter_fields() {
+        let content = "---\n\
+name: reviewer\n\
+description: >\n  Reviews code: style,\n  tests and docs\n\
+tools: Read, Grep, Bash(git log:*, git diff:*)\n\
+model: sonnet\n\
+color: blue\n\
+priority: 3\n\
+---\n\nYou review code.\n";
+
+        let (frontmatter, body) = parse_frontmatter(content).unwrap();
+        assert_eq!(frontmatter.name.as_deref(), Some("reviewer"));
+        assert_eq!(
+            frontmatter.description.as_deref(),
+            Some("Reviews code: style, tests and docs\n")
+        );
+        assert_eq!(
+            frontmatter.tools.unwrap(),
+            ["Read", "Grep", "Bash(git log:*, git diff:*)"]
+        );
+        assert_eq!(frontmatter.color.as_deref(), Some("blue"));
+        assert_eq!(frontmatter.extra["priority"], 3);
+        assert_eq!(body, "You review code.");
+    }
+
+    #[test]
+    fn test_parse_frontmatter_command_keys() {
+        let content = "---\n\
+description: \"Commit: staged changes\"\n\
+allowed-tools:\n  - Bash(git commit:*)\n  - Read\n\
+argument-hint: \"[message]\"\n\
+---\nCommit with $ARGUMENTS\n";
+
+        let (frontmatter, body) = parse_frontmatter(content).unwrap();
+        assert_eq!(
+            frontmatter.description.as_deref(),
+            Some("Commit: staged changes")
+        );
+        assert_eq!(
+            frontmatter.allowed_tools.unwrap(),
+            ["Bash(git commit:*)", "Read"]
+        );
+        assert_eq!(frontmatter.argument_hint.as_deref(), Some("[message]"));
+        assert_eq!(body, "Commit with $ARGUMENTS");
+    }
+
+    #[test]
+    fn test_parse_frontmatter_unquoted_colons() {
+        let content = "---\n\
+name: pdf-tools\n\
+description: Use when: the user mentions PDFs, forms\n  or scanned documents\n\
+allowed-tools: Bash(pdftotext:*), Read\n\
+---\nExtract text.\n";
+
+        let (frontmatter, body) = parse_frontmatter(content).unwrap();
+        assert_eq!(frontmatter.name.as_deref(), Some("pdf-tools"));
+        assert_eq!(
+            frontmatter.description.as_deref(),
+            Some("Use when: the user mentions PDFs, forms or scanned documents")
+        );
+        assert_eq!(
+            frontmatter.allowed_tools.unwrap(),
+            ["Bash(pdftotext:*)", "Read"]
+        );
+        assert_eq!(body, "Extract text.");
+    }
+
+    #[test]
+    fn test_render_markdown_round_trip() {
+        let frontmatter = Frontmatter {
+            name: Some("reviewer".to_string()),
+            description: Some("Reviews code: carefully".to_string()),
+            tools: Some(vec!["Read".to_string(), "Bash(git diff:*)".to_string()]),
+            ..Default::default()
+        };
+
+        let rendered = render_markdown(&frontmatter, "You review code.\n").unwrap();
+        assert!(rendered.starts_with("---\n"));
+        assert!(rendered.contains("tools: Read, Bash(git diff:*)\n"));
+        assert!(rendered.ends_with("---\n\nYou review code.\n"));
+
+        let (parsed, body) = parse_frontmatter(&rendered).unwrap();
+        assert_eq!(parsed, frontmatter);
+        assert_eq!(body, "You review code.");
+
+        assert_eq!(
+            render_markdown(&Frontmatter::default(), "Plain").unwrap(),
+            "Plain\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_frontmatter_errors() {
+        assert!(parse_frontmatter("---\ndescription: x\n").is_err());
+        assert!(parse_frontmatter("---\ndescription: [unclosed\n---\nbody").is_err());
+
+        let (frontmatter, body) = parse_frontmatter("No frontmatter --- here").unwrap();
+        assert_eq!(frontmatter, Frontmatter::default());
+        assert_eq!(body, "No frontmatter --- here");
+    }
+}
@@ -0,0 +1,689 @@
+//! Hooks management
+//!
+//! Typed access to the `hooks` section of Claude settings files:
+//!
+//! ```json
+//! "hooks": {
+//!   "PreToolUse": [
+//!     { "matcher": "Bash", "hooks": [{ "type": "command", "command": "./check.sh" }] }
+//!   ]
+//! }
+//! ```
+//!
+//! Hooks are addressed by scope, event, the index of their matcher group and
+//! their index within that group.
+
+use super::mcp_health::kill_group;
+use super::settings::{self, SettingsScope};
+use super::CommandResult;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tauri::command;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Timeout Claude applies to hooks that don't set one, in seconds
+const DEFAULT_HOOK_TIMEOUT_SECS: u64 = 60;
+
+/// Lifecycle events hooks can attach to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HookEvent {
+    PreToolUse,
+    PostToolUse,
+    Notification,
+    UserPromptSubmit,
+    Stop,
+    SubagentStop,
+    PreCompact,
+    SessionStart,
+    SessionEnd,
+}
+
+impl HookEvent {
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(name.to_string())).ok()
+    }
+
+    fn name(self) -> String {
+        format!("{:?}", self)
+    }
+
+    /// Fixed matcher values for events that match on something other than tools
+    fn matcher_values(self) -> Option<&'static [&'static str]> {
+        match self {
+            Self::PreCompact => Some(&["manual", "auto"]),
+            Self::SessionStart => Some(&["startup", "resume", "clear", "compact"]),
+            _ => None,
+        }
+    }
+
+    fn matches_tools(self) -> bool {
+        matches!(self, Self::PreToolUse | Self::PostToolUse)
+    }
+}
+
+/// A single hook command
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HookCommand {
+    /// Hook type: `command` runs a shell command, `prompt` asks the model
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// Shell command of a `command` hook
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    /// Prompt of a `prompt` hook
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    /// Seconds before Claude kills the hook
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    /// Keys not modelled here, kept as they are
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Hooks that run when an event's matcher matches
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HookMatcher {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matcher: Option<String>,
+    pub hooks: Vec<HookCommand>,
+    /// Keys not modelled here, kept as they are
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// The `hooks` section, keyed by event name
+pub type HooksConfig = BTreeMap<String, Vec<HookMatcher>>;
+
+/// A hook together with where it is configured
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HookEntry {
+    pub scope: SettingsScope,
+    pub event: HookEvent,
+    pub matcher: Option<String>,
+    /// Index of the matcher group within the event
+    pub index: usize,
+    /// Index of the hook within its matcher group
+    pub hook_index: usize,
+    pub hook: HookCommand,
+}
+
+/// Output of a hook dry run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HookTestResult {
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+    /// Exit code 2 blocks the action and feeds stderr back to Claude
+    pub blocking: bool,
+    /// Stdout parsed as JSON, for hooks that return structured decisions
+    pub output_json: Option<serde_json::Value>,
+    pub timed_out: bool,
+    pub duration_ms: u64,
+    /// Payload that was written to the hook's stdin
+    pub payload: serde_json::Value,
+}
+
+fn normalize_matcher(matcher: Option<String>) -> Option<String> {
+    matcher
+        .map(|m| m.trim().to_string())
+        .filter(|m| !m.is_empty())
+}
+
+/// Check a hook before it is written to a settings file
+pub(crate) fn validate_hook(
+    event: HookEvent,
+    matcher: Option<&str>,
+    hook: &HookCommand,
+) -> Result<(), String> {
+    let is_blank = |text: &Option<String>| text.as_deref().map_or(true, |t| t.trim().is_empty());
+    match hook.kind.as_str() {
+        "command" if is_blank(&hook.command) => {
+            return Err("Hook command cannot be empty".to_string())
+        }
+        "prompt" if is_blank(&hook.prompt) => return Err("Hook prompt cannot be empty".to_string()),
+        "command" | "prompt" => {}
+        kind => return Err(format!("Unsupported hook type: {}", kind)),
+    }
+    if hook.timeout == Some(0) {
+        return Err("Hook timeout must be at least one second".to_string());
+    }
+
+    let Some(matcher) = matcher else {
+        return Ok(());
+    };
+
+    if event.matches_tools() {
+        if matcher != "*" {
+            regex::Regex::new(matcher)
+                .map_err(|e| format!("Invalid matcher pattern '{}': {}", matcher, e))?;
+        }
+        Ok(())
+    } else if let Some(values) = event.matcher_values() {
+        if values.contains(&matcher) {
+            Ok(())
+        } else {
+            Err(format!(
+                "{} matcher must be one of: {}",
+                event.name(),
+                values.join(", ")
+            ))
+        }
+    } else {
+        Err(format!("{} hooks do not take a matcher", event.name()))
+    }
+}
+
+fn parse_hooks(settings: &serde_json::Value) -> Result<HooksConfig, String> {
+    match settings.get("hooks") {
+        Some(hooks) => serde_json::from_value(hooks.clone())
+            .map_err(|e| format!("Invalid hooks configuration: {}", e)),
+        None => Ok(HooksConfig::new()),
+    }
+}
+
+/// Load a scope's hooks, modify them and write the settings back
+///
+/// Empty matcher groups and events are removed, as is an empty `hooks` key.
+fn update_hooks<T>(
+    scope: SettingsScope,
+    project_path: Option<&str>,
+    f: impl FnOnce(&mut HooksConfig) -> Result<T, String>,
+) -> Result<T, String> {
+    let path = settings::settings_path(scope, project_path)?;
+    let mut settings = settings::read_settings(&path)?;
+    let mut hooks = parse_hooks(&settings)?;
+
+    let result = f(&mut hooks)?;
+
+    for groups in hooks.values_mut() {
+        groups.retain(|g| !g.hooks.is_empty());
+    }
+    hooks.retain(|_, groups| !groups.is_empty());
+
+    let object = settings
+        .as_object_mut()
+        .ok_or_else(|| format!("Settings in {} are not an object", path.display()))?;
+    if hooks.is_empty() {
+        object.remove("hooks");
+    } else {
+        let value = serde_json::to_value(&hooks)
+            .map_err(|e| format!("Failed to serialize hooks: {}", e))?;
+        object.insert("hooks".to_string(), value);
+    }
+
+    settings::write_settings(&path, &settings)?;
+    Ok(result)
+}
+
+/// Append a hook to the group with the same matcher, creating it if needed
+fn insert_hook(
+    hooks: &mut HooksConfig,
+    scope: SettingsScope,
+    event: HookEvent,
+    matcher: Option<String>,
+    hook: HookCommand,
+) -> HookEntry {
+    let groups = hooks.entry(event.name()).or_default();
+    let index = match groups.iter().position(|g| g.matcher == matcher) {
+        Some(index) => index,
+        None => {
+            groups.push(HookMatcher {
+                matcher: matcher.clone(),
+                hooks: vec![],
+                extra: Map::new(),
+            });
+            groups.len() - 1
+        }
+    };
+
+    groups[index].hooks.push(hook.clone());
+    HookEntry {
+        scope,
+        event,
+        matcher,
+        index,
+        hook_index: groups[index].hooks.len() - 1,
+        hook,
+    }
+}
+
+fn hook_mut(
+    hooks: &mut HooksConfig,
+    event: HookEvent,
+    index: usize,
+    hook_index: usize,
+) -> Result<(&mut HookMatcher, usize), String> {
+    hooks
+        .get_mut(&event.name())
+        .and_then(|groups| groups.get_mut(index))
+        .filter(|group| hook_index < group.hooks.len())
+        .map(|group| (group, hook_index))
+        .ok_or_else(|| format!("Hook not found: {} #{}.{}", event.name(), index, hook_index))
+}
+
+/// Sample stdin payload Claude would send for an event
+fn sample_payload(event: HookEvent, cwd: &str) -> serde_json::Value {
+    let mut payload = serde_json::json!({
+        "session_id": "00000000-0000-0000-0000-000000000000",
+        "transcript_path": "",
+        "cwd": cwd,
+        "hook_event_name": event.name(),
+    });
+
+    let extra = match event {
+        HookEvent::PreToolUse => serde_json::json!({
+            "tool_name": "Bash",
+            "tool_input": { "command": "echo hello", "description": "Print hello" },
+        }),
+        HookEvent::PostToolUse => serde_json::json!({
+            "tool_name": "Bash",
+            "tool_input": { "command": "echo hello", "description": "Print hello" },
+            "tool_response": { "stdout": "hello\n", "stderr": "", "interrupted": false },
+        }),
+        HookEvent::Notification => serde_json::json!({
+            "message": "Claude needs your permission to use Bash",
+        }),
+        HookEvent::UserPromptSubmit => serde_json::json!({
+            "prompt": "Summarize the recent changes",
+        }),
+        HookEvent::Stop | HookEvent::SubagentStop => serde_json::json!({
+            "stop_hook_active": false,
+        }),
+        HookEvent::PreCompact => serde_json::json!({
+            "trigger": "manual",
+            "custom_instructions": "",
+        }),
+        HookEvent::SessionStart => serde_json::json!({ "source": "startup" }),
+        HookEvent::SessionEnd => serde_json::json!({ "reason": "exit" }),
+    };
+
+    if let (Some(payload), Some(extra)) = (payload.as_object_mut(), extra.as_object()) {
+        payload.extend(extra.clone());
+    }
+    payload
+}
+
+fn shell_command(command: &str) -> Command {
+    #[cfg(windows)]
+    {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C").arg(command);
+        cmd
+    }
+    #[cfg(not(windows))]
+    {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(command);
+        cmd
+    }
+}
+
+/// List hooks from user settings and, with a project, its project and local settings
+#[command]
+pub async fn list_hooks(project_path: Option<String>) -> CommandResult<Vec<HookEntry>> {
+    let mut entries = Vec::new();
+
+    for scope in SettingsScope::ALL {
+        if scope != SettingsScope::User && project_path.is_none() {
+            continue;
+        }
+
+        let hooks = match settings::settings_path(scope, project_path.as_deref())
+            .and_then(|path| settings::read_settings(&path))
+            .and_then(|settings| parse_hooks(&settings))
+        {
+            Ok(h) => h,
+            Err(e) => return CommandResult::err(e),
+        };
+
+        for (name, groups) in hooks {
+            // Unknown events are kept in the file but not listed
+            let Some(event) = HookEvent::from_name(&name) else {
+                continue;
+            };
+            for (index, group) in groups.into_iter().enumerate() {
+                for (hook_index, hook) in group.hooks.into_iter().enumerate() {
+                    entries.push(HookEntry {
+                        scope,
+                        event,
+                        matcher: group.matcher.clone(),
+                        index,
+                        hook_index,
+                        hook,
+                    });
+                }
+            }
+        }
+    }
+
+    CommandResult::ok(entries)
+}
+
+/// Add a hook at a scope
+#[command]
+pub async fn add_hook(
+    scope: SettingsScope,
+    project_path: Option<String>,
+    event: HookEvent,
+    matcher: Option<String>,
+    hook: HookCommand,
+) -> CommandResult<HookEntry> {
+    let matcher = normalize_matcher(matcher);
+    if let Err(e) = validate_hook(event, matcher.as_deref(), &hook) {
+        return CommandResult::err(e);
+    }
+
+    match update_hooks(scope, project_path.as_deref(), |hooks| {
+        Ok(insert_hook(hooks, scope, event, matcher, hook))
+    }) {
+        Ok(entry) => CommandResult::ok(entry),
+        Err(e) => CommandResult::err(e),
+    }
+}
+
+/// Replace a hook, moving it to another matcher group if the matcher changed
+#[command]
+pub async fn update_hook(
+    scope: SettingsScope,
+    project_path: Option<String>,
+    event: HookEvent,
+    index: usize,
+    hook_index: usize,
+    matcher: Option<String>,
+    hook: HookCommand,
+) -> CommandResult<HookEntry> {
+    let matcher = normalize_matcher(matcher);
+    if let Err(e) = validate_hook(event, matcher.as_deref(), &hook) {
+        return CommandResult::err(e);
+    }
+
+    let result = update_hooks(scope, project_path.as_deref(), |hooks| {
+        let (group, hook_index) = hook_mut(hooks, event, index, hook_index)?;
+        if group.matcher == matcher {
+            group.hooks[hook_index] = hook.clone();
+            return Ok(HookEntry {
+                scope,
+                event,
+                matcher,
+                index,
+                hook_index,
+                hook,
+            });
+        }
+
+        group.hooks.remove(hook_index);
+        let moved = insert_hook(hooks, scope, event, matcher, hook);
+        // The emptied group is pruned on save, shifting later groups down
+        let emptied = hooks[&event.name()][index].hooks.is_empty();
+        Ok(HookEntry {
+            index: if emptied && moved.index > index {
+                moved.index - 1
+            } else {
+                moved.index
+            },
+            ..moved
+        })
+    });
+
+    match result {
+        Ok(entry) => CommandResult::ok(entry),
+        Err(e) => CommandResult::err(e),
+    }
+}
+
+/// Remove a hook at a scope
+#[command]
+pub async fn remove_hook(
+    scope: SettingsScope,
+    project_path: Option<String>,
+    event: HookEvent,
+    index: usize,
+    hook_index: usize,
+) -> CommandResult<()> {
+    match update_hooks(scope, project_path.as_deref(), |hooks| {
+        let (group, hook_index) = hook_mut(hooks, event, index, hook_index)?;
+        group.hooks.remove(hook_index);
+        Ok(())
+    }) {
+        Ok(()) => CommandResult::ok(()),
+        Err(e) => CommandResult::err(e),
+    }
+}
+
+/// Run a hook once with a sample payload on stdin and report its output
+///
+/// Nothing is written to settings. `payload` overrides the sample payload
+/// for the event.
+#[command]
+pub async fn test_hook(
+    event: HookEvent,
+    hook: HookCommand,
+    payload: Option<serde_json::Value>,
+    project_path: Option<String>,
+) -> CommandResult<HookTestResult> {
+    if let Err(e) = validate_hook(event, None, &hook) {
+        return CommandResult::err(e);
+    }
+    let Some(command) = hook.command.as_deref().filter(|_| hook.kind == "command") else {
+        return CommandResult::err("Only command hooks can be run".to_string());
+    };
+
+    let cwd = project_path.clone().unwrap_or_else(|| {
+        std::env::current_dir()
+            .map(|d| d.to_string_lossy().to_string())
+            .unwrap_or_default()
+    });
+    let payload = payload.unwrap_or_else(|| sample_payload(event, &cwd));
+
+    let mut cmd = shell_command(command);
+    cmd.stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    // Lead a new process group so a timeout also stops what the hook starts
+    #[cfg(unix)]
+    cmd.process_group(0);
+    if let Some(project) = &project_path {
+        cmd.current_dir(project).env("CLAUDE_PROJECT_DIR", project);
+    }
+
+    let started = Instant::now();
+    let mut child = match cmd.spawn() {
+        Ok(c) => c,
+        Err(e) => return CommandResult::err(format!("Failed to run hook: {}", e)),
+    };
+    let group = child.id();
+
+    if let Some(mut stdin) = child.stdin.take() {
+        let input = payload.to_string();
+        // Hooks that ignore stdin may exit before reading it
+        let _ = stdin.write_all(input.as_bytes()).await;
+    }
+
+    let timeout = Duration::from_secs(hook.timeout.unwrap_or(DEFAULT_HOOK_TIMEOUT_SECS));
+    let (output, timed_out) = match tokio::time::timeout(timeout, child.wait_with_output()).await {
+        Ok(Ok(output)) => (Some(output), false),
+        Ok(Err(e)) => return CommandResult::err(format!("Failed to run hook: {}", e)),
+        Err(_) => {
+            kill_group(group);
+            (None, true)
+        }
+    };
+
+    let stdout = output
+        .as_ref()
+        .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
+        .unwrap_or_default();
+    let exit_code = output.as_ref().and_then(|o| o.status.code());
+
+    CommandResult::ok(HookTestResult {
+        exit_code,
+        stderr: output
+            .as_ref()
+            .map(|o| String::from_utf8_lossy(&o.stderr).to_string())
+            .unwrap_or_default(),
+        blocking: exit_code == Some(2),
+        output_json: serde_json::from_str(stdout.trim()).ok(),
+        stdout,
+        timed_out,
+        duration_ms: started.elapsed().as_millis() as u64,
+        payload,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hook(command: &str) -> HookCommand {
+        HookCommand {
+            kind: "command".to_string(),
+            command: Some(command.to_string()),
+            prompt: None,
+            timeout: None,
+            extra: Map::new(),
+        }
+    }
+
+    #[test]
+    fn test_validate_hook() {
+        let ok = hook("./check.sh");
+        assert!(validate_hook(HookEvent::PreToolUse, Some("Edit|Write"), &ok).is_ok());
+        assert!(validate_hook(HookEvent::PreToolUse, Some("*"), &ok).is_ok());
+        assert!(validate_hook(HookEvent::PreToolUse, Some("Edit("), &ok).is_err());
+        assert!(validate_hook(HookEvent::SessionStart, Some("resume"), &ok).is_ok());
+        assert!(validate_hook(HookEvent::SessionStart, Some("Bash"), &ok).is_err());
+        assert!(validate_hook(HookEvent::Stop, Some("Bash"), &ok).is_err());
+        assert!(validate_hook(HookEvent::Stop, None, &hook("  ")).is_err());
+
+        let mut prompt = hook("");
+        prompt.kind = "prompt".to_string();
+        prompt.command = None;
+        assert!(validate_hook(HookEvent::Stop, None, &prompt).is_err());
+        prompt.prompt = Some("Check that the task is finished".to_string());
+        assert!(validate_hook(HookEvent::Stop, None, &prompt).is_ok());
+        prompt.kind = "agent".to_string();
+        assert!(validate_hook(HookEvent::Stop, None, &prompt).is_err());
+    }
+
+    #[test]
+    fn test_hooks_config_round_trip() {
+        let settings = serde_json::json!({
+            "model": "opus",
+            "hooks": {
+                "PreToolUse": [{
+                    "matcher": "Bash",
+                    "hooks": [{ "type": "command", "command": "./check.sh", "timeout": 5, "async": true }],
+                    "description": "lint shell commands"
+                }],
+                "Stop": [{ "hooks": [
+                    { "type": "command", "command": "notify-send done" },
+                    { "type": "prompt", "prompt": "Is the task finished?" }
+                ] }]
+            }
+        });
+
+        let mut hooks = parse_hooks(&settings).unwrap();
+        assert_eq!(hooks["PreToolUse"][0].hooks[0].timeout, Some(5));
+        assert_eq!(hooks["Stop"][0].matcher, None);
+        assert_eq!(hooks["Stop"][0].hooks[1].command, None);
+
+        let entry = insert_hook(
+            &mut hooks,
+            SettingsScope::User,
+            HookEvent::PreToolUse,
+            Some("Bash".to_string()),
+            hook("./lint.sh"),
+        );
+        assert_eq!((entry.index, entry.hook_index), (0, 1));
+
+        let value = serde_json::to_value(&hooks).unwrap();
+        assert_eq!(value["Stop"][0], settings["hooks"]["Stop"][0]);
+        assert_eq!(
+            value["PreToolUse"][0]["hooks"][0],
+            settings["hooks"]["PreToolUse"][0]["hooks"][0]
+        );
+        assert_eq!(value["PreToolUse"][0]["description"], "lint shell commands");
+        assert_eq!(value["PreToolUse"][0]["hooks"][1]["command"], "./lint.sh");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_test_hook_reads_payload() {
+        let result = test_hook(
+            HookEvent::PreToolUse,
+            hook("cat; echo blocked >&2; exit 2"),
+            None,
+            None,
+        )
+        .await;
+
+        let result = result.data.unwrap();
+        assert!(result.blocking);
+        assert_eq!(result.stderr.trim(), "blocked");
+        let echoed = result.output_json.unwrap();
+        assert_eq!(echoed["hook_event_name"], "PreToolUse");
+        assert_eq!(echoed["tool_name"], "Bash");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_test_hook_timeout_kills_process_group() {
+        let pid_file =
+            std::env::temp_dir().join(format!("codepod-hook-{}.pid", uuid::Uuid::new_v4()));
+        let mut slow = hook(&format!(
+            "sleep 30 & echo $! > '{}'; wait",
+            pid_file.display()
+        ));
+        slow.timeout = Some(1);
+
+        let result = test_hook(HookEvent::Stop, slow, None, None)
+            .await
+            .data
+            .unwrap();
+        assert!(result.timed_out);
+
+        // The `sleep` the hook started was killed along with it
+        let pid = std::fs::read_to_string(&pid_file)
+            .unwrap()
+            .trim()
+            .to_string();
+        let _ = std::fs::remove_file(&pid_file);
+        let running = || {
+            let ps = std::process::Command::new("ps")
+                .args(["-o", "stat=", "-p", &pid])
+                .output()
+                .unwrap();
+            // Orphans may linger as zombies until init reaps them
+            let state = String::from_utf8_lossy(&ps.stdout);
+            !state.trim().is_empty() && !state.trim().starts_with('Z')
+        };
+        let mut alive = true;
+        for _ in 0..50 {
+            if !running() {
+                alive = false;
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert!(!alive, "sleep {} is still running", pid);
+    }
+
+    #[tokio::test]
+    async fn test_test_hook_rejects_prompt_hooks() {
+        let mut prompt = hook("");
+        prompt.kind = "prompt".to_string();
+        prompt.command = None;
+        prompt.prompt = Some("Is the task finished?".to_string());
+        let result = test_hook(HookEvent::Stop, prompt, None, None).await;
+        assert_eq!(
+            result.error.as_deref(),
+            Some("Only command hooks can be run")
+        );
+    }
+}
@@ -0,0 +1,528 @@
+//! Claude job queue
+//!
+//! Bounds how many Claude CLI processes run at once, both globally and per
+//! project directory; jobs without a directory only count against the global
+//! limit. Jobs wait in priority order until a slot frees up, and
+//! every state change is emitted to the frontend as a `claude-job` event.
+
+use super::CommandResult;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{command, AppHandle, Emitter};
+use tokio::sync::{oneshot, Notify};
+
+/// Number of finished jobs kept around for `list_claude_jobs`
+const MAX_FINISHED_JOBS: usize = 100;
+
+/// Lifecycle state of a job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobState {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl JobState {
+    pub fn is_finished(self) -> bool {
+        matches!(
+            self,
+            JobState::Completed | JobState::Failed | JobState::Cancelled
+        )
+    }
+}
+
+/// Concurrency limits applied by the queue
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct JobLimits {
+    pub global: usize,
+    pub per_project: usize,
+}
+
+impl Default for JobLimits {
+    fn default() -> Self {
+        Self {
+            global: 4,
+            per_project: 2,
+        }
+    }
+}
+
+/// Job information sent to frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobInfo {
+    pub id: String,
+    pub project: Option<String>,
+    /// Claude session the job is running, once known
+    pub session_id: Option<String>,
+    pub priority: i32,
+    pub state: JobState,
+    pub queued_at: u64,
+    pub started_at: Option<u64>,
+    pub finished_at: Option<u64>,
+    pub error: Option<String>,
+}
+
+struct JobEntry {
+    info: JobInfo,
+    seq: u64,
+    start: Option<oneshot::Sender<()>>,
+    cancel: Arc<Notify>,
+    /// Why the job was asked to stop, when not by the user
+    cancel_reason: Option<String>,
+}
+
+#[derive(Default)]
+struct JobQueue {
+    limits: JobLimits,
+    jobs: HashMap<String, JobEntry>,
+    next_seq: u64,
+}
+
+// Global job queue
+lazy_static::lazy_static! {
+    static ref JOB_QUEUE: Arc<Mutex<JobQueue>> = Arc::new(Mutex::new(JobQueue::default()));
+}
+
+pub(crate) fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+impl JobQueue {
+    fn running(&self) -> impl Iterator<Item = &JobInfo> {
+        self.jobs
+            .values()
+            .map(|j| &j.info)
+            .filter(|info| info.state == JobState::Running)
+    }
+
+    fn push(
+        &mut self,
+        id: &str,
+        project: Option<String>,
+        priority: i32,
+    ) -> Result<(JobInfo, oneshot::Receiver<()>, Arc<Notify>), String> {
+        if self
+            .jobs
+            .get(id)
+            .is_some_and(|j| !j.info.state.is_finished())
+        {
+            return Err(format!("Job already active: {}", id));
+        }
+
+        let (tx, rx) = oneshot::channel();
+        let cancel = Arc::new(Notify::new());
+        let info = JobInfo {
+            id: id.to_string(),
+            project,
+            session_id: None,
+            priority,
+            state: JobState::Queued,
+            queued_at: now_millis(),
+            started_at: None,
+            finished_at: None,
+            error: None,
+        };
+
+        self.next_seq += 1;
+        self.jobs.insert(
+            id.to_string(),
+            JobEntry {
+                info: info.clone(),
+                seq: self.next_seq,
+                start: Some(tx),
+                cancel: cancel.clone(),
+                cancel_reason: None,
+            },
+        );
+
+        Ok((info, rx, cancel))
+    }
+
+    /// Start as many queued jobs as the limits allow, highest priority first
+    fn schedule(&mut self) -> Vec<JobInfo> {
+        let mut queued: Vec<(i32, u64, String)> = self
+            .jobs
+            .values()
+            .filter(|j| j.info.state == JobState::Queued)
+            .map(|j| (j.info.priority, j.seq, j.info.id.clone()))
+            .collect();
+        queued.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+
+        let mut changed = Vec::new();
+
+        for (_, _, id) in queued {
+            if self.running().count() >= self.limits.global {
+                break;
+            }
+
+            if let Some(project) = &self.jobs[&id].info.project {
+                let project_running = self
+                    .running()
+                    .filter(|j| j.project.as_ref() == Some(project))
+                    .count();
+                if project_running >= self.limits.per_project {
+                    continue;
+                }
+            }
+
+            let entry = self.jobs.get_mut(&id).expect("queued job exists");
+            let started = entry.start.take().is_some_and(|tx| tx.send(()).is_ok());
+
+            if started {
+                entry.info.state = JobState::Running;
+                entry.info.started_at = Some(now_millis());
+            } else {
+                // The waiting side went away before the job could start
+                entry.info.state = JobState::Cancelled;
+                entry.info.finished_at = Some(now_millis());
+            }
+            changed.push(entry.info.clone());
+        }
+
+        changed
+    }
+
+    fn finish(&mut self, id: &str, state: JobState, error: Option<String>) -> Option<JobInfo> {
+        let entry = self.jobs.get_mut(id)?;
+        if entry.info.state.is_finished() {
+            return None;
+        }
+
+        entry.start = None;
+        entry.info.state = state;
+        entry.info.error = error;
+        entry.info.finished_at = Some(now_millis());
+        let info = entry.info.clone();
+
+        self.prune();
+        Some(info)
+    }
+
+    /// Drop the oldest finished jobs beyond `MAX_FINISHED_JOBS`
+    fn prune(&mut self) {
+        let mut finished: Vec<(u64, String)> = self
+            .jobs
+            .values()
+            .filter(|j| j.info.state.is_finished())
+            .map(|j| (j.seq, j.info.id.clone()))
+            .collect();
+
+        if finished.len() <= MAX_FINISHED_JOBS {
+            return;
+        }
+
+        finished.sort();
+        let excess = finished.len() - MAX_FINISHED_JOBS;
+        for (_, id) in finished.into_iter().take(excess) {
+            self.jobs.remove(&id);
+        }
+    }
+
+    fn list(&self) -> Vec<JobInfo> {
+        let mut entries: Vec<&JobEntry> = self.jobs.values().collect();
+        entries.sort_by_key(|j| j.seq);
+        entries.into_iter().map(|j| j.info.clone()).collect()
+    }
+}
+
+fn emit_jobs(app: &AppHandle, jobs: &[JobInfo]) {
+    for job in jobs {
+        let _ = app.emit("claude-job", job);
+    }
+}
+
+/// A queued job waiting for a free slot
+pub struct JobTicket {
+    app: AppHandle,
+    id: String,
+    start: oneshot::Receiver<()>,
+    cancel: Arc<Notify>,
+}
+
+/// A running job; finishing it frees its slot for the next queued job
+pub struct JobGuard {
+    app: AppHandle,
+    id: String,
+    cancel: Arc<Notify>,
+    finished: bool,
+}
+
+/// Add a job to the queue
+pub fn enqueue(
+    app: &AppHandle,
+    id: &str,
+    project: Option<String>,
+    priority: i32,
+) -> Result<JobTicket, String> {
+    let (changed, start, cancel) = {
+        let mut queue = JOB_QUEUE.lock();
+        let (info, start, cancel) = queue.push(id, project, priority)?;
+        let mut changed = vec![info];
+        changed.extend(queue.schedule());
+        (changed, start, cancel)
+    };
+
+    emit_jobs(app, &changed);
+
+    Ok(JobTicket {
+        app: app.clone(),
+        id: id.to_string(),
+        start,
+        cancel,
+    })
+}
+
+impl JobTicket {
+    /// Wait until the queue starts this job
+    pub async fn wait(self) -> Result<JobGuard, String> {
+        self.start
+            .await
+            .map_err(|_| format!("Job cancelled: {}", self.id))?;
+
+        Ok(JobGuard {
+            app: self.app,
+            id: self.id,
+            cancel: self.cancel,
+            finished: false,
+        })
+    }
+}
+
+impl JobGuard {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Record the Claude session this job is running
+    pub fn set_session(&self, session_id: &str) {
+        if let Some(entry) = JOB_QUEUE.lock().jobs.get_mut(&self.id) {
+            entry.info.session_id = Some(session_id.to_string());
+        }
+    }
+
+    /// Resolves once `cancel_claude_job` is called for this job
+    pub async fn cancelled(&self) {
+        self.cancel.notified().await
+    }
+
+    /// Error to report for a cancelled job, with the reason if one was given
+    pub fn cancel_message(&self) -> String {
+        JOB_QUEUE
+            .lock()
+            .jobs
+            .get(&self.id)
+            .and_then(|entry| entry.cancel_reason.clone())
+            .unwrap_or_else(|| format!("Job cancelled: {}", self.id))
+    }
+
+    /// Record the final state and start the next queued jobs
+    pub fn finish(mut self, state: JobState, error: Option<String>) {
+        self.finished = true;
+        complete(&self.app, &self.id, state, error);
+    }
+}
+
+impl Drop for JobGuard {
+    fn drop(&mut self) {
+        if !self.finished {
+            complete(
+                &self.app,
+                &self.id,
+                JobState::Failed,
+                Some("Job ended unexpectedly".to_string()),
+            );
+        }
+    }
+}
+
+fn complete(app: &AppHandle, id: &str, state: JobState, error: Option<String>) {
+    let changed = {
+        let mut queue = JOB_QUEUE.lock();
+        let mut changed: Vec<JobInfo> = queue.finish(id, state, error).into_iter().collect();
+        changed.extend(queue.schedule());
+        changed
+    };
+
+    emit_jobs(app, &changed);
+}
+
+/// Request cancellation of every running job matching the predicate
+///
+/// Returns the ids of the jobs that were signalled.
+pub fn cancel_running(predicate: impl Fn(&JobInfo) -> bool, reason: &str) -> Vec<String> {
+    let mut queue = JOB_QUEUE.lock();
+    queue
+        .jobs
+        .values_mut()
+        .filter(|j| j.info.state == JobState::Running && predicate(&j.info))
+        .map(|j| {
+            j.cancel_reason = Some(reason.to_string());
+            j.cancel.notify_one();
+            j.info.id.clone()
+        })
+        .collect()
+}
+
+/// List queued, running and recently finished Claude jobs
+#[command]
+pub async fn list_claude_jobs() -> CommandResult<Vec<JobInfo>> {
+    CommandResult::ok(JOB_QUEUE.lock().list())
+}
+
+/// Get the current concurrency limits
+#[command]
+pub async fn get_claude_job_limits() -> CommandResult<JobLimits> {
+    CommandResult::ok(JOB_QUEUE.lock().limits)
+}
+
+/// Update the concurrency limits, starting queued jobs if slots opened up
+#[command]
+pub async fn set_claude_job_limits(app: AppHandle, limits: JobLimits) -> CommandResult<JobLimits> {
+    if limits.global == 0 || limits.per_project == 0 {
+        return CommandResult::err("Job limits must be at least 1");
+    }
+
+    let changed = {
+        let mut queue = JOB_QUEUE.lock();
+        queue.limits = limits;
+        queue.schedule()
+    };
+
+    emit_jobs(&app, &changed);
+    CommandResult::ok(limits)
+}
+
+/// Cancel a queued or running job
+#[command]
+pub async fn cancel_claude_job(app: AppHandle, job_id: String) -> CommandResult<()> {
+    let changed = {
+        let mut queue = JOB_QUEUE.lock();
+        let state = match queue.jobs.get(&job_id) {
+            Some(job) => job.info.state,
+            None => return CommandResult::err(format!("Job not found: {}", job_id)),
+        };
+
+        match state {
+            JobState::Queued => queue
+                .finish(&job_id, JobState::Cancelled, None)
+                .into_iter()
+                .collect(),
+            JobState::Running => {
+                // The runner kills its process and finishes the job itself
+                queue.jobs[&job_id].cancel.notify_one();
+                vec![]
+            }
+            _ => return CommandResult::err(format!("Job already finished: {}", job_id)),
+        }
+    };
+
+    emit_jobs(&app, &changed);
+    CommandResult::ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue(global: usize, per_project: usize) -> JobQueue {
+        JobQueue {
+            limits: JobLimits {
+                global,
+                per_project,
+            },
+            ..Default::default()
+        }
+    }
+
+    fn push(queue: &mut JobQueue, id: &str, project: &str, priority: i32) -> oneshot::Receiver<()> {
+        queue
+            .push(id, Some(project.to_string()), priority)
+            .unwrap()
+            .1
+    }
+
+    fn state(queue: &JobQueue, id: &str) -> JobState {
+        queue.jobs[id].info.state
+    }
+
+    #[test]
+    fn test_schedule_respects_limits() {
+        let mut q = queue(3, 2);
+        let _a = push(&mut q, "a", "/p1", 0);
+        let _b = push(&mut q, "b", "/p1", 0);
+        let _c = push(&mut q, "c", "/p1", 0);
+        let _d = push(&mut q, "d", "/p2", 0);
+        let _e = push(&mut q, "e", "/p3", 0);
+
+        q.schedule();
+
+        assert_eq!(state(&q, "a"), JobState::Running);
+        assert_eq!(state(&q, "b"), JobState::Running);
+        assert_eq!(state(&q, "c"), JobState::Queued);
+        assert_eq!(state(&q, "d"), JobState::Running);
+        assert_eq!(state(&q, "e"), JobState::Queued);
+
+        q.finish("a", JobState::Completed, None);
+        q.schedule();
+
+        assert_eq!(state(&q, "c"), JobState::Running);
+        assert_eq!(state(&q, "e"), JobState::Queued);
+    }
+
+    #[test]
+    fn test_schedule_jobs_without_project_share_no_slot() {
+        let mut q = queue(3, 1);
+        let _a = q.push("a", None, 0).unwrap().1;
+        let _b = q.push("b", None, 0).unwrap().1;
+        let _c = push(&mut q, "c", "/p", 0);
+
+        q.schedule();
+
+        assert_eq!(state(&q, "a"), JobState::Running);
+        assert_eq!(state(&q, "b"), JobState::Running);
+        assert_eq!(state(&q, "c"), JobState::Running);
+    }
+
+    #[test]
+    fn test_schedule_priority_order() {
+        let mut q = queue(1, 1);
+        let _low = push(&mut q, "low", "/p", 0);
+        let _high = push(&mut q, "high", "/p", 10);
+
+        q.schedule();
+
+        assert_eq!(state(&q, "high"), JobState::Running);
+        assert_eq!(state(&q, "low"), JobState::Queued);
+    }
+
+    #[test]
+    fn test_schedule_skips_abandoned_jobs() {
+        let mut q = queue(1, 1);
+        drop(push(&mut q, "gone", "/p", 0));
+        let _next = push(&mut q, "next", "/p", 0);
+
+        q.schedule();
+
+        assert_eq!(state(&q, "gone"), JobState::Cancelled);
+        assert_eq!(state(&q, "next"), JobState::Running);
+    }
+
+    #[test]
+    fn test_push_rejects_active_duplicate() {
+        let mut q = queue(1, 1);
+        let _a = push(&mut q, "a", "/p", 0);
+        assert!(q.push("a", None, 0).is_err());
+
+        q.finish("a", JobState::Completed, None);
+        assert!(q.push("a", None, 0).is_ok());
+    }
+}
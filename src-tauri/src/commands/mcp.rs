@@ -0,0 +1,828 @@
+//! MCP server definitions
+//!
+//! Servers live where the Claude CLI keeps them:
+//!
+//! - user: `mcpServers` in `~/.claude.json`
+//! - local: `projects.<path>.mcpServers` in `~/.claude.json`
+//! - project: `mcpServers` in the project's `.mcp.json`
+//!
+//! Project servers are disabled through `disabledMcpjsonServers` in the
+//! project's local settings, the way Claude does. User and local servers have
+//! no such switch, so disabling one moves its definition into
+//! `~/.codepod/mcp-disabled.json` until it is enabled again.
+
+use super::settings::{self, SettingsScope};
+use super::{backups, codepod_dir, CommandResult};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::command;
+
+/// Tries at a server update while other programs keep changing the file
+const UPDATE_ATTEMPTS: usize = 3;
+
+/// How Claude talks to a server
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum McpTransport {
+    #[default]
+    Stdio,
+    Sse,
+    Http,
+}
+
+/// One server definition as stored in the config files
+///
+/// Strings may contain `${VAR}` or `${VAR:-default}` placeholders, which
+/// Claude expands from the environment when it starts the server.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct McpServerConfig {
+    /// Defaults to stdio when absent, as in files written by hand
+    #[serde(rename = "type", default)]
+    pub transport: McpTransport,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    /// Keys not modelled here, kept as they are
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A server as listed for the UI
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpServerEntry {
+    pub name: String,
+    pub scope: SettingsScope,
+    pub config: McpServerConfig,
+    pub enabled: bool,
+    /// File the definition is read from
+    pub path: String,
+    /// Placeholders with no default whose variable is not set
+    pub missing_env: Vec<String>,
+    /// Why the definition could not be parsed; `config` is then empty
+    pub error: Option<String>,
+}
+
+/// Path of the CLI's global config file
+///
+/// It sits inside `CLAUDE_CONFIG_DIR` when that is set, otherwise in the home
+/// directory.
+pub(crate) fn claude_json_path() -> PathBuf {
+    match std::env::var_os("CLAUDE_CONFIG_DIR") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir).join(".claude.json"),
+        _ => dirs::home_dir().unwrap_or_default().join(".claude.json"),
+    }
+}
+
+fn disabled_store_path() -> PathBuf {
+    codepod_dir().join("mcp-disabled.json")
+}
+
+fn require_project(project_path: Option<&str>) -> Result<&str, String> {
+    project_path
+        .filter(|p| !p.is_empty())
+        .map(|p| p.trim_end_matches(['/', '\\']))
+        .ok_or_else(|| "A project path is required for project and local servers".to_string())
+}
+
+/// File holding a scope's servers
+fn servers_path(scope: SettingsScope, project_path: Option<&str>) -> Result<PathBuf, String> {
+    match scope {
+        SettingsScope::User => Ok(claude_json_path()),
+        SettingsScope::Local => require_project(project_path).map(|_| claude_json_path()),
+        SettingsScope::Project => Ok(Path::new(require_project(project_path)?).join(".mcp.json")),
+    }
+}
+
+/// Read a JSON object file; a missing or empty file is an empty object
+pub(crate) fn read_json(path: &Path) -> Result<Value, String> {
+    read_json_versioned(path).map(|(value, _)| value)
+}
+
+/// Read a JSON object file together with the version of its content
+pub(crate) fn read_json_versioned(path: &Path) -> Result<(Value, String), String> {
+    let content = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Ok((
+                Value::Object(Map::new()),
+                backups::MISSING_VERSION.to_string(),
+            ))
+        }
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    let version = backups::content_version(&content);
+    let content = String::from_utf8(content)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    if content.trim().is_empty() {
+        return Ok((Value::Object(Map::new()), version));
+    }
+    match serde_json::from_str(&content) {
+        Ok(value @ Value::Object(_)) => Ok((value, version)),
+        Ok(_) => Err(format!("{} is not a JSON object", path.display())),
+        Err(e) => Err(format!("Invalid JSON in {}: {}", path.display(), e)),
+    }
+}
+
+/// Write a JSON file, failing if it no longer has `expected_version`
+fn write_json(path: &Path, value: &Value, expected_version: &str) -> Result<(), String> {
+    let mut content = serde_json::to_string_pretty(value)
+        .map_err(|e| format!("Failed to serialize {}: {}", path.display(), e))?;
+    content.push('\n');
+    backups::safe_write(path, content.as_bytes(), Some(expected_version)).map(|_| ())
+}
+
+/// Object at `keys` below `root`, created if missing
+pub(crate) fn object_at<'a>(
+    root: &'a mut Value,
+    keys: &[&str],
+) -> Result<&'a mut Map<String, Value>, String> {
+    let mut current = root;
+    for key in keys {
+        let object = current
+            .as_object_mut()
+            .ok_or_else(|| format!("Expected an object above '{}'", key))?;
+        current = object
+            .entry(key.to_string())
+            .or_insert_with(|| Value::Object(Map::new()));
+    }
+    current
+        .as_object_mut()
+        .ok_or_else(|| format!("'{}' is not an object", keys.join(".")))
+}
+
+/// Keys leading to a scope's `mcpServers` object inside its file
+fn servers_keys(scope: SettingsScope, project_path: Option<&str>) -> Result<Vec<String>, String> {
+    Ok(match scope {
+        SettingsScope::Local => vec![
+            "projects".to_string(),
+            require_project(project_path)?.to_string(),
+            "mcpServers".to_string(),
+        ],
+        _ => vec!["mcpServers".to_string()],
+    })
+}
+
+/// Key of a scope in the disabled store
+fn disabled_key(scope: SettingsScope, project_path: Option<&str>) -> Result<String, String> {
+    Ok(match scope {
+        SettingsScope::Local => format!("local:{}", require_project(project_path)?),
+        _ => "user".to_string(),
+    })
+}
+
+/// File and keys of a scope's enabled or disabled servers
+///
+/// Disabled project servers stay in `.mcp.json`, so only user and local
+/// scopes have a separate disabled map.
+pub(crate) fn servers_location(
+    scope: SettingsScope,
+    project_path: Option<&str>,
+    enabled: bool,
+) -> Result<(PathBuf, Vec<String>), String> {
+    if enabled || scope == SettingsScope::Project {
+        Ok((
+            servers_path(scope, project_path)?,
+            servers_keys(scope, project_path)?,
+        ))
+    } else {
+        Ok((
+            disabled_store_path(),
+            vec![disabled_key(scope, project_path)?],
+        ))
+    }
+}
+
+/// Read, change and write back the enabled or disabled servers of a scope
+///
+/// The Claude CLI rewrites `~/.claude.json` often; if the file changes
+/// between the read and the write, the change is applied again to the new
+/// content, up to `UPDATE_ATTEMPTS` times.
+fn update_servers<T>(
+    scope: SettingsScope,
+    project_path: Option<&str>,
+    enabled: bool,
+    mut f: impl FnMut(&mut Map<String, Value>) -> Result<T, String>,
+) -> Result<T, String> {
+    let (path, keys) = servers_location(scope, project_path, enabled)?;
+    let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+
+    let mut attempt = 1;
+    loop {
+        let (mut root, version) = read_json_versioned(&path)?;
+        let result = f(object_at(&mut root, &keys)?)?;
+        match write_json(&path, &root, &version) {
+            Ok(()) => return Ok(result),
+            Err(_) if attempt < UPDATE_ATTEMPTS && backups::file_version(&path)? != version => {
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Servers of a scope, enabled or disabled
+pub(crate) fn read_servers(
+    scope: SettingsScope,
+    project_path: Option<&str>,
+    enabled: bool,
+) -> Result<(PathBuf, Map<String, Value>), String> {
+    let (path, keys) = servers_location(scope, project_path, enabled)?;
+
+    let root = read_json(&path)?;
+    let servers = keys
+        .iter()
+        .try_fold(&root, |value, key| value.get(key.as_str()))
+        .and_then(Value::as_object)
+        .cloned()
+        .unwrap_or_default();
+    Ok((path, servers))
+}
+
+/// Names of project servers turned off in the local settings
+fn disabled_project_servers(project_path: &str) -> Result<Vec<String>, String> {
+    let path = settings::settings_path(SettingsScope::Local, Some(project_path))?;
+    let local = settings::read_settings(&path)?;
+    Ok(local
+        .get("disabledMcpjsonServers")
+        .and_then(Value::as_array)
+        .map(|names| {
+            names
+                .iter()
+                .filter_map(|n| n.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+/// Drop a name from a settings list, removing the list once it is empty
+fn remove_listed(settings: &mut Map<String, Value>, key: &str, name: &str) {
+    if let Some(Value::Array(names)) = settings.get_mut(key) {
+        names.retain(|n| n.as_str() != Some(name));
+        if names.is_empty() {
+            settings.remove(key);
+        }
+    }
+}
+
+/// Forget a removed project server in the local settings
+fn forget_project_server(project_path: &str, name: &str) -> Result<(), String> {
+    let path = settings::settings_path(SettingsScope::Local, Some(project_path))?;
+    if !path.exists() {
+        return Ok(());
+    }
+    let mut local = settings::read_settings(&path)?;
+    let Some(object) = local.as_object_mut() else {
+        return Ok(());
+    };
+
+    let before = object.clone();
+    remove_listed(object, "enabledMcpjsonServers", name);
+    remove_listed(object, "disabledMcpjsonServers", name);
+    if *object == before {
+        return Ok(());
+    }
+    settings::write_settings(&path, &local)
+}
+
+/// Turn a project server on or off in the local settings
+fn set_project_server_enabled(project_path: &str, name: &str, enabled: bool) -> Result<(), String> {
+    let path = settings::settings_path(SettingsScope::Local, Some(project_path))?;
+    let mut local = settings::read_settings(&path)?;
+    let object = local
+        .as_object_mut()
+        .ok_or_else(|| format!("Settings in {} are not an object", path.display()))?;
+
+    let (add_to, remove_from) = if enabled {
+        ("enabledMcpjsonServers", "disabledMcpjsonServers")
+    } else {
+        ("disabledMcpjsonServers", "enabledMcpjsonServers")
+    };
+    remove_listed(object, remove_from, name);
+    let names = object.entry(add_to).or_insert_with(|| Value::Array(vec![]));
+    match names {
+        Value::Array(names) if !names.iter().any(|n| n.as_str() == Some(name)) => {
+            names.push(Value::String(name.to_string()))
+        }
+        Value::Array(_) => {}
+        _ => return Err(format!("'{}' in {} is not a list", add_to, path.display())),
+    }
+
+    settings::write_settings(&path, &local)
+}
+
+/// Check a server name the way `claude mcp add` does
+pub(crate) fn validate_server_name(name: &str) -> Result<(), String> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!(
+            "Server name '{}' may only contain letters, numbers, hyphens and underscores",
+            name
+        ));
+    }
+    Ok(())
+}
+
+/// Variables referenced by `${VAR}` and `${VAR:-default}` placeholders,
+/// with whether each has a default
+pub(crate) fn placeholders(text: &str) -> Result<Vec<(String, bool)>, String> {
+    let mut found = Vec::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find("${") {
+        let after = &rest[start + 2..];
+        let end = after
+            .find('}')
+            .ok_or_else(|| format!("Unclosed placeholder in '{}'", text))?;
+        let inner = &after[..end];
+        let (name, has_default) = match inner.split_once(":-") {
+            Some((name, _)) => (name, true),
+            None => (inner, false),
+        };
+
+        let valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            return Err(format!(
+                "Invalid placeholder '${{{}}}' in '{}'",
+                inner, text
+            ));
+        }
+        found.push((name.to_string(), has_default));
+        rest = &after[end + 1..];
+    }
+
+    Ok(found)
+}
+
+/// Expand placeholders from `lookup`, failing on variables with no value
+/// and no default
+pub(crate) fn expand_placeholders(
+    text: &str,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<String, String> {
+    let mut expanded = String::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find("${") {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after
+            .find('}')
+            .ok_or_else(|| format!("Unclosed placeholder in '{}'", text))?;
+        let inner = &after[..end];
+        let (name, default) = match inner.split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (inner, None),
+        };
+        match lookup(name).or_else(|| default.map(str::to_string)) {
+            Some(value) => expanded.push_str(&value),
+            None => return Err(format!("Environment variable {} is not set", name)),
+        }
+        rest = &after[end + 1..];
+    }
+
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+impl McpServerConfig {
+    /// Every string Claude expands placeholders in
+    fn expandable_strings(&self) -> Vec<&str> {
+        let mut strings: Vec<&str> = Vec::new();
+        strings.extend(self.command.as_deref());
+        strings.extend(self.args.iter().map(String::as_str));
+        strings.extend(self.env.values().map(String::as_str));
+        strings.extend(self.url.as_deref());
+        strings.extend(self.headers.values().map(String::as_str));
+        strings
+    }
+
+    /// Variables the definition needs that are neither set nor defaulted
+    fn missing_env(&self) -> Vec<String> {
+        let mut missing: Vec<String> = self
+            .expandable_strings()
+            .into_iter()
+            .filter_map(|s| placeholders(s).ok())
+            .flatten()
+            .filter(|(name, has_default)| !has_default && std::env::var_os(name).is_none())
+            .map(|(name, _)| name)
+            .collect();
+        missing.sort();
+        missing.dedup();
+        missing
+    }
+}
+
+/// Check that a definition has the fields its transport needs and no others
+pub(crate) fn validate_server(config: &McpServerConfig) -> Result<(), String> {
+    match config.transport {
+        McpTransport::Stdio => {
+            if config
+                .command
+                .as_deref()
+                .map_or(true, |c| c.trim().is_empty())
+            {
+                return Err("Stdio servers need a command".to_string());
+            }
+            if config.url.is_some() || !config.headers.is_empty() {
+                return Err("Stdio servers take a command, not a URL or headers".to_string());
+            }
+        }
+        McpTransport::Sse | McpTransport::Http => {
+            let url = config.url.as_deref().unwrap_or("").trim();
+            if url.is_empty() {
+                return Err("SSE and HTTP servers need a URL".to_string());
+            }
+            let checkable = !url.contains("${");
+            if checkable && !url.starts_with("http://") && !url.starts_with("https://") {
+                return Err(format!("URL '{}' must start with http:// or https://", url));
+            }
+            if config.command.is_some() || !config.args.is_empty() || !config.env.is_empty() {
+                return Err("SSE and HTTP servers take a URL, not a command".to_string());
+            }
+        }
+    }
+
+    for key in config.env.keys() {
+        if key.is_empty() || key.contains('=') {
+            return Err(format!("Invalid environment variable name '{}'", key));
+        }
+    }
+    for text in config.expandable_strings() {
+        placeholders(text)?;
+    }
+    Ok(())
+}
+
+/// A server as listed; a definition that fails to parse is reported with
+/// its error instead of failing the whole list
+fn entry(
+    name: String,
+    scope: SettingsScope,
+    value: Value,
+    enabled: bool,
+    path: &Path,
+) -> McpServerEntry {
+    let (config, error) = match serde_json::from_value::<McpServerConfig>(value) {
+        Ok(config) => (config, None),
+        Err(e) => (
+            McpServerConfig::default(),
+            Some(format!(
+                "Invalid MCP server '{}' in {}: {}",
+                name,
+                path.display(),
+                e
+            )),
+        ),
+    };
+    McpServerEntry {
+        missing_env: config.missing_env(),
+        name,
+        scope,
+        config,
+        enabled,
+        path: path.to_string_lossy().to_string(),
+        error,
+    }
+}
+
+/// Find which map a server is in: `Some(true)` enabled, `Some(false)` disabled
+pub(crate) fn server_state(
+    scope: SettingsScope,
+    project_path: Option<&str>,
+    name: &str,
+) -> Result<Option<bool>, String> {
+    for enabled in [true, false] {
+        if !enabled && scope == SettingsScope::Project {
+            break;
+        }
+        if read_servers(scope, project_path, enabled)?
+            .1
+            .contains_key(name)
+        {
+            return Ok(Some(enabled));
+        }
+    }
+    Ok(None)
+}
+
+/// List the user's servers and, with a project, its project and local servers
+#[command]
+pub async fn list_mcp_servers(project_path: Option<String>) -> CommandResult<Vec<McpServerEntry>> {
+    let project_path = project_path.as_deref();
+    let mut entries = Vec::new();
+
+    for scope in SettingsScope::ALL {
+        if scope != SettingsScope::User && project_path.is_none() {
+            continue;
+        }
+
+        let result = (|| {
+            let (path, servers) = read_servers(scope, project_path, true)?;
+            if scope == SettingsScope::Project {
+                let disabled = disabled_project_servers(require_project(project_path)?)?;
+                for (name, value) in servers {
+                    let enabled = !disabled.contains(&name);
+                    entries.push(entry(name, scope, value, enabled, &path));
+                }
+                return Ok(());
+            }
+
+            for (name, value) in servers {
+                entries.push(entry(name, scope, value, true, &path));
+            }
+            let (store, disabled) = read_servers(scope, project_path, false)?;
+            for (name, value) in disabled {
+                entries.push(entry(name, scope, value, false, &store));
+            }
+            Ok::<(), String>(())
+        })();
+
+        if let Err(e) = result {
+            return CommandResult::err(e);
+        }
+    }
+
+    CommandResult::ok(entries)
+}
+
+/// Add a server at a scope
+#[command]
+pub async fn add_mcp_server(
+    scope: SettingsScope,
+    project_path: Option<String>,
+    name: String,
+    config: McpServerConfig,
+) -> CommandResult<McpServerEntry> {
+    let project_path = project_path.as_deref();
+    let result = validate_server_name(&name)
+        .and_then(|_| validate_server(&config))
+        .and_then(|_| match server_state(scope, project_path, &name)? {
+            Some(_) => Err(format!("MCP server '{}' already exists", name)),
+            None => Ok(()),
+        })
+        .and_then(|_| {
+            let value = serde_json::to_value(&config)
+                .map_err(|e| format!("Failed to serialize server: {}", e))?;
+            update_servers(scope, project_path, true, |servers| {
+                servers.insert(name.clone(), value.clone());
+                Ok(())
+            })?;
+            let path = servers_path(scope, project_path)?;
+            Ok(entry(name.clone(), scope, value, true, &path))
+        });
+
+    match result {
+        Ok(entry) => CommandResult::ok(entry),
+        Err(e) => CommandResult::err(e),
+    }
+}
+
+/// Replace a server's definition, keeping its enabled state
+#[command]
+pub async fn update_mcp_server(
+    scope: SettingsScope,
+    project_path: Option<String>,
+    name: String,
+    config: McpServerConfig,
+) -> CommandResult<McpServerEntry> {
+    let project_path = project_path.as_deref();
+    let result = validate_server(&config).and_then(|_| {
+        let Some(stored) = server_state(scope, project_path, &name)? else {
+            return Err(format!("MCP server '{}' does not exist", name));
+        };
+        let value = serde_json::to_value(&config)
+            .map_err(|e| format!("Failed to serialize server: {}", e))?;
+        update_servers(scope, project_path, stored, |servers| {
+            servers.insert(name.clone(), value.clone());
+            Ok(())
+        })?;
+
+        let enabled = if scope == SettingsScope::Project {
+            !disabled_project_servers(require_project(project_path)?)?.contains(&name)
+        } else {
+            stored
+        };
+        let (path, _) = servers_location(scope, project_path, stored)?;
+        Ok(entry(name.clone(), scope, value, enabled, &path))
+    });
+
+    match result {
+        Ok(entry) => CommandResult::ok(entry),
+        Err(e) => CommandResult::err(e),
+    }
+}
+
+/// Remove a server, enabled or not
+///
+/// A project server is also dropped from the enabled and disabled lists in
+/// the local settings.
+#[command]
+pub async fn remove_mcp_server(
+    scope: SettingsScope,
+    project_path: Option<String>,
+    name: String,
+) -> CommandResult<()> {
+    let project_path = project_path.as_deref();
+    let result = server_state(scope, project_path, &name).and_then(|stored| {
+        let Some(stored) = stored else {
+            return Err(format!("MCP server '{}' does not exist", name));
+        };
+        update_servers(scope, project_path, stored, |servers| {
+            servers.remove(&name);
+            Ok(())
+        })?;
+        match scope {
+            SettingsScope::Project => forget_project_server(require_project(project_path)?, &name),
+            _ => Ok(()),
+        }
+    });
+
+    match result {
+        Ok(()) => CommandResult::ok(()),
+        Err(e) => CommandResult::err(e),
+    }
+}
+
+/// Enable or disable a server
+#[command]
+pub async fn set_mcp_server_enabled(
+    scope: SettingsScope,
+    project_path: Option<String>,
+    name: String,
+    enabled: bool,
+) -> CommandResult<()> {
+    let project_path = project_path.as_deref();
+    let result = server_state(scope, project_path, &name).and_then(|stored| {
+        let Some(stored) = stored else {
+            return Err(format!("MCP server '{}' does not exist", name));
+        };
+        if scope == SettingsScope::Project {
+            return set_project_server_enabled(require_project(project_path)?, &name, enabled);
+        }
+        if stored == enabled {
+            return Ok(());
+        }
+
+        // Copy before removing, so a failed write never loses the server
+        let value = read_servers(scope, project_path, stored)?
+            .1
+            .remove(&name)
+            .ok_or_else(|| format!("MCP server '{}' does not exist", name))?;
+        update_servers(scope, project_path, enabled, |servers| {
+            servers.insert(name.clone(), value.clone());
+            Ok(())
+        })?;
+        update_servers(scope, project_path, stored, |servers| {
+            servers.remove(&name);
+            Ok(())
+        })
+    });
+
+    match result {
+        Ok(()) => CommandResult::ok(()),
+        Err(e) => CommandResult::err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_server_config_round_trip() {
+        let value = serde_json::json!({
+            "command": "npx",
+            "args": ["-y", "@modelcontextprotocol/server-github"],
+            "env": {"GITHUB_TOKEN": "${GITHUB_TOKEN}"},
+            "timeout": 30
+        });
+
+        let config: McpServerConfig = serde_json::from_value(value).unwrap();
+        assert_eq!(config.transport, McpTransport::Stdio);
+        assert_eq!(config.extra["timeout"], 30);
+        assert!(validate_server(&config).is_ok());
+
+        let written = serde_json::to_value(&config).unwrap();
+        assert_eq!(written["type"], "stdio");
+        assert_eq!(written["timeout"], 30);
+        assert!(written.get("url").is_none());
+    }
+
+    #[test]
+    fn test_validate_server() {
+        let http = McpServerConfig {
+            transport: McpTransport::Http,
+            url: Some("https://mcp.example.com/mcp".to_string()),
+            ..Default::default()
+        };
+        assert!(validate_server(&http).is_ok());
+
+        let with_command = McpServerConfig {
+            command: Some("node".to_string()),
+            ..http.clone()
+        };
+        assert!(validate_server(&with_command).is_err());
+
+        let bad_url = McpServerConfig {
+            url: Some("mcp.example.com".to_string()),
+            ..http.clone()
+        };
+        assert!(validate_server(&bad_url).is_err());
+
+        let placeholder_url = McpServerConfig {
+            url: Some("${MCP_URL:-http://localhost:3000}".to_string()),
+            ..http
+        };
+        assert!(validate_server(&placeholder_url).is_ok());
+
+        assert!(validate_server(&McpServerConfig::default()).is_err());
+        assert!(validate_server_name("github_tools-2").is_ok());
+        assert!(validate_server_name("my server").is_err());
+    }
+
+    #[test]
+    fn test_placeholders() {
+        assert_eq!(
+            placeholders("${HOME}/bin:${PORT:-8080}").unwrap(),
+            [("HOME".to_string(), false), ("PORT".to_string(), true)]
+        );
+        assert!(placeholders("${UNCLOSED").is_err());
+        assert!(placeholders("${1BAD}").is_err());
+
+        let lookup = |name: &str| (name == "HOST").then(|| "example.com".to_string());
+        assert_eq!(
+            expand_placeholders("https://${HOST}:${PORT:-443}/", lookup).unwrap(),
+            "https://example.com:443/"
+        );
+        assert!(expand_placeholders("${TOKEN}", lookup).is_err());
+    }
+
+    #[test]
+    fn test_object_at_creates_path() {
+        let mut root = serde_json::json!({"projects": {"/work": {"allowedTools": []}}});
+        object_at(&mut root, &["projects", "/work", "mcpServers"])
+            .unwrap()
+            .insert("git".to_string(), serde_json::json!({"command": "git-mcp"}));
+
+        assert_eq!(
+            root["projects"]["/work"]["mcpServers"]["git"]["command"],
+            "git-mcp"
+        );
+        assert!(root["projects"]["/work"]["allowedTools"].is_array());
+    }
+
+    #[test]
+    fn test_remove_listed() {
+        let mut settings = serde_json::json!({
+            "enabledMcpjsonServers": ["github"],
+            "disabledMcpjsonServers": ["github", "sentry"]
+        });
+        let object = settings.as_object_mut().unwrap();
+
+        remove_listed(object, "enabledMcpjsonServers", "github");
+        remove_listed(object, "disabledMcpjsonServers", "github");
+        assert_eq!(
+            settings,
+            serde_json::json!({ "disabledMcpjsonServers": ["sentry"] })
+        );
+    }
+
+    #[test]
+    fn test_entry_reports_invalid_definitions() {
+        let path = Path::new("/home/me/.claude.json");
+        let valid = entry(
+            "github".to_string(),
+            SettingsScope::User,
+            serde_json::json!({ "command": "npx" }),
+            true,
+            path,
+        );
+        assert_eq!(valid.error, None);
+
+        let unknown = entry(
+            "socket".to_string(),
+            SettingsScope::User,
+            serde_json::json!({ "type": "websocket", "url": "ws://localhost:9000" }),
+            false,
+            path,
+        );
+        assert!(unknown.error.unwrap().contains("'socket'"));
+        assert_eq!(unknown.name, "socket");
+        assert!(!unknown.enabled);
+    }
+}
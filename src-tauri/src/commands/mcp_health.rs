@@ -0,0 +1,945 @@
+//! MCP server health checks
+//!
+//! A check starts a stdio server, or connects to an HTTP or SSE endpoint,
+//! performs the `initialize` handshake and lists the server's tools,
+//! resources and prompts.
+
+use super::mcp::{expand_placeholders, validate_server, McpServerConfig, McpTransport};
+use super::CommandResult;
+use parking_lot::Mutex;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, ACCEPT, CONTENT_TYPE};
+use reqwest::{Client, Response, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::command;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child, ChildStderr, ChildStdin, ChildStdout, Command};
+use tokio::task::JoinHandle;
+
+/// Default limit for the whole check
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+/// Protocol version offered in `initialize`
+const PROTOCOL_VERSION: &str = "2025-06-18";
+/// Stderr kept in the report
+const MAX_STDERR_BYTES: usize = 16 * 1024;
+/// Pages fetched per list request
+const MAX_PAGES: usize = 10;
+/// Time a stdio server gets to exit after its stdin is closed
+const EXIT_GRACE: Duration = Duration::from_millis(500);
+
+/// A tool, resource or prompt offered by a server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct McpItem {
+    pub name: String,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct McpHealthReport {
+    pub ok: bool,
+    /// First failure, if any
+    pub error: Option<String>,
+    pub timed_out: bool,
+    pub duration_ms: u64,
+    /// Time until the server answered `initialize`
+    pub initialize_ms: Option<u64>,
+    pub protocol_version: Option<String>,
+    /// `serverInfo` from the handshake, usually a name and version
+    pub server_info: Option<Value>,
+    /// Capabilities the server declared, e.g. `tools` or `resources`
+    pub capabilities: Vec<String>,
+    pub tools: Vec<McpItem>,
+    pub resources: Vec<McpItem>,
+    pub prompts: Vec<McpItem>,
+    /// Stderr of a stdio server, truncated
+    pub stderr: String,
+}
+
+/// Parse an endpoint URL
+fn parse_url(url: &str) -> Result<Url, String> {
+    let parsed = Url::parse(url).map_err(|e| format!("Invalid URL '{}': {}", url, e))?;
+    match parsed.scheme() {
+        "http" | "https" => Ok(parsed),
+        scheme => Err(format!("Unsupported URL scheme '{}' in '{}'", scheme, url)),
+    }
+}
+
+/// Client sending the configured headers with every request
+fn http_client(headers: &[(String, String)]) -> Result<Client, String> {
+    let mut map = HeaderMap::new();
+    for (name, value) in headers {
+        let header = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| format!("Invalid header name '{}'", name))?;
+        let value = HeaderValue::from_str(value)
+            .map_err(|_| format!("Invalid value for header '{}'", name))?;
+        map.insert(header, value);
+    }
+    Client::builder()
+        .default_headers(map)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))
+}
+
+/// Describe a failed response, with the start of its body
+async fn response_error(response: Response) -> String {
+    let status = response.status().as_u16();
+    let body = response.text().await.unwrap_or_default();
+    let body: String = body.trim().chars().take(200).collect();
+    if body.is_empty() {
+        format!("Server answered HTTP {}", status)
+    } else {
+        format!("Server answered HTTP {}: {}", status, body)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct SseEvent {
+    event: String,
+    data: String,
+}
+
+/// Incremental parser for server-sent events
+#[derive(Default)]
+struct SseParser {
+    buffer: Vec<u8>,
+    event: String,
+    data: Vec<String>,
+}
+
+impl SseParser {
+    fn push(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Next complete event with data in the input so far
+    fn next_event(&mut self) -> Option<SseEvent> {
+        while let Some(end) = self.buffer.iter().position(|&b| b == b'\n') {
+            let raw: Vec<u8> = self.buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&raw);
+            let line = line.trim_end_matches(['\n', '\r']);
+
+            if line.is_empty() {
+                let event = std::mem::take(&mut self.event);
+                if !self.data.is_empty() {
+                    return Some(SseEvent {
+                        event: if event.is_empty() {
+                            "message".to_string()
+                        } else {
+                            event
+                        },
+                        data: std::mem::take(&mut self.data).join("\n"),
+                    });
+                }
+                continue;
+            }
+
+            let (field, value) = match line.split_once(':') {
+                Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+                None => (line, ""),
+            };
+            match field {
+                "event" => self.event = value.to_string(),
+                "data" => self.data.push(value.to_string()),
+                // Comments, ids and retry hints
+                _ => {}
+            }
+        }
+        None
+    }
+}
+
+/// Server-sent events read from a response body
+struct SseStream {
+    response: Response,
+    parser: SseParser,
+}
+
+impl SseStream {
+    fn new(response: Response) -> Self {
+        SseStream {
+            response,
+            parser: SseParser::default(),
+        }
+    }
+
+    /// Next event with data, or `None` when the stream ends
+    async fn next_event(&mut self) -> Result<Option<SseEvent>, String> {
+        loop {
+            if let Some(event) = self.parser.next_event() {
+                return Ok(Some(event));
+            }
+            match self.response.chunk().await {
+                Ok(Some(bytes)) => self.parser.push(&bytes),
+                Ok(None) => return Ok(None),
+                Err(e) => return Err(format!("Failed to read response: {}", e)),
+            }
+        }
+    }
+}
+
+/// POST a JSON-RPC message the way streamable HTTP clients do
+async fn post_message(
+    client: &Client,
+    url: &Url,
+    session_id: Option<&str>,
+    protocol_version: Option<&str>,
+    message: &Value,
+) -> Result<Response, String> {
+    let mut request = client
+        .post(url.clone())
+        .header(ACCEPT, "application/json, text/event-stream")
+        .header(CONTENT_TYPE, "application/json")
+        .body(message.to_string());
+    if let Some(id) = session_id {
+        request = request.header("Mcp-Session-Id", id);
+    }
+    if let Some(version) = protocol_version {
+        request = request.header("MCP-Protocol-Version", version);
+    }
+    request
+        .send()
+        .await
+        .map_err(|e| format!("Request to {} failed: {}", url, e))
+}
+
+/// Answer a request the server sent us; only `ping` is supported
+fn reply_to_server_request(message: &Value) -> Option<Value> {
+    let id = message.get("id")?;
+    let method = message.get("method")?.as_str()?;
+    Some(if method == "ping" {
+        json!({ "jsonrpc": "2.0", "id": id, "result": {} })
+    } else {
+        json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": -32601, "message": format!("Method not found: {}", method) }
+        })
+    })
+}
+
+/// The response with `id` in a message or batch
+fn find_response(message: Value, id: u64) -> Option<Value> {
+    match message {
+        Value::Array(messages) => messages.into_iter().find_map(|m| find_response(m, id)),
+        message
+            if message.get("id") == Some(&json!(id))
+                && (message.get("result").is_some() || message.get("error").is_some()) =>
+        {
+            Some(message)
+        }
+        _ => None,
+    }
+}
+
+async fn write_line(stdin: &mut ChildStdin, message: &Value) -> Result<(), String> {
+    let line = format!("{}\n", message);
+    stdin
+        .write_all(line.as_bytes())
+        .await
+        .map_err(|e| format!("Failed to write to server: {}", e))
+}
+
+/// Keep the start of a server's stderr, draining the rest
+fn capture_stderr(stderr: ChildStderr, buffer: Arc<Mutex<String>>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(stderr).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            let mut buffer = buffer.lock();
+            if buffer.len() + line.len() < MAX_STDERR_BYTES {
+                buffer.push_str(&line);
+                buffer.push('\n');
+            }
+        }
+    })
+}
+
+enum Connection {
+    Stdio {
+        child: Child,
+        /// Server pid, which on unix is also its process group id
+        group: Option<u32>,
+        stdin: ChildStdin,
+        stdout: Lines<BufReader<ChildStdout>>,
+        stderr_task: JoinHandle<()>,
+    },
+    /// Streamable HTTP: every message is a POST to one URL
+    Http {
+        client: Client,
+        url: Url,
+        session_id: Option<String>,
+        protocol_version: Option<String>,
+    },
+    /// Legacy SSE: responses arrive on a long-lived event stream and
+    /// messages are posted to the endpoint it announces
+    Sse {
+        client: Client,
+        events: SseStream,
+        endpoint: Url,
+    },
+}
+
+struct McpClient {
+    connection: Connection,
+    next_id: u64,
+}
+
+impl McpClient {
+    async fn connect(
+        config: &McpServerConfig,
+        cwd: Option<&str>,
+        stderr: Arc<Mutex<String>>,
+    ) -> Result<Self, String> {
+        let expand = |text: &str| expand_placeholders(text, |name| std::env::var(name).ok());
+        let headers = config
+            .headers
+            .iter()
+            .map(|(name, value)| Ok((name.clone(), expand(value)?)))
+            .collect::<Result<Vec<_>, String>>()?;
+        let url = || parse_url(&expand(config.url.as_deref().unwrap_or_default())?);
+
+        let connection = match config.transport {
+            McpTransport::Stdio => {
+                let program = expand(config.command.as_deref().unwrap_or_default())?;
+                let mut cmd = Command::new(&program);
+                for arg in &config.args {
+                    cmd.arg(expand(arg)?);
+                }
+                for (name, value) in &config.env {
+                    cmd.env(name, expand(value)?);
+                }
+                if let Some(cwd) = cwd {
+                    cmd.current_dir(cwd);
+                }
+                cmd.stdin(Stdio::piped())
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::piped())
+                    .kill_on_drop(true);
+                // Lead a new process group so `close` can stop what the server starts
+                #[cfg(unix)]
+                cmd.process_group(0);
+
+                let mut child = cmd
+                    .spawn()
+                    .map_err(|e| format!("Failed to start '{}': {}", program, e))?;
+                let (Some(stdin), Some(stdout), Some(stderr_pipe)) =
+                    (child.stdin.take(), child.stdout.take(), child.stderr.take())
+                else {
+                    return Err("Failed to open the server's stdio".to_string());
+                };
+
+                Connection::Stdio {
+                    group: child.id(),
+                    child,
+                    stdin,
+                    stdout: BufReader::new(stdout).lines(),
+                    stderr_task: capture_stderr(stderr_pipe, stderr),
+                }
+            }
+            McpTransport::Http => Connection::Http {
+                client: http_client(&headers)?,
+                url: url()?,
+                session_id: None,
+                protocol_version: None,
+            },
+            McpTransport::Sse => {
+                let url = url()?;
+                let client = http_client(&headers)?;
+                let response = client
+                    .get(url.clone())
+                    .header(ACCEPT, "text/event-stream")
+                    .send()
+                    .await
+                    .map_err(|e| format!("Request to {} failed: {}", url, e))?;
+                if response.status() != StatusCode::OK {
+                    return Err(response_error(response).await);
+                }
+
+                // The first event names the URL to post messages to
+                let mut events = SseStream::new(response);
+                let endpoint = loop {
+                    match events.next_event().await? {
+                        Some(event) if event.event == "endpoint" => {
+                            break url.join(event.data.trim()).map_err(|e| {
+                                format!("Invalid endpoint '{}': {}", event.data.trim(), e)
+                            })?
+                        }
+                        Some(_) => continue,
+                        None => {
+                            return Err("Event stream closed before naming an endpoint".to_string())
+                        }
+                    }
+                };
+
+                Connection::Sse {
+                    client,
+                    events,
+                    endpoint,
+                }
+            }
+        };
+
+        Ok(McpClient {
+            connection,
+            next_id: 1,
+        })
+    }
+
+    /// Send a request and wait for its result
+    async fn request(&mut self, method: &str, params: Value) -> Result<Value, String> {
+        let id = self.next_id;
+        self.next_id += 1;
+        let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+
+        let response = match &mut self.connection {
+            Connection::Stdio { stdin, stdout, .. } => {
+                write_line(stdin, &message).await?;
+                loop {
+                    let line = stdout
+                        .next_line()
+                        .await
+                        .map_err(|e| format!("Failed to read from server: {}", e))?
+                        .ok_or_else(|| format!("Server exited before answering {}", method))?;
+                    // Some servers log to stdout; skip anything that is not JSON
+                    let Ok(incoming) = serde_json::from_str::<Value>(&line) else {
+                        continue;
+                    };
+                    if let Some(reply) = reply_to_server_request(&incoming) {
+                        write_line(stdin, &reply).await?;
+                    } else if let Some(response) = find_response(incoming, id) {
+                        break response;
+                    }
+                }
+            }
+            Connection::Http {
+                client,
+                url,
+                session_id,
+                protocol_version,
+            } => {
+                let response = post_message(
+                    client,
+                    url,
+                    session_id.as_deref(),
+                    protocol_version.as_deref(),
+                    &message,
+                )
+                .await?;
+                let header = |name| {
+                    response
+                        .headers()
+                        .get(name)
+                        .and_then(|v| v.to_str().ok())
+                        .map(str::to_string)
+                };
+                if let Some(id) = header("mcp-session-id") {
+                    *session_id = Some(id);
+                }
+                if !response.status().is_success() {
+                    return Err(response_error(response).await);
+                }
+
+                let streamed =
+                    header("content-type").is_some_and(|t| t.starts_with("text/event-stream"));
+                if streamed {
+                    let mut events = SseStream::new(response);
+                    loop {
+                        let event = events
+                            .next_event()
+                            .await?
+                            .ok_or_else(|| format!("Stream ended before answering {}", method))?;
+                        let found = serde_json::from_str(&event.data)
+                            .ok()
+                            .and_then(|m| find_response(m, id));
+                        if let Some(response) = found {
+                            break response;
+                        }
+                    }
+                } else {
+                    let text = response
+                        .text()
+                        .await
+                        .map_err(|e| format!("Failed to read response: {}", e))?;
+                    let incoming = serde_json::from_str(&text)
+                        .map_err(|e| format!("Invalid response to {}: {}", method, e))?;
+                    find_response(incoming, id)
+                        .ok_or_else(|| format!("No response to {} in the reply", method))?
+                }
+            }
+            Connection::Sse {
+                client,
+                events,
+                endpoint,
+            } => {
+                let response = post_message(client, endpoint, None, None, &message).await?;
+                if !response.status().is_success() {
+                    return Err(response_error(response).await);
+                }
+                loop {
+                    let event = events
+                        .next_event()
+                        .await?
+                        .ok_or_else(|| format!("Stream ended before answering {}", method))?;
+                    let Ok(incoming) = serde_json::from_str::<Value>(&event.data) else {
+                        continue;
+                    };
+                    if let Some(reply) = reply_to_server_request(&incoming) {
+                        post_message(client, endpoint, None, None, &reply).await?;
+                    } else if let Some(response) = find_response(incoming, id) {
+                        break response;
+                    }
+                }
+            }
+        };
+
+        if let Some(error) = response.get("error") {
+            let message = error
+                .get("message")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .unwrap_or_else(|| error.to_string());
+            return Err(format!("{} failed: {}", method, message));
+        }
+        Ok(response.get("result").cloned().unwrap_or(Value::Null))
+    }
+
+    /// Send a notification, which has no response
+    async fn notify(&mut self, method: &str) -> Result<(), String> {
+        let message = json!({ "jsonrpc": "2.0", "method": method });
+        let response = match &mut self.connection {
+            Connection::Stdio { stdin, .. } => return write_line(stdin, &message).await,
+            Connection::Http {
+                client,
+                url,
+                session_id,
+                protocol_version,
+            } => {
+                post_message(
+                    client,
+                    url,
+                    session_id.as_deref(),
+                    protocol_version.as_deref(),
+                    &message,
+                )
+                .await?
+            }
+            Connection::Sse {
+                client, endpoint, ..
+            } => post_message(client, endpoint, None, None, &message).await?,
+        };
+
+        if !response.status().is_success() {
+            return Err(response_error(response).await);
+        }
+        Ok(())
+    }
+
+    /// Send the negotiated version on later HTTP requests, as the spec asks
+    fn set_protocol_version(&mut self, version: &str) {
+        if let Connection::Http {
+            protocol_version, ..
+        } = &mut self.connection
+        {
+            *protocol_version = Some(version.to_string());
+        }
+    }
+
+    /// Stop a stdio server and anything it started
+    ///
+    /// The server gets `EXIT_GRACE` to exit once stdin closes; then its
+    /// process group is killed, which also stops processes it left behind.
+    async fn close(self) {
+        if let Connection::Stdio {
+            mut child,
+            group,
+            stdin,
+            mut stderr_task,
+            ..
+        } = self.connection
+        {
+            drop(stdin);
+            let _ = tokio::time::timeout(EXIT_GRACE, child.wait()).await;
+            kill_group(group);
+            let _ = child.kill().await;
+            // Children of the server may still hold stderr open
+            if tokio::time::timeout(EXIT_GRACE, &mut stderr_task)
+                .await
+                .is_err()
+            {
+                stderr_task.abort();
+            }
+        }
+    }
+}
+
+/// Kill the process group a child leads; a no-op outside unix
+pub(crate) fn kill_group(group: Option<u32>) {
+    #[cfg(unix)]
+    if let Some(group) = group {
+        // SAFETY: killpg only sends a signal; a stale group id fails with ESRCH
+        unsafe {
+            libc::killpg(group as libc::pid_t, libc::SIGKILL);
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = group;
+}
+
+/// Fetch every page of a list method
+async fn list_items(
+    client: &mut McpClient,
+    method: &str,
+    key: &str,
+) -> Result<Vec<McpItem>, String> {
+    let mut items = Vec::new();
+    let mut cursor: Option<String> = None;
+
+    for _ in 0..MAX_PAGES {
+        let params = match &cursor {
+            Some(cursor) => json!({ "cursor": cursor }),
+            None => json!({}),
+        };
+        let result = client.request(method, params).await?;
+
+        for item in result
+            .get(key)
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            let name = item
+                .get("name")
+                .or_else(|| item.get("uri"))
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            items.push(McpItem {
+                name: name.to_string(),
+                description: item
+                    .get("description")
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+            });
+        }
+
+        cursor = result
+            .get("nextCursor")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        if cursor.is_none() {
+            break;
+        }
+    }
+
+    Ok(items)
+}
+
+/// Handshake and list what the server declares, filling in the report as it goes
+async fn run_check(
+    client: &mut McpClient,
+    report: &mut McpHealthReport,
+    started: Instant,
+) -> Result<(), String> {
+    let result = client
+        .request(
+            "initialize",
+            json!({
+                "protocolVersion": PROTOCOL_VERSION,
+                "capabilities": {},
+                "clientInfo": { "name": "codepod", "version": env!("CARGO_PKG_VERSION") }
+            }),
+        )
+        .await?;
+    report.initialize_ms = Some(started.elapsed().as_millis() as u64);
+    report.protocol_version = result
+        .get("protocolVersion")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    report.server_info = result.get("serverInfo").cloned();
+    report.capabilities = result
+        .get("capabilities")
+        .and_then(Value::as_object)
+        .map(|c| c.keys().cloned().collect())
+        .unwrap_or_default();
+
+    if let Some(version) = report.protocol_version.clone() {
+        client.set_protocol_version(&version);
+    }
+    client.notify("notifications/initialized").await?;
+
+    let declared = |name: &str| report.capabilities.iter().any(|c| c == name);
+    let (tools, resources, prompts) = (
+        declared("tools"),
+        declared("resources"),
+        declared("prompts"),
+    );
+    if tools {
+        report.tools = list_items(client, "tools/list", "tools").await?;
+    }
+    if resources {
+        report.resources = list_items(client, "resources/list", "resources").await?;
+    }
+    if prompts {
+        report.prompts = list_items(client, "prompts/list", "prompts").await?;
+    }
+    Ok(())
+}
+
+/// Check a server within `limit`, always stopping what was started
+pub(crate) async fn check_server(
+    config: &McpServerConfig,
+    cwd: Option<&str>,
+    limit: Duration,
+) -> McpHealthReport {
+    let started = Instant::now();
+    let stderr = Arc::new(Mutex::new(String::new()));
+    let mut report = McpHealthReport::default();
+    let mut client = None;
+
+    let outcome = tokio::time::timeout(limit, async {
+        let client = client.insert(McpClient::connect(config, cwd, stderr.clone()).await?);
+        run_check(client, &mut report, started).await
+    })
+    .await;
+    if let Some(client) = client {
+        client.close().await;
+    }
+
+    match outcome {
+        Ok(Ok(())) => report.ok = true,
+        Ok(Err(e)) => report.error = Some(e),
+        Err(_) => {
+            report.timed_out = true;
+            report.error = Some(format!("No answer within {} seconds", limit.as_secs()));
+        }
+    }
+    report.duration_ms = started.elapsed().as_millis() as u64;
+    report.stderr = stderr.lock().clone();
+    report
+}
+
+/// Start or connect to an MCP server, handshake and list its capabilities
+///
+/// Stdio servers run in `project_path` when given and are stopped afterwards,
+/// also on failure or timeout.
+#[command]
+pub async fn test_mcp_server(
+    config: McpServerConfig,
+    project_path: Option<String>,
+    timeout_secs: Option<u64>,
+) -> CommandResult<McpHealthReport> {
+    if let Err(e) = validate_server(&config) {
+        return CommandResult::err(e);
+    }
+
+    let limit = Duration::from_secs(timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS));
+    CommandResult::ok(check_server(&config, project_path.as_deref(), limit).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_parse_url() {
+        assert!(parse_url("http://localhost:3000/mcp").is_ok());
+        assert!(parse_url("https://mcp.example.com/mcp").is_ok());
+        assert!(parse_url("ftp://example.com").is_err());
+        assert!(parse_url("localhost:3000").is_err());
+
+        let base = parse_url("http://localhost:3000/mcp/sse").unwrap();
+        assert_eq!(
+            base.join("/messages?sessionId=a").unwrap().as_str(),
+            "http://localhost:3000/messages?sessionId=a"
+        );
+        assert_eq!(
+            base.join("messages").unwrap().as_str(),
+            "http://localhost:3000/mcp/messages"
+        );
+    }
+
+    #[test]
+    fn test_sse_parser_across_chunks() {
+        let payload =
+            ": keep-alive\n\nevent: endpoint\ndata: /messages\n\ndata: {\"a\":\ndata: 1}\r\n\r\n";
+        let (first, second) = payload.split_at(30);
+
+        let mut parser = SseParser::default();
+        parser.push(first.as_bytes());
+        assert_eq!(parser.next_event(), None);
+        parser.push(second.as_bytes());
+        assert_eq!(
+            parser.next_event(),
+            Some(SseEvent {
+                event: "endpoint".to_string(),
+                data: "/messages".to_string()
+            })
+        );
+        assert_eq!(parser.next_event().unwrap().data, "{\"a\":\n1}");
+        assert_eq!(parser.next_event(), None);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_check_stdio_server() {
+        let script = r#"sleep 30 &
+echo $! > "$PID_FILE"
+read -r line
+echo 'not json'
+echo '{"jsonrpc":"2.0","id":1,"result":{"protocolVersion":"2025-06-18","capabilities":{"tools":{}},"serverInfo":{"name":"sh-server","version":"1.0"}}}'
+echo 'ready' >&2
+read -r line
+read -r line
+echo '{"jsonrpc":"2.0","id":2,"result":{"tools":[{"name":"echo","description":"Echo text"}]}}'
+wait"#;
+        let pid_file =
+            std::env::temp_dir().join(format!("codepod-mcp-{}.pid", uuid::Uuid::new_v4()));
+        let config = McpServerConfig {
+            command: Some("sh".to_string()),
+            args: vec!["-c".to_string(), script.to_string()],
+            env: [("PID_FILE".to_string(), pid_file.display().to_string())].into(),
+            ..Default::default()
+        };
+
+        let report = check_server(&config, None, Duration::from_secs(10)).await;
+        assert!(report.ok, "{:?}", report.error);
+        assert_eq!(report.server_info.unwrap()["name"], "sh-server");
+        assert_eq!(report.capabilities, ["tools"]);
+        assert_eq!(report.tools[0].name, "echo");
+        assert_eq!(report.stderr, "ready\n");
+        assert!(report.duration_ms < 5000);
+
+        // The `sleep` the server started was killed along with it
+        let pid: u32 = std::fs::read_to_string(&pid_file)
+            .unwrap()
+            .trim()
+            .parse()
+            .unwrap();
+        let _ = std::fs::remove_file(&pid_file);
+        let running = || {
+            let ps = std::process::Command::new("ps")
+                .args(["-o", "stat=", "-p", &pid.to_string()])
+                .output()
+                .unwrap();
+            // Orphans may linger as zombies until init reaps them
+            let state = String::from_utf8_lossy(&ps.stdout);
+            !state.trim().is_empty() && !state.trim().starts_with('Z')
+        };
+        let mut alive = true;
+        for _ in 0..50 {
+            if !running() {
+                alive = false;
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert!(!alive, "sleep {} is still running", pid);
+    }
+
+    /// Answer streamable HTTP requests: JSON for `initialize`, 202 for
+    /// notifications and a chunked event stream for `tools/list`
+    async fn serve_streamable_http(listener: TcpListener) {
+        loop {
+            let Ok((stream, _)) = listener.accept().await else {
+                return;
+            };
+            let mut reader = BufReader::new(stream);
+            let mut length = 0;
+            let mut session = None;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).await.unwrap();
+                let lower = line.trim().to_ascii_lowercase();
+                if lower.is_empty() {
+                    break;
+                }
+                if let Some(value) = lower.strip_prefix("content-length:") {
+                    length = value.trim().parse().unwrap();
+                }
+                if let Some(value) = lower.strip_prefix("mcp-session-id:") {
+                    session = Some(value.trim().to_string());
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).await.unwrap();
+            let message: Value = serde_json::from_slice(&body).unwrap();
+
+            let response = match message["method"].as_str().unwrap() {
+                "initialize" => {
+                    let body = json!({
+                        "jsonrpc": "2.0",
+                        "id": message["id"],
+                        "result": {
+                            "protocolVersion": "2025-06-18",
+                            "capabilities": { "prompts": {} },
+                            "serverInfo": { "name": "http-server" }
+                        }
+                    })
+                    .to_string();
+                    format!(
+                        "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Type: application/json\r\nMcp-Session-Id: s1\r\nContent-Length: {}\r\n\r\n{}",
+                        body.len(),
+                        body
+                    )
+                }
+                "prompts/list" if session.as_deref() == Some("s1") => {
+                    let event = format!(
+                        "data: {}\n\n",
+                        json!({
+                            "jsonrpc": "2.0",
+                            "id": message["id"],
+                            "result": { "prompts": [{ "name": "review" }] }
+                        })
+                    );
+                    format!(
+                        "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Type: text/event-stream\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n{}\r\n0\r\n\r\n",
+                        event.len(),
+                        event
+                    )
+                }
+                _ if message.get("id").is_none() => {
+                    "HTTP/1.1 202 Accepted\r\nConnection: close\r\nContent-Length: 0\r\n\r\n"
+                        .to_string()
+                }
+                _ => {
+                    "HTTP/1.1 400 Bad Request\r\nConnection: close\r\nContent-Length: 3\r\n\r\nbad"
+                        .to_string()
+                }
+            };
+            reader
+                .get_mut()
+                .write_all(response.as_bytes())
+                .await
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_check_http_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(serve_streamable_http(listener));
+
+        let config = McpServerConfig {
+            transport: McpTransport::Http,
+            url: Some(format!("http://127.0.0.1:{}/mcp", port)),
+            ..Default::default()
+        };
+        let report = check_server(&config, None, Duration::from_secs(10)).await;
+        server.abort();
+
+        assert!(report.ok, "{:?}", report.error);
+        assert_eq!(report.protocol_version.as_deref(), Some("2025-06-18"));
+        assert_eq!(
+            report.prompts,
+            [McpItem {
+                name: "review".to_string(),
+                description: None
+            }]
+        );
+        assert!(report.tools.is_empty());
+    }
+}
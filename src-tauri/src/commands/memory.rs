@@ -0,0 +1,590 @@
+//! CLAUDE.md memory files
+//!
+//! Claude loads memory in this order: the enterprise policy file, the user's
+//! `~/.claude/CLAUDE.md`, then `CLAUDE.md`, `.claude/CLAUDE.md` and
+//! `CLAUDE.local.md` in each directory from the top of the filesystem down to
+//! the working directory. Memory files in subdirectories are only read once
+//! Claude works with files there. Any of them can pull in other files with
+//! `@path` imports.
+
+use super::backups::{self, VersionedContent};
+use super::{claude_dir, CommandResult};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::command;
+
+const MEMORY_FILE: &str = "CLAUDE.md";
+const LOCAL_MEMORY_FILE: &str = "CLAUDE.local.md";
+/// Import hops Claude follows from a memory file
+const MAX_IMPORT_DEPTH: usize = 5;
+/// How far below the working directory memory files are looked for
+const MAX_SCAN_DEPTH: usize = 6;
+/// Directories never searched for memory files, besides hidden ones
+const SKIPPED_DIRS: [&str; 5] = ["node_modules", "target", "dist", "build", "vendor"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MemoryScope {
+    Enterprise,
+    User,
+    Project,
+    Local,
+    Subdirectory,
+}
+
+/// A file pulled in by an `@path` reference
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MemoryImport {
+    /// Reference as written, without the `@`
+    pub reference: String,
+    pub path: String,
+    /// File the reference appears in
+    pub imported_by: String,
+    /// 1 for references in the memory file itself
+    pub depth: usize,
+    pub exists: bool,
+    pub size: u64,
+    /// Why an existing file is not loaded, e.g. a cycle
+    pub skipped: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MemoryFile {
+    pub path: String,
+    pub scope: MemoryScope,
+    pub exists: bool,
+    pub size: u64,
+    /// False for subdirectory files, which load only when Claude works there
+    pub loaded: bool,
+    pub imports: Vec<MemoryImport>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MemoryReport {
+    /// In the order Claude loads them
+    pub files: Vec<MemoryFile>,
+    /// Size of the loaded files and their imports, each file counted once
+    pub total_bytes: u64,
+    /// Size of subdirectory files and their imports
+    pub on_demand_bytes: u64,
+}
+
+/// A place a memory file may be
+struct Candidate {
+    path: PathBuf,
+    scope: MemoryScope,
+    loaded: bool,
+    /// Listed even when missing, as a place to create one
+    suggested: bool,
+}
+
+/// Enterprise policy file managed by administrators
+fn enterprise_path() -> PathBuf {
+    if cfg!(target_os = "macos") {
+        PathBuf::from("/Library/Application Support/ClaudeCode").join(MEMORY_FILE)
+    } else if cfg!(windows) {
+        PathBuf::from(r"C:\ProgramData\ClaudeCode").join(MEMORY_FILE)
+    } else {
+        PathBuf::from("/etc/claude-code").join(MEMORY_FILE)
+    }
+}
+
+/// Whether a directory below the working directory is searched for memory
+fn is_scanned_dir(name: &str) -> bool {
+    !name.starts_with('.') && !SKIPPED_DIRS.contains(&name)
+}
+
+/// The memory files of a subdirectory
+fn push_subdirectory(dir: &Path, found: &mut Vec<Candidate>) {
+    for name in [MEMORY_FILE, LOCAL_MEMORY_FILE] {
+        let path = dir.join(name);
+        if path.is_file() {
+            found.push(Candidate {
+                path,
+                scope: MemoryScope::Subdirectory,
+                loaded: false,
+                suggested: false,
+            });
+        }
+    }
+}
+
+/// Memory files in subdirectories of `dir`, sorted by path
+fn scan_subdirectories(dir: &Path, depth: usize, found: &mut Vec<Candidate>) {
+    if depth > MAX_SCAN_DEPTH {
+        return;
+    }
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut subdirs: Vec<PathBuf> = entries
+        .flatten()
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+        .filter(|e| is_scanned_dir(&e.file_name().to_string_lossy()))
+        .map(|e| e.path())
+        .collect();
+    subdirs.sort();
+
+    for subdir in subdirs {
+        push_subdirectory(&subdir, found);
+        scan_subdirectories(&subdir, depth + 1, found);
+    }
+}
+
+/// Subdirectory memory files on the way from `cwd` down to `target`
+fn subdirectories_toward(cwd: &Path, target: &Path, found: &mut Vec<Candidate>) {
+    let target = file_identity(target);
+    let Some(relative) = target
+        .parent()
+        .and_then(|dir| dir.strip_prefix(identity(cwd)).ok())
+    else {
+        return;
+    };
+
+    let mut dir = cwd.to_path_buf();
+    for (depth, component) in relative.components().enumerate() {
+        let name = component.as_os_str().to_string_lossy();
+        if depth >= MAX_SCAN_DEPTH || !is_scanned_dir(&name) {
+            return;
+        }
+        dir.push(component);
+        push_subdirectory(&dir, found);
+    }
+}
+
+/// The enterprise and user files and those from the top of the filesystem
+/// down to `cwd`, which Claude loads at startup
+fn loaded_candidates(cwd: Option<&Path>) -> Vec<Candidate> {
+    let mut found = vec![
+        Candidate {
+            path: enterprise_path(),
+            scope: MemoryScope::Enterprise,
+            loaded: true,
+            suggested: false,
+        },
+        Candidate {
+            path: claude_dir().join(MEMORY_FILE),
+            scope: MemoryScope::User,
+            loaded: true,
+            suggested: true,
+        },
+    ];
+
+    if let Some(cwd) = cwd {
+        // From the top down, leaving out the filesystem root itself
+        let mut dirs: Vec<&Path> = cwd.ancestors().filter(|d| d.parent().is_some()).collect();
+        dirs.reverse();
+        for dir in dirs {
+            let here = dir == cwd;
+            for (path, scope, suggested) in [
+                (dir.join(MEMORY_FILE), MemoryScope::Project, here),
+                (
+                    dir.join(".claude").join(MEMORY_FILE),
+                    MemoryScope::Project,
+                    false,
+                ),
+                (dir.join(LOCAL_MEMORY_FILE), MemoryScope::Local, here),
+            ] {
+                found.push(Candidate {
+                    path,
+                    scope,
+                    loaded: true,
+                    suggested,
+                });
+            }
+        }
+    }
+    found
+}
+
+/// Drop missing files that aren't suggested and repeated paths
+fn existing(mut found: Vec<Candidate>) -> Vec<Candidate> {
+    // The user file doubles as a project file when working in the home directory
+    let mut seen = BTreeSet::new();
+    found.retain(|c| (c.suggested || c.path.is_file()) && seen.insert(c.path.clone()));
+    found
+}
+
+/// Every place Claude looks for memory when started in `cwd`, in load order
+fn candidates(cwd: Option<&Path>) -> Vec<Candidate> {
+    let mut found = loaded_candidates(cwd);
+    if let Some(cwd) = cwd {
+        scan_subdirectories(cwd, 1, &mut found);
+    }
+    existing(found)
+}
+
+/// The memory files that could list `target`, without scanning the tree
+///
+/// Subdirectory files are only looked for on the path down to `target`, so
+/// an import from a sibling folder's memory file is not found.
+fn candidates_for(cwd: Option<&Path>, target: &Path) -> Vec<Candidate> {
+    let mut found = loaded_candidates(cwd);
+    if let Some(cwd) = cwd {
+        subdirectories_toward(cwd, target, &mut found);
+    }
+    existing(found)
+}
+
+/// `@path` references outside code blocks and code spans
+fn import_references(content: &str) -> Vec<String> {
+    let mut references = Vec::new();
+    let mut fence: Option<&str> = None;
+
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            }
+            continue;
+        }
+        if let Some(marker) = ["```", "~~~"].into_iter().find(|m| trimmed.starts_with(m)) {
+            fence = Some(marker);
+            continue;
+        }
+
+        // Every other piece between backticks is a code span
+        let text: Vec<&str> = line.split('`').step_by(2).collect();
+        for word in text.join(" ").split_whitespace() {
+            let Some(reference) = word.strip_prefix('@') else {
+                continue;
+            };
+            let reference = reference.trim_end_matches(['.', ',', ';', ':', '!', '?', ')']);
+            if !reference.is_empty() {
+                references.push(reference.to_string());
+            }
+        }
+    }
+    references
+}
+
+/// Path of an import: `~/` is the home directory, relative paths are
+/// relative to the importing file
+fn resolve_import(importer: &Path, reference: &str) -> PathBuf {
+    if let Some(rest) = reference.strip_prefix("~/") {
+        return dirs::home_dir().unwrap_or_default().join(rest);
+    }
+    let path = Path::new(reference);
+    if path.is_absolute() {
+        return path.to_path_buf();
+    }
+    importer.parent().unwrap_or(Path::new("")).join(path)
+}
+
+fn identity(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Follow the imports of a file, depth first as Claude inlines them
+fn collect_imports(
+    importer: &Path,
+    content: &str,
+    depth: usize,
+    seen: &mut BTreeSet<PathBuf>,
+    imports: &mut Vec<MemoryImport>,
+) {
+    for reference in import_references(content) {
+        let path = resolve_import(importer, &reference);
+        let metadata = fs::metadata(&path).ok().filter(|m| m.is_file());
+        let mut import = MemoryImport {
+            reference,
+            path: path.to_string_lossy().into_owned(),
+            imported_by: importer.to_string_lossy().into_owned(),
+            depth,
+            exists: metadata.is_some(),
+            size: metadata.map_or(0, |m| m.len()),
+            skipped: None,
+        };
+        if !import.exists {
+            imports.push(import);
+            continue;
+        }
+
+        if depth > MAX_IMPORT_DEPTH {
+            import.skipped = Some(format!(
+                "Imports are followed at most {} deep",
+                MAX_IMPORT_DEPTH
+            ));
+        } else if !seen.insert(identity(&path)) {
+            import.skipped = Some("Already imported".to_string());
+        }
+        let follow = import.skipped.is_none();
+        imports.push(import);
+
+        if follow {
+            if let Ok(nested) = fs::read_to_string(&path) {
+                collect_imports(&path, &nested, depth + 1, seen, imports);
+            }
+        }
+    }
+}
+
+fn build_report(candidates: Vec<Candidate>) -> MemoryReport {
+    let mut report = MemoryReport {
+        files: Vec::new(),
+        total_bytes: 0,
+        on_demand_bytes: 0,
+    };
+    let mut counted = BTreeSet::new();
+
+    for candidate in candidates {
+        let content = fs::read_to_string(&candidate.path).ok();
+        let mut imports = Vec::new();
+        if let Some(content) = &content {
+            let mut seen = BTreeSet::from([identity(&candidate.path)]);
+            collect_imports(&candidate.path, content, 1, &mut seen, &mut imports);
+        }
+
+        let file = MemoryFile {
+            path: candidate.path.to_string_lossy().into_owned(),
+            scope: candidate.scope,
+            exists: content.is_some(),
+            size: content.as_ref().map_or(0, |c| c.len() as u64),
+            loaded: candidate.loaded,
+            imports,
+        };
+
+        let mut loaded_files: Vec<(PathBuf, u64)> = Vec::new();
+        if file.exists {
+            loaded_files.push((candidate.path.clone(), file.size));
+        }
+        loaded_files.extend(
+            file.imports
+                .iter()
+                .filter(|i| i.exists && i.skipped.is_none())
+                .map(|i| (PathBuf::from(&i.path), i.size)),
+        );
+        let bytes: u64 = loaded_files
+            .into_iter()
+            .filter(|(path, _)| counted.insert(identity(path)))
+            .map(|(_, size)| size)
+            .sum();
+        if file.loaded {
+            report.total_bytes += bytes;
+        } else {
+            report.on_demand_bytes += bytes;
+        }
+        report.files.push(file);
+    }
+    report
+}
+
+/// Like `identity`, but also for files not created yet
+fn file_identity(path: &Path) -> PathBuf {
+    match (path.parent(), path.file_name()) {
+        (Some(dir), Some(name)) if !path.exists() => identity(dir).join(name),
+        _ => identity(path),
+    }
+}
+
+/// Whether a report lists `path` as a memory file or an import of one
+fn is_listed(report: &MemoryReport, path: &Path) -> bool {
+    let target = file_identity(path);
+    report
+        .files
+        .iter()
+        .flat_map(|f| std::iter::once(&f.path).chain(f.imports.iter().map(|i| &i.path)))
+        .any(|listed| file_identity(Path::new(listed)) == target)
+}
+
+/// List the memory files that apply to a working directory, in load order
+///
+/// Without a working directory only the enterprise and user files are listed.
+#[command]
+pub async fn list_memory_files(cwd: Option<String>) -> CommandResult<MemoryReport> {
+    let cwd = cwd.filter(|c| !c.is_empty()).map(PathBuf::from);
+    // The working directory's subfolders are scanned, so stay off the runtime
+    match tokio::task::spawn_blocking(move || build_report(candidates(cwd.as_deref()))).await {
+        Ok(report) => CommandResult::ok(report),
+        Err(e) => CommandResult::err(format!("Failed to list memory files: {}", e)),
+    }
+}
+
+/// Read a memory file; a missing file reads as empty
+#[command]
+pub async fn read_memory_file(path: String) -> CommandResult<VersionedContent> {
+    match fs::read_to_string(&path) {
+        Ok(content) => CommandResult::ok(VersionedContent {
+            version: backups::content_version(content.as_bytes()),
+            content,
+        }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => CommandResult::ok(VersionedContent {
+            content: String::new(),
+            version: backups::MISSING_VERSION.to_string(),
+        }),
+        Err(e) => CommandResult::err(format!("Failed to read {}: {}", path, e)),
+    }
+}
+
+/// Write a memory or imported markdown file, returning its new version
+///
+/// Only memory files for `cwd` and their imports can be written, as
+/// `list_memory_files` reports them.
+#[command]
+pub async fn write_memory_file(
+    path: String,
+    content: String,
+    expected_version: Option<String>,
+    cwd: Option<String>,
+) -> CommandResult<String> {
+    let target = Path::new(&path);
+    let markdown = target
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("md"));
+    if !markdown {
+        return CommandResult::err(format!("{} is not a markdown file", path));
+    }
+    let cwd = cwd.filter(|c| !c.is_empty()).map(PathBuf::from);
+    let check = target.to_path_buf();
+    let listed = tokio::task::spawn_blocking(move || {
+        is_listed(
+            &build_report(candidates_for(cwd.as_deref(), &check)),
+            &check,
+        )
+    })
+    .await;
+    match listed {
+        Ok(true) => {}
+        Ok(false) => {
+            return CommandResult::err(format!(
+                "{} is not a memory file or import of this project",
+                path
+            ))
+        }
+        Err(e) => return CommandResult::err(format!("Failed to check {}: {}", path, e)),
+    }
+
+    match backups::safe_write(target, content.as_bytes(), expected_version.as_deref()) {
+        Ok(version) => CommandResult::ok(version),
+        Err(e) => CommandResult::err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_references() {
+        let content = "See @docs/style.md and @~/notes.md.\n\
+                       Mail me at dev@example.com, or run `npm i @scope/pkg`.\n\
+                       ```\n@not/an/import.md\n```\n\
+                       (@README)\n- @../shared/CLAUDE.md\n";
+        assert_eq!(
+            import_references(content),
+            ["docs/style.md", "~/notes.md", "../shared/CLAUDE.md"]
+        );
+    }
+
+    #[test]
+    fn test_hierarchy_and_imports() {
+        let root = std::env::temp_dir().join(format!("codepod-memory-{}", uuid::Uuid::new_v4()));
+        let app = root.join("app");
+        let cwd = app.join("src");
+        let write = |path: &Path, content: &str| {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        };
+        write(&root.join(MEMORY_FILE), "Team rules\n");
+        write(
+            &app.join(".claude").join(MEMORY_FILE),
+            "App @../docs/a.md\n",
+        );
+        write(&app.join("docs/a.md"), "A imports @b.md and @missing.md\n");
+        write(&app.join("docs/b.md"), "B imports @a.md again\n");
+        write(&cwd.join(LOCAL_MEMORY_FILE), "Mine\n");
+        write(&cwd.join("ui").join(MEMORY_FILE), "UI @../../docs/b.md\n");
+        write(&cwd.join("api").join(MEMORY_FILE), "API @notes.md\n");
+        write(&cwd.join("node_modules/pkg").join(MEMORY_FILE), "ignored\n");
+
+        let found: Vec<Candidate> = candidates(Some(&cwd))
+            .into_iter()
+            .filter(|c| c.path.starts_with(&root))
+            .collect();
+        let order: Vec<(PathBuf, MemoryScope)> = found
+            .iter()
+            .map(|c| (c.path.strip_prefix(&root).unwrap().to_path_buf(), c.scope))
+            .collect();
+        assert_eq!(
+            order,
+            [
+                (PathBuf::from("CLAUDE.md"), MemoryScope::Project),
+                (PathBuf::from("app/.claude/CLAUDE.md"), MemoryScope::Project),
+                (PathBuf::from("app/src/CLAUDE.md"), MemoryScope::Project),
+                (PathBuf::from("app/src/CLAUDE.local.md"), MemoryScope::Local),
+                (
+                    PathBuf::from("app/src/api/CLAUDE.md"),
+                    MemoryScope::Subdirectory
+                ),
+                (
+                    PathBuf::from("app/src/ui/CLAUDE.md"),
+                    MemoryScope::Subdirectory
+                ),
+            ]
+        );
+
+        let report = build_report(found);
+        assert!(is_listed(&report, &app.join("docs/../docs/b.md")));
+        assert!(is_listed(&report, &app.join("docs/missing.md")));
+        assert!(!is_listed(&report, &app.join("README.md")));
+
+        // Writes only look down the target's own path
+        let listed = |target: &Path| {
+            let report = build_report(candidates_for(Some(&cwd), target));
+            is_listed(&report, target)
+        };
+        assert!(listed(&cwd.join("ui").join(MEMORY_FILE)));
+        assert!(listed(&cwd.join("ui/../../docs/b.md")));
+        assert!(!listed(&cwd.join("node_modules/pkg").join(MEMORY_FILE)));
+        let paths: Vec<PathBuf> = candidates_for(Some(&cwd), &cwd.join("ui").join(MEMORY_FILE))
+            .into_iter()
+            .map(|c| c.path)
+            .collect();
+        assert!(!paths.contains(&cwd.join("api").join(MEMORY_FILE)));
+        fs::remove_dir_all(&root).unwrap();
+
+        let app_file = &report.files[1];
+        let imports: Vec<(&str, usize, bool, Option<&str>)> = app_file
+            .imports
+            .iter()
+            .map(|i| {
+                (
+                    i.reference.as_str(),
+                    i.depth,
+                    i.exists,
+                    i.skipped.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            imports,
+            [
+                ("../docs/a.md", 1, true, None),
+                ("b.md", 2, true, None),
+                ("a.md", 3, true, Some("Already imported")),
+                ("missing.md", 2, false, None),
+            ]
+        );
+        assert!(!report.files[2].exists);
+
+        // Team rules, app, a, b and the local file; b counts once
+        let loaded = [
+            "Team rules\n",
+            "App @../docs/a.md\n",
+            "A imports @b.md and @missing.md\n",
+            "B imports @a.md again\n",
+            "Mine\n",
+        ];
+        assert_eq!(
+            report.total_bytes,
+            loaded.iter().map(|c| c.len() as u64).sum::<u64>()
+        );
+        assert_eq!(
+            report.on_demand_bytes,
+            ("API @notes.md\n".len() + "UI @../../docs/b.md\n".len()) as u64
+        );
+    }
+}
@@ -1,9 +1,51 @@
+pub mod backups;
+pub mod batch;
+pub mod budget;
+pub mod bundles;
+pub mod capture;
 pub mod claude;
 pub mod config;
+pub mod definitions;
+pub mod frontmatter;
 pub mod fs;
 pub mod git;
+pub mod hooks;
+pub mod jobs;
+pub mod mcp;
+pub mod mcp_health;
+pub mod memory;
+pub mod plugins;
+pub mod scheduler;
+pub mod sessions;
+pub mod settings;
+pub mod skills;
+pub mod structured;
+pub mod templates;
+pub mod usage;
+pub mod watcher;
 
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// CodePod's own data directory
+///
+/// Defaults to `~/.codepod`; `CODEPOD_DATA_DIR` overrides it, e.g. in tests.
+pub fn codepod_dir() -> PathBuf {
+    match std::env::var_os("CODEPOD_DATA_DIR") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => dirs::home_dir().unwrap_or_default().join(".codepod"),
+    }
+}
+
+/// Claude CLI's configuration directory
+///
+/// Defaults to `~/.claude`; honours `CLAUDE_CONFIG_DIR` like the CLI does.
+pub fn claude_dir() -> PathBuf {
+    match std::env::var_os("CLAUDE_CONFIG_DIR") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => dirs::home_dir().unwrap_or_default().join(".claude"),
+    }
+}
 
 /// Unified command result wrapper
 #[derive(Debug, Serialize, Deserialize)]
@@ -0,0 +1,156 @@
+//! Installed Claude plugins
+//!
+//! Plugins are recorded in `~/.claude/plugins/installed_plugins.json`, keyed
+//! by `name@marketplace`. Each install directory may hold `commands`,
+//! `agents` and `skills` like a `.claude` directory. Whether a plugin is on
+//! is decided by `enabledPlugins` in settings.
+
+use super::claude_dir;
+use super::settings::{self, SettingsScope};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A plugin install visible from a project
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstalledPlugin {
+    /// Plugin name without the marketplace
+    pub name: String,
+    /// `name@marketplace` key used in settings
+    pub key: String,
+    pub path: PathBuf,
+    pub enabled: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PluginInstall {
+    install_path: PathBuf,
+    #[serde(default)]
+    scope: Option<String>,
+    #[serde(default)]
+    project_path: Option<String>,
+}
+
+/// Version 1 of the file has one install per plugin, later versions a list
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PluginInstalls {
+    One(PluginInstall),
+    Many(Vec<PluginInstall>),
+}
+
+#[derive(Deserialize)]
+struct InstalledPluginsFile {
+    #[serde(default)]
+    plugins: BTreeMap<String, PluginInstalls>,
+}
+
+/// Whether an install applies: user installs always, others only in their project
+fn install_visible(install: &PluginInstall, project_path: Option<&str>) -> bool {
+    match install.scope.as_deref() {
+        None | Some("user") => true,
+        Some(_) => match (install.project_path.as_deref(), project_path) {
+            (Some(installed), Some(project)) => Path::new(installed) == Path::new(project),
+            _ => false,
+        },
+    }
+}
+
+/// Whether settings enable a plugin; the most specific scope that mentions it wins
+fn plugin_enabled(key: &str, project_path: Option<&str>) -> bool {
+    let mut enabled = true;
+    for scope in SettingsScope::ALL {
+        if scope != SettingsScope::User && project_path.is_none() {
+            continue;
+        }
+        let value = settings::settings_path(scope, project_path)
+            .and_then(|path| settings::read_settings(&path))
+            .ok()
+            .and_then(|s| s.get("enabledPlugins")?.get(key)?.as_bool());
+        if let Some(value) = value {
+            enabled = value;
+        }
+    }
+    enabled
+}
+
+/// Plugins installed for the user or the given project
+///
+/// A missing or unreadable install file means no plugins.
+pub(crate) fn installed_plugins(project_path: Option<&str>) -> Vec<InstalledPlugin> {
+    let path = claude_dir().join("plugins").join("installed_plugins.json");
+    let Some(file) = fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<InstalledPluginsFile>(&content).ok())
+    else {
+        return vec![];
+    };
+    parse_installs(file, project_path)
+}
+
+fn parse_installs(file: InstalledPluginsFile, project_path: Option<&str>) -> Vec<InstalledPlugin> {
+    let mut plugins = Vec::new();
+    for (key, installs) in file.plugins {
+        let installs = match installs {
+            PluginInstalls::One(install) => vec![install],
+            PluginInstalls::Many(installs) => installs,
+        };
+        // A project install takes the place of the user one
+        let Some(install) = installs
+            .into_iter()
+            .filter(|i| install_visible(i, project_path))
+            .max_by_key(|i| i.project_path.is_some())
+        else {
+            continue;
+        };
+
+        let name = key.split('@').next().unwrap_or(&key).to_string();
+        plugins.push(InstalledPlugin {
+            name,
+            enabled: plugin_enabled(&key, project_path),
+            key,
+            path: install.install_path,
+        });
+    }
+    plugins
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_installs_versions() {
+        let file: InstalledPluginsFile = serde_json::from_str(
+            r#"{
+                "version": 2,
+                "plugins": {
+                    "review@tools": {"installPath": "/cache/review", "version": "1.0.0"},
+                    "deploy@acme": [
+                        {"scope": "user", "installPath": "/cache/deploy/1"},
+                        {"scope": "project", "projectPath": "/work/app", "installPath": "/cache/deploy/2"}
+                    ],
+                    "other@acme": [
+                        {"scope": "project", "projectPath": "/work/other", "installPath": "/cache/other"}
+                    ]
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let plugins = parse_installs(file, Some("/work/app"));
+        let found: Vec<(&str, &str, &Path)> = plugins
+            .iter()
+            .map(|p| (p.name.as_str(), p.key.as_str(), p.path.as_path()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("deploy", "deploy@acme", Path::new("/cache/deploy/2")),
+                ("review", "review@tools", Path::new("/cache/review")),
+            ]
+        );
+    }
+}
@@ -0,0 +1,649 @@
+//! Scheduled Claude tasks
+//!
+//! Runs prompts on cron schedules through the non-interactive invocation
+//! path. Tasks are stored in `~/.codepod/schedules.json` and every finished
+//! run is appended to `~/.codepod/task-runs.jsonl` for later review.
+
+use super::claude::{self, ClaudeAttempt, ClaudeOptions};
+use super::jobs::now_millis;
+use super::templates::{self, PromptTemplate, TemplateVariable, VariableKind};
+use super::{codepod_dir, CommandResult};
+use chrono::{DateTime, Local};
+use cron::Schedule;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{command, AppHandle, Emitter};
+use uuid::Uuid;
+
+/// How often the scheduler checks for due tasks
+const TICK: Duration = Duration::from_secs(15);
+
+// Task file lock and the runs currently in progress, keyed by task id
+lazy_static::lazy_static! {
+    static ref TASKS_LOCK: Mutex<()> = Mutex::new(());
+    static ref RUNNING: Arc<Mutex<HashMap<String, TaskRun>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+}
+
+/// A prompt that runs on a schedule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledTask {
+    pub id: String,
+    pub name: String,
+    /// Cron expression, with or without a leading seconds field
+    pub schedule: String,
+    pub cwd: Option<String>,
+    /// Prompt template body; rendered with the prompt template built-ins
+    /// plus `{{task}}`, the task name
+    pub prompt: String,
+    #[serde(default)]
+    pub options: ClaudeOptions,
+    pub enabled: bool,
+    pub created_at: u64,
+    pub last_run_at: Option<u64>,
+}
+
+/// Fields supplied when creating or updating a task
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledTaskInput {
+    pub name: String,
+    pub schedule: String,
+    pub cwd: Option<String>,
+    pub prompt: String,
+    #[serde(default)]
+    pub options: ClaudeOptions,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// Task information sent to frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledTaskInfo {
+    #[serde(flatten)]
+    pub task: ScheduledTask,
+    pub next_run_at: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskRunStatus {
+    Running,
+    Succeeded,
+    Failed,
+}
+
+/// One execution of a scheduled task
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskRun {
+    pub id: String,
+    pub task_id: String,
+    pub started_at: u64,
+    pub finished_at: Option<u64>,
+    pub status: TaskRunStatus,
+    /// Prompt after template rendering
+    pub prompt: String,
+    /// Final result text reported by Claude
+    pub output: Option<String>,
+    pub error: Option<String>,
+    pub cost_usd: Option<f64>,
+    #[serde(default)]
+    pub attempts: Vec<ClaudeAttempt>,
+}
+
+fn tasks_path() -> PathBuf {
+    codepod_dir().join("schedules.json")
+}
+
+fn runs_path() -> PathBuf {
+    codepod_dir().join("task-runs.jsonl")
+}
+
+const WEEKDAYS: [&str; 7] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+/// Name a standard cron weekday number (0-7, with 0 and 7 both Sunday)
+///
+/// Anything else is returned as-is for the `cron` crate to validate.
+fn weekday_name(value: &str) -> Result<String, String> {
+    match value.parse::<usize>() {
+        Ok(n) if n <= 7 => Ok(WEEKDAYS[n % 7].to_string()),
+        Ok(_) => Err(format!("Invalid weekday '{}'", value)),
+        Err(_) => Ok(value.to_string()),
+    }
+}
+
+/// Names of the weekdays a numeric `start-end/step` range picks
+fn expand_weekdays(start: usize, end: usize, step: &str) -> Result<String, String> {
+    let step: usize = step
+        .parse()
+        .ok()
+        .filter(|s| *s > 0)
+        .ok_or_else(|| format!("Invalid weekday step '{}'", step))?;
+    if start > end || end > 7 {
+        return Err(format!("Invalid weekday range '{}-{}'", start, end));
+    }
+    let mut names: Vec<&str> = Vec::new();
+    for day in (start..=end).step_by(step) {
+        let name = WEEKDAYS[day % 7];
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    Ok(names.join(","))
+}
+
+/// Rewrite numeric weekdays in a five-field day-of-week field as names
+///
+/// The `cron` crate numbers weekdays 1-7 starting on Sunday, while standard
+/// cron uses 0-7 starting on Sunday (7 also Sunday). Names mean the same to
+/// both, so `1-5` becomes `MON-FRI`.
+fn convert_weekdays(field: &str) -> Result<String, String> {
+    let mut parts = Vec::new();
+    for part in field.split(',') {
+        let (base, step) = match part.split_once('/') {
+            Some((base, step)) => (base, Some(step)),
+            None => (part, None),
+        };
+        let range = base.split_once('-');
+        let numeric = range.and_then(|(start, end)| Some((start.parse().ok()?, end.parse().ok()?)));
+        if let (Some((start, end)), Some(step)) = (numeric, step) {
+            // Steps count from the range start, which shifts once named
+            parts.push(expand_weekdays(start, end, step)?);
+            continue;
+        }
+        let converted = match range {
+            // Both Sundays, so every day
+            Some(("0", "7")) => "SUN-SAT".to_string(),
+            // `5-7` ends on Sunday, which sorts first once named
+            Some((start, "7")) => format!("{}-SAT,SUN", weekday_name(start)?),
+            Some((start, end)) => format!("{}-{}", weekday_name(start)?, weekday_name(end)?),
+            None if base == "*" => base.to_string(),
+            None => weekday_name(base)?,
+        };
+        parts.push(match step {
+            Some(step) => format!("{}/{}", converted, step),
+            None => converted,
+        });
+    }
+    Ok(parts.join(","))
+}
+
+/// Parse a cron expression, accepting the common five-field form
+///
+/// Five-field expressions (`min hour dom mon dow`) run at second zero and
+/// use standard cron weekday numbers (0 or 7 = Sunday, 1 = Monday). Longer
+/// expressions are passed to the `cron` crate unchanged.
+fn parse_schedule(expr: &str) -> Result<Schedule, String> {
+    let expr = expr.trim();
+    let fields: Vec<&str> = expr.split_whitespace().collect();
+    let full = if fields.len() == 5 {
+        let weekdays = convert_weekdays(fields[4])
+            .map_err(|e| format!("Invalid schedule '{}': {}", expr, e))?;
+        format!("0 {} {}", fields[..4].join(" "), weekdays)
+    } else {
+        expr.to_string()
+    };
+
+    Schedule::from_str(&full).map_err(|e| format!("Invalid schedule '{}': {}", expr, e))
+}
+
+/// Check whether the schedule fires in `(after, until]`
+fn is_due(schedule: &Schedule, after: DateTime<Local>, until: DateTime<Local>) -> bool {
+    schedule
+        .after(&after)
+        .next()
+        .is_some_and(|next| next <= until)
+}
+
+fn next_run(task: &ScheduledTask) -> Option<u64> {
+    if !task.enabled {
+        return None;
+    }
+    let schedule = parse_schedule(&task.schedule).ok()?;
+    schedule
+        .upcoming(Local)
+        .next()
+        .map(|t| t.timestamp_millis() as u64)
+}
+
+/// Wrap a task prompt as a template with `{{task}}` bound to the task name
+fn task_template(task: &ScheduledTask) -> PromptTemplate {
+    PromptTemplate {
+        name: task.id.clone(),
+        description: None,
+        body: task.prompt.clone(),
+        variables: vec![TemplateVariable {
+            name: "task".to_string(),
+            kind: VariableKind::String,
+            description: None,
+            required: false,
+            default: Some(task.name.clone()),
+            choices: vec![],
+        }],
+    }
+}
+
+fn load_tasks() -> Result<Vec<ScheduledTask>, String> {
+    match fs::read_to_string(tasks_path()) {
+        Ok(content) => {
+            serde_json::from_str(&content).map_err(|e| format!("Invalid schedules file: {}", e))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(vec![]),
+        Err(e) => Err(format!("Failed to read schedules: {}", e)),
+    }
+}
+
+fn save_tasks(tasks: &[ScheduledTask]) -> Result<(), String> {
+    let path = tasks_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
+    }
+
+    let content = serde_json::to_string_pretty(tasks)
+        .map_err(|e| format!("Failed to serialize schedules: {}", e))?;
+    fs::write(&path, content).map_err(|e| format!("Failed to write schedules: {}", e))
+}
+
+/// Load, modify and save the task list under the task lock
+fn update_tasks<T>(
+    f: impl FnOnce(&mut Vec<ScheduledTask>) -> Result<T, String>,
+) -> Result<T, String> {
+    let _guard = TASKS_LOCK.lock();
+    let mut tasks = load_tasks()?;
+    let result = f(&mut tasks)?;
+    save_tasks(&tasks)?;
+    Ok(result)
+}
+
+fn append_run(run: &TaskRun) -> std::io::Result<()> {
+    let path = runs_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut line = serde_json::to_string(run)?;
+    line.push('\n');
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(line.as_bytes())
+}
+
+fn load_runs() -> std::io::Result<Vec<TaskRun>> {
+    match fs::read_to_string(runs_path()) {
+        Ok(content) => Ok(content
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(vec![]),
+        Err(e) => Err(e),
+    }
+}
+
+fn to_info(task: ScheduledTask) -> ScheduledTaskInfo {
+    let next_run_at = next_run(&task);
+    ScheduledTaskInfo { task, next_run_at }
+}
+
+/// Run a task once through the non-interactive invocation path
+async fn run_task(app: AppHandle, task: ScheduledTask) -> Result<TaskRun, String> {
+    let now = Local::now();
+    let rendered = templates::render_blocking(
+        task_template(&task),
+        HashMap::new(),
+        templates::working_dir(task.cwd.as_deref()),
+    )
+    .await;
+    let mut run = TaskRun {
+        id: Uuid::new_v4().to_string(),
+        task_id: task.id.clone(),
+        started_at: now.timestamp_millis() as u64,
+        finished_at: None,
+        status: TaskRunStatus::Running,
+        prompt: match &rendered {
+            Ok(r) => r.prompt.clone(),
+            Err(_) => task.prompt.clone(),
+        },
+        output: None,
+        error: None,
+        cost_usd: None,
+        attempts: vec![],
+    };
+
+    {
+        let mut running = RUNNING.lock();
+        if running.contains_key(&task.id) {
+            return Err(format!("Task already running: {}", task.name));
+        }
+        running.insert(task.id.clone(), run.clone());
+    }
+    let _ = app.emit("scheduled-task-run", &run);
+
+    let mut options = task.options.clone();
+    if task.cwd.is_some() {
+        options.cwd = task.cwd.clone();
+    }
+
+    let result = match rendered {
+        Ok(_) => claude::execute_claude_job(&app, &run.id, &run.prompt, &options).await,
+        Err(e) => CommandResult::err(e),
+    };
+    let invocation = result.data.unwrap_or_default();
+    let output: Option<serde_json::Value> = invocation
+        .output
+        .as_deref()
+        .and_then(|o| serde_json::from_str(o.trim()).ok());
+
+    run.finished_at = Some(now_millis());
+    run.status = if result.success {
+        TaskRunStatus::Succeeded
+    } else {
+        TaskRunStatus::Failed
+    };
+    run.output = output
+        .as_ref()
+        .and_then(|o| o.get("result"))
+        .and_then(|v| v.as_str())
+        .map(String::from);
+    run.cost_usd = output
+        .as_ref()
+        .and_then(|o| o.get("total_cost_usd"))
+        .and_then(|v| v.as_f64());
+    run.error = result.error;
+    run.attempts = invocation.attempts;
+
+    RUNNING.lock().remove(&task.id);
+
+    if let Err(e) = append_run(&run) {
+        log::error!("Failed to record task run: {}", e);
+    }
+    let finished_at = run.finished_at;
+    if let Err(e) = update_tasks(|tasks| {
+        if let Some(t) = tasks.iter_mut().find(|t| t.id == task.id) {
+            t.last_run_at = finished_at;
+        }
+        Ok(())
+    }) {
+        log::error!("{}", e);
+    }
+
+    let _ = app.emit("scheduled-task-run", &run);
+    Ok(run)
+}
+
+/// Start the background loop that runs due tasks
+///
+/// Runs missed while the app was closed are not caught up.
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut last_check = Local::now();
+
+        loop {
+            tokio::time::sleep(TICK).await;
+            let now = Local::now();
+
+            let tasks = match load_tasks() {
+                Ok(t) => t,
+                Err(e) => {
+                    log::error!("{}", e);
+                    last_check = now;
+                    continue;
+                }
+            };
+
+            for task in tasks.into_iter().filter(|t| t.enabled) {
+                match parse_schedule(&task.schedule) {
+                    Ok(schedule) if is_due(&schedule, last_check, now) => {
+                        let app = app.clone();
+                        tauri::async_runtime::spawn(async move {
+                            if let Err(e) = run_task(app, task).await {
+                                log::warn!("{}", e);
+                            }
+                        });
+                    }
+                    Ok(_) => {}
+                    Err(e) => log::warn!("Skipping task {}: {}", task.name, e),
+                }
+            }
+
+            last_check = now;
+        }
+    });
+}
+
+/// List scheduled tasks with their next run time
+#[command]
+pub async fn list_scheduled_tasks() -> CommandResult<Vec<ScheduledTaskInfo>> {
+    match load_tasks() {
+        Ok(tasks) => CommandResult::ok(tasks.into_iter().map(to_info).collect()),
+        Err(e) => CommandResult::err(e),
+    }
+}
+
+/// Create a scheduled task
+#[command]
+pub async fn create_scheduled_task(input: ScheduledTaskInput) -> CommandResult<ScheduledTaskInfo> {
+    if let Err(e) = parse_schedule(&input.schedule) {
+        return CommandResult::err(e);
+    }
+
+    let task = ScheduledTask {
+        id: Uuid::new_v4().to_string(),
+        name: input.name,
+        schedule: input.schedule,
+        cwd: input.cwd,
+        prompt: input.prompt,
+        options: input.options,
+        enabled: input.enabled,
+        created_at: now_millis(),
+        last_run_at: None,
+    };
+
+    let created = task.clone();
+    match update_tasks(|tasks| {
+        tasks.push(task);
+        Ok(())
+    }) {
+        Ok(()) => CommandResult::ok(to_info(created)),
+        Err(e) => CommandResult::err(e),
+    }
+}
+
+/// Update a scheduled task
+#[command]
+pub async fn update_scheduled_task(
+    task_id: String,
+    input: ScheduledTaskInput,
+) -> CommandResult<ScheduledTaskInfo> {
+    if let Err(e) = parse_schedule(&input.schedule) {
+        return CommandResult::err(e);
+    }
+
+    let result = update_tasks(|tasks| {
+        let task = tasks
+            .iter_mut()
+            .find(|t| t.id == task_id)
+            .ok_or_else(|| format!("Task not found: {}", task_id))?;
+
+        task.name = input.name;
+        task.schedule = input.schedule;
+        task.cwd = input.cwd;
+        task.prompt = input.prompt;
+        task.options = input.options;
+        task.enabled = input.enabled;
+        Ok(task.clone())
+    });
+
+    match result {
+        Ok(task) => CommandResult::ok(to_info(task)),
+        Err(e) => CommandResult::err(e),
+    }
+}
+
+/// Delete a scheduled task
+#[command]
+pub async fn delete_scheduled_task(task_id: String) -> CommandResult<()> {
+    let result = update_tasks(|tasks| {
+        let before = tasks.len();
+        tasks.retain(|t| t.id != task_id);
+        if tasks.len() == before {
+            return Err(format!("Task not found: {}", task_id));
+        }
+        Ok(())
+    });
+
+    match result {
+        Ok(()) => CommandResult::ok(()),
+        Err(e) => CommandResult::err(e),
+    }
+}
+
+/// Run a scheduled task immediately and wait for it to finish
+#[command]
+pub async fn run_scheduled_task_now(app: AppHandle, task_id: String) -> CommandResult<TaskRun> {
+    let task = match load_tasks() {
+        Ok(tasks) => tasks.into_iter().find(|t| t.id == task_id),
+        Err(e) => return CommandResult::err(e),
+    };
+
+    match task {
+        Some(task) => match run_task(app, task).await {
+            Ok(run) => CommandResult::ok(run),
+            Err(e) => CommandResult::err(e),
+        },
+        None => CommandResult::err(format!("Task not found: {}", task_id)),
+    }
+}
+
+/// List task runs, newest first, including runs still in progress
+#[command]
+pub async fn list_task_runs(
+    task_id: Option<String>,
+    limit: Option<usize>,
+) -> CommandResult<Vec<TaskRun>> {
+    let mut runs = match load_runs() {
+        Ok(r) => r,
+        Err(e) => return CommandResult::err(format!("Failed to read task runs: {}", e)),
+    };
+    runs.extend(RUNNING.lock().values().cloned());
+
+    if let Some(id) = &task_id {
+        runs.retain(|r| &r.task_id == id);
+    }
+    runs.sort_by_key(|r| std::cmp::Reverse(r.started_at));
+    runs.truncate(limit.unwrap_or(50));
+
+    CommandResult::ok(runs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Datelike, TimeZone};
+    use std::path::Path;
+
+    fn task(prompt: &str) -> ScheduledTask {
+        ScheduledTask {
+            id: "t1".to_string(),
+            name: "Digest".to_string(),
+            schedule: "0 9 * * *".to_string(),
+            cwd: Some("/repo".to_string()),
+            prompt: prompt.to_string(),
+            options: ClaudeOptions::default(),
+            enabled: true,
+            created_at: 0,
+            last_run_at: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_schedule_five_fields() {
+        let schedule = parse_schedule("30 9 * * *").unwrap();
+        let after = Local.with_ymd_and_hms(2025, 3, 1, 8, 0, 0).unwrap();
+        let next = schedule.after(&after).next().unwrap();
+        assert_eq!(next, Local.with_ymd_and_hms(2025, 3, 1, 9, 30, 0).unwrap());
+    }
+
+    #[test]
+    fn test_parse_schedule_numeric_weekdays() {
+        // 2025-03-02 is a Sunday, 2025-03-03 a Monday
+        let schedule = parse_schedule("0 9 * * 1-5").unwrap();
+        let saturday = Local.with_ymd_and_hms(2025, 3, 1, 10, 0, 0).unwrap();
+        let next = schedule.after(&saturday).next().unwrap();
+        assert_eq!(next, Local.with_ymd_and_hms(2025, 3, 3, 9, 0, 0).unwrap());
+
+        let sunday = Local.with_ymd_and_hms(2025, 3, 2, 8, 0, 0).unwrap();
+        assert!(!is_due(
+            &schedule,
+            sunday,
+            sunday + chrono::Duration::hours(2)
+        ));
+
+        for expr in ["0 9 * * 0", "0 9 * * 7"] {
+            let schedule = parse_schedule(expr).unwrap();
+            let next = schedule.after(&saturday).next().unwrap();
+            assert_eq!(next, Local.with_ymd_and_hms(2025, 3, 2, 9, 0, 0).unwrap());
+        }
+
+        let weekend = parse_schedule("0 9 * * 6-7").unwrap();
+        let next = weekend.after(&saturday).next().unwrap();
+        assert_eq!(next, Local.with_ymd_and_hms(2025, 3, 2, 9, 0, 0).unwrap());
+
+        let days = |expr: &str| -> Vec<u32> {
+            let from = Local.with_ymd_and_hms(2025, 3, 1, 10, 0, 0).unwrap();
+            let schedule = parse_schedule(expr).unwrap();
+            schedule.after(&from).take(7).map(|t| t.day()).collect()
+        };
+        assert_eq!(days("0 9 * * 0-7"), [2, 3, 4, 5, 6, 7, 8]);
+        // Sunday, Tuesday, Thursday and Saturday
+        assert_eq!(days("0 9 * * 0-7/2"), [2, 4, 6, 8, 9, 11, 13]);
+        assert_eq!(days("0 9 * * 1-7/3"), [2, 3, 6, 9, 10, 13, 16]);
+
+        assert!(parse_schedule("0 9 * * 8").is_err());
+    }
+
+    #[test]
+    fn test_parse_schedule_invalid() {
+        assert!(parse_schedule("not a schedule").is_err());
+    }
+
+    #[test]
+    fn test_is_due() {
+        let schedule = parse_schedule("0 9 * * *").unwrap();
+        let before = Local.with_ymd_and_hms(2025, 3, 1, 8, 59, 50).unwrap();
+        let after = Local.with_ymd_and_hms(2025, 3, 1, 9, 0, 5).unwrap();
+
+        assert!(is_due(&schedule, before, after));
+        assert!(!is_due(
+            &schedule,
+            after,
+            after + chrono::Duration::seconds(15)
+        ));
+    }
+
+    #[test]
+    fn test_render_prompt() {
+        let now = Local.with_ymd_and_hms(2025, 3, 1, 9, 0, 0).unwrap();
+        let template = task_template(&task(
+            "{{task}}: summarise commits since {{yesterday}} in {{cwd}} ({{date}})",
+        ));
+        let rendered = templates::render_at(&template, &HashMap::new(), Path::new("/repo"), now);
+        assert_eq!(
+            rendered.unwrap().prompt,
+            "Digest: summarise commits since 2025-02-28 in /repo (2025-03-01)"
+        );
+
+        let prompt = "Fix the {{ foo }} binding in {{#each items}}";
+        let template = task_template(&task(prompt));
+        let rendered = templates::render_at(&template, &HashMap::new(), Path::new("/repo"), now);
+        assert_eq!(rendered.unwrap().prompt, prompt);
+    }
+}
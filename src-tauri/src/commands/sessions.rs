@@ -0,0 +1,370 @@
+//! Session forking
+//!
+//! Copies a Claude session transcript (`~/.claude/projects/<project>/<id>.jsonl`)
+//! up to a chosen message into a new session, which can then be resumed with
+//! `--resume` through `create_claude_pty` or `invoke_claude_stream`. Forks and
+//! resumes that change the session id are recorded in an append-only lineage
+//! file (`~/.codepod/session-lineage.jsonl`) so the UI can draw the tree.
+
+use super::jobs::now_millis;
+use super::{claude_dir, codepod_dir, CommandResult};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use tauri::command;
+use uuid::Uuid;
+
+/// Characters of message text shown in previews
+const PREVIEW_CHARS: usize = 120;
+
+// Serializes appends to the lineage file
+lazy_static::lazy_static! {
+    static ref LINEAGE_LOCK: Mutex<()> = Mutex::new(());
+}
+
+/// How a child session came from its parent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionLinkKind {
+    Fork,
+    Resume,
+}
+
+/// One edge of the conversation tree
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionLink {
+    pub session_id: String,
+    pub parent_session_id: String,
+    pub kind: SessionLinkKind,
+    /// Last transcript entry copied from the parent, for forks
+    pub fork_message_uuid: Option<String>,
+    pub label: Option<String>,
+    pub cwd: Option<String>,
+    pub created_at: u64,
+}
+
+/// A transcript entry that can be used as a fork point
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptMessage {
+    pub uuid: String,
+    pub parent_uuid: Option<String>,
+    /// Entry type, `user` or `assistant`
+    pub kind: String,
+    pub timestamp: Option<String>,
+    pub preview: String,
+}
+
+/// A newly forked session, ready to be resumed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionFork {
+    pub session_id: String,
+    pub parent_session_id: String,
+    pub transcript_path: String,
+    /// Working directory of the parent session; resume from here
+    pub cwd: Option<String>,
+    pub messages: usize,
+}
+
+fn lineage_path() -> PathBuf {
+    codepod_dir().join("session-lineage.jsonl")
+}
+
+/// Locate a session transcript in any project directory
+pub(crate) fn find_transcript(session_id: &str) -> Result<PathBuf, String> {
+    if session_id.is_empty() || session_id.contains(['/', '\\', '.']) {
+        return Err(format!("Invalid session id: {}", session_id));
+    }
+
+    let projects = claude_dir().join("projects");
+    let file_name = format!("{}.jsonl", session_id);
+    let entries = fs::read_dir(&projects)
+        .map_err(|e| format!("Failed to read {}: {}", projects.display(), e))?;
+
+    entries
+        .flatten()
+        .map(|entry| entry.path().join(&file_name))
+        .find(|path| path.is_file())
+        .ok_or_else(|| format!("Session not found: {}", session_id))
+}
+
+fn str_field<'a>(entry: &'a serde_json::Value, key: &str) -> Option<&'a str> {
+    entry.get(key).and_then(|v| v.as_str())
+}
+
+fn message_id(entry: &serde_json::Value) -> Option<&str> {
+    entry
+        .get("message")
+        .and_then(|m| m.get("id"))
+        .and_then(|v| v.as_str())
+}
+
+/// Short text describing a user or assistant entry
+fn preview(entry: &serde_json::Value) -> String {
+    let content = entry.get("message").and_then(|m| m.get("content"));
+
+    let text = match content {
+        Some(serde_json::Value::String(text)) => text.clone(),
+        Some(serde_json::Value::Array(blocks)) => blocks
+            .iter()
+            .find_map(|block| match str_field(block, "type") {
+                Some("text") => str_field(block, "text").map(String::from),
+                Some("tool_use") => str_field(block, "name").map(|n| format!("[tool: {}]", n)),
+                Some("tool_result") => Some("[tool result]".to_string()),
+                _ => None,
+            })
+            .unwrap_or_default(),
+        _ => String::new(),
+    };
+
+    let text = text.trim();
+    if text.chars().count() > PREVIEW_CHARS {
+        let cut: String = text.chars().take(PREVIEW_CHARS).collect();
+        format!("{}…", cut)
+    } else {
+        text.to_string()
+    }
+}
+
+/// List the user and assistant entries of a transcript
+fn transcript_messages(content: &str) -> Vec<TranscriptMessage> {
+    content
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .filter(|entry| matches!(str_field(entry, "type"), Some("user" | "assistant")))
+        .filter_map(|entry| {
+            Some(TranscriptMessage {
+                uuid: str_field(&entry, "uuid")?.to_string(),
+                parent_uuid: str_field(&entry, "parentUuid").map(String::from),
+                kind: str_field(&entry, "type")?.to_string(),
+                timestamp: str_field(&entry, "timestamp").map(String::from),
+                preview: preview(&entry),
+            })
+        })
+        .collect()
+}
+
+/// Copy transcript entries up to `message_uuid` under a new session id
+///
+/// A streamed assistant message is split across several entries sharing one
+/// message id; forking at any of them keeps the whole message. Returns the
+/// new transcript, the number of entries copied and the last known cwd.
+fn fork_transcript(
+    content: &str,
+    message_uuid: &str,
+    new_session_id: &str,
+) -> Result<(String, usize, Option<String>), String> {
+    let entries: Vec<serde_json::Value> = content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+
+    let target = entries
+        .iter()
+        .position(|e| str_field(e, "uuid") == Some(message_uuid))
+        .ok_or_else(|| format!("Message not found in transcript: {}", message_uuid))?;
+
+    let mut end = target + 1;
+    if let Some(id) = message_id(&entries[target]) {
+        while end < entries.len() && message_id(&entries[end]) == Some(id) {
+            end += 1;
+        }
+    }
+
+    let mut output = String::new();
+    let mut cwd = None;
+    for mut entry in entries.into_iter().take(end) {
+        if let Some(dir) = str_field(&entry, "cwd") {
+            cwd = Some(dir.to_string());
+        }
+        if let Some(object) = entry.as_object_mut() {
+            if object.contains_key("sessionId") {
+                object.insert("sessionId".to_string(), new_session_id.into());
+            }
+        }
+        output.push_str(&entry.to_string());
+        output.push('\n');
+    }
+
+    Ok((output, end, cwd))
+}
+
+fn append_link(link: &SessionLink) -> Result<(), String> {
+    let _guard = LINEAGE_LOCK.lock();
+
+    let path = lineage_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
+    }
+
+    let mut line =
+        serde_json::to_string(link).map_err(|e| format!("Failed to serialize lineage: {}", e))?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .map_err(|e| format!("Failed to write session lineage: {}", e))
+}
+
+fn load_links() -> Result<Vec<SessionLink>, String> {
+    match fs::read_to_string(lineage_path()) {
+        Ok(content) => Ok(content
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(vec![]),
+        Err(e) => Err(format!("Failed to read session lineage: {}", e)),
+    }
+}
+
+/// Record that resuming `parent` continued under a new session id
+pub(crate) fn record_resume(parent_session_id: &str, session_id: &str, cwd: Option<&str>) {
+    if parent_session_id == session_id {
+        return;
+    }
+
+    let link = SessionLink {
+        session_id: session_id.to_string(),
+        parent_session_id: parent_session_id.to_string(),
+        kind: SessionLinkKind::Resume,
+        fork_message_uuid: None,
+        label: None,
+        cwd: cwd.map(String::from),
+        created_at: now_millis(),
+    };
+    if let Err(e) = append_link(&link) {
+        log::warn!("{}", e);
+    }
+}
+
+/// List the messages of a session that can be forked from
+#[command]
+pub async fn list_transcript_messages(session_id: String) -> CommandResult<Vec<TranscriptMessage>> {
+    let path = match find_transcript(&session_id) {
+        Ok(p) => p,
+        Err(e) => return CommandResult::err(e),
+    };
+
+    match fs::read_to_string(&path) {
+        Ok(content) => CommandResult::ok(transcript_messages(&content)),
+        Err(e) => CommandResult::err(format!("Failed to read transcript: {}", e)),
+    }
+}
+
+/// Fork a session at a message into a new session id
+///
+/// The new transcript sits next to the parent's, so resuming it from the
+/// returned `cwd` continues the conversation from the chosen message.
+#[command]
+pub async fn fork_claude_session(
+    session_id: String,
+    message_uuid: String,
+    label: Option<String>,
+) -> CommandResult<SessionFork> {
+    let path = match find_transcript(&session_id) {
+        Ok(p) => p,
+        Err(e) => return CommandResult::err(e),
+    };
+    let content = match fs::read_to_string(&path) {
+        Ok(c) => c,
+        Err(e) => return CommandResult::err(format!("Failed to read transcript: {}", e)),
+    };
+
+    let new_session_id = Uuid::new_v4().to_string();
+    let (transcript, messages, cwd) =
+        match fork_transcript(&content, &message_uuid, &new_session_id) {
+            Ok(f) => f,
+            Err(e) => return CommandResult::err(e),
+        };
+
+    let new_path = path.with_file_name(format!("{}.jsonl", new_session_id));
+    if let Err(e) = fs::write(&new_path, transcript) {
+        return CommandResult::err(format!("Failed to write transcript: {}", e));
+    }
+
+    let link = SessionLink {
+        session_id: new_session_id.clone(),
+        parent_session_id: session_id.clone(),
+        kind: SessionLinkKind::Fork,
+        fork_message_uuid: Some(message_uuid),
+        label,
+        cwd: cwd.clone(),
+        created_at: now_millis(),
+    };
+    if let Err(e) = append_link(&link) {
+        let _ = fs::remove_file(&new_path);
+        return CommandResult::err(e);
+    }
+
+    CommandResult::ok(SessionFork {
+        session_id: new_session_id,
+        parent_session_id: session_id,
+        transcript_path: new_path.to_string_lossy().to_string(),
+        cwd,
+        messages,
+    })
+}
+
+/// List every recorded fork and resume, oldest first
+#[command]
+pub async fn get_session_lineage() -> CommandResult<Vec<SessionLink>> {
+    match load_links() {
+        Ok(links) => CommandResult::ok(links),
+        Err(e) => CommandResult::err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn transcript() -> String {
+        [
+            json!({"type": "summary", "summary": "Fix tests", "leafUuid": "a2"}),
+            json!({"type": "user", "uuid": "u1", "parentUuid": null, "sessionId": "old",
+                   "cwd": "/repo", "message": {"role": "user", "content": "Fix the tests"}}),
+            json!({"type": "assistant", "uuid": "a1", "parentUuid": "u1", "sessionId": "old",
+                   "message": {"id": "msg_1", "content": [{"type": "text", "text": "Looking"}]}}),
+            json!({"type": "assistant", "uuid": "a2", "parentUuid": "a1", "sessionId": "old",
+                   "message": {"id": "msg_1", "content": [{"type": "tool_use", "name": "Bash"}]}}),
+            json!({"type": "user", "uuid": "u2", "parentUuid": "a2", "sessionId": "old",
+                   "message": {"role": "user", "content": [{"type": "tool_result"}]}}),
+        ]
+        .iter()
+        .map(|v| v.to_string() + "\n")
+        .collect()
+    }
+
+    #[test]
+    fn test_fork_transcript_keeps_whole_message() {
+        let (output, count, cwd) = fork_transcript(&transcript(), "a1", "new").unwrap();
+        assert_eq!(count, 4);
+        assert_eq!(cwd.as_deref(), Some("/repo"));
+
+        let entries: Vec<serde_json::Value> = output
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(entries.last().unwrap()["uuid"], "a2");
+        assert!(entries[1..].iter().all(|e| e["sessionId"] == "new"));
+        assert!(entries[0].get("sessionId").is_none());
+
+        assert!(fork_transcript(&transcript(), "missing", "new").is_err());
+    }
+
+    #[test]
+    fn test_transcript_messages_previews() {
+        let messages = transcript_messages(&transcript());
+        let previews: Vec<&str> = messages.iter().map(|m| m.preview.as_str()).collect();
+        assert_eq!(
+            previews,
+            ["Fix the tests", "Looking", "[tool: Bash]", "[tool result]"]
+        );
+        assert_eq!(messages[1].parent_uuid.as_deref(), Some("u1"));
+    }
+}
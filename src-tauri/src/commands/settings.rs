@@ -0,0 +1,650 @@
+//! Claude settings files
+//!
+//! Claude reads `settings.json` at several scopes: the user's
+//! `~/.claude/settings.json`, a project's shared `.claude/settings.json` and
+//! its uncommitted `.claude/settings.local.json`. This module models those
+//! files, validates them before they are written and merges them into the
+//! effective settings Claude would use.
+
+use super::backups;
+use super::claude_dir;
+use super::hooks::{self, HookEvent, HooksConfig};
+use super::CommandResult;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::command;
+
+/// Which settings file to read or write
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SettingsScope {
+    User,
+    Project,
+    Local,
+}
+
+impl SettingsScope {
+    /// Every scope, from lowest to highest precedence
+    pub const ALL: [SettingsScope; 3] = [Self::User, Self::Project, Self::Local];
+}
+
+/// Permission mode Claude starts in
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PermissionMode {
+    Default,
+    AcceptEdits,
+    Plan,
+    BypassPermissions,
+    /// A mode CodePod does not know, kept as written
+    #[serde(untagged)]
+    Other(String),
+}
+
+/// Tool permission rules such as `Bash(npm run test:*)` or `Read(./.env)`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Permissions {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deny: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ask: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub additional_directories: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_mode: Option<PermissionMode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disable_bypass_permissions_mode: Option<String>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Custom status line shown by the interactive CLI
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatusLine {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub command: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub padding: Option<i64>,
+}
+
+/// Contents of a Claude `settings.json`
+///
+/// Keys CodePod does not model are kept in `extra` so nothing is lost when
+/// a file is read and written back.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClaudeSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env: Option<BTreeMap<String, String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<Permissions>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hooks: Option<HooksConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status_line: Option<StatusLine>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key_helper: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cleanup_period_days: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub include_co_authored_by: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_style: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub force_login_method: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enable_all_project_mcp_servers: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled_mcpjson_servers: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disabled_mcpjson_servers: Option<Vec<String>>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A problem found while validating settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SettingsDiagnostic {
+    /// JSON pointer to the offending value
+    pub path: String,
+    pub message: String,
+}
+
+/// One settings file that took part in a merge
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScopeSource {
+    pub scope: SettingsScope,
+    pub path: String,
+    pub exists: bool,
+    /// Set when the file could not be read; it is left out of the merge
+    pub error: Option<String>,
+}
+
+/// Settings merged across scopes, with the scope each value came from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EffectiveSettings {
+    pub settings: ClaudeSettings,
+    /// Scope of each value, keyed by JSON pointer. Merged lists such as
+    /// permission rules and hook groups are reported per element.
+    pub sources: BTreeMap<String, SettingsScope>,
+    pub scopes: Vec<ScopeSource>,
+}
+
+/// Path of the settings file for a scope
+///
+/// Project and local scopes need the project directory.
+pub fn settings_path(scope: SettingsScope, project_path: Option<&str>) -> Result<PathBuf, String> {
+    let project = || {
+        project_path
+            .filter(|p| !p.is_empty())
+            .map(|p| Path::new(p).join(".claude"))
+            .ok_or_else(|| format!("A project path is required for {:?} settings", scope))
+    };
+
+    match scope {
+        SettingsScope::User => Ok(claude_dir().join("settings.json")),
+        SettingsScope::Project => Ok(project()?.join("settings.json")),
+        SettingsScope::Local => Ok(project()?.join("settings.local.json")),
+    }
+}
+
+/// Whether a path looks like a Claude settings file at any scope
+pub fn is_settings_file(path: &Path) -> bool {
+    let name = path.file_name().and_then(|n| n.to_str());
+    let in_claude_dir = path
+        .parent()
+        .is_some_and(|dir| dir == claude_dir() || dir.file_name().is_some_and(|n| n == ".claude"));
+    in_claude_dir && matches!(name, Some("settings.json" | "settings.local.json"))
+}
+
+/// Read a settings file as JSON; a missing file is an empty object
+pub fn read_settings(path: &Path) -> Result<serde_json::Value, String> {
+    match fs::read_to_string(path) {
+        Ok(content) if content.trim().is_empty() => Ok(serde_json::json!({})),
+        Ok(content) => serde_json::from_str(&content)
+            .map_err(|e| format!("Invalid settings in {}: {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(serde_json::json!({})),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+/// Write a settings file as pretty-printed JSON, atomically and with a backup
+pub fn write_settings(path: &Path, settings: &serde_json::Value) -> Result<(), String> {
+    let mut content = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    content.push('\n');
+    backups::safe_write(path, content.as_bytes(), None).map(|_| ())
+}
+
+/// JSON Schema for the settings keys CodePod knows about
+///
+/// Unknown keys are allowed so newer CLI settings are not rejected.
+fn settings_schema() -> serde_json::Value {
+    let strings = serde_json::json!({ "type": "array", "items": { "type": "string" } });
+
+    serde_json::json!({
+        "type": "object",
+        "$defs": {
+            "rules": {
+                "type": "array",
+                "items": {
+                    "type": "string",
+                    "pattern": "^[A-Za-z][A-Za-z0-9_-]*(\\(.*\\))?$"
+                }
+            }
+        },
+        "properties": {
+            "model": { "type": "string", "minLength": 1 },
+            "env": { "type": "object", "additionalProperties": { "type": "string" } },
+            "permissions": {
+                "type": "object",
+                "properties": {
+                    "allow": { "$ref": "#/$defs/rules" },
+                    "deny": { "$ref": "#/$defs/rules" },
+                    "ask": { "$ref": "#/$defs/rules" },
+                    "additionalDirectories": strings,
+                    "defaultMode": {
+                        "enum": ["default", "acceptEdits", "plan", "bypassPermissions"]
+                    },
+                    "disableBypassPermissionsMode": { "enum": ["disable"] }
+                }
+            },
+            "hooks": {
+                "type": "object",
+                "additionalProperties": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["hooks"],
+                        "properties": {
+                            "matcher": { "type": "string" },
+                            "hooks": {
+                                "type": "array",
+                                "items": {
+                                    "type": "object",
+                                    "required": ["type"],
+                                    "properties": {
+                                        "type": { "enum": ["command", "prompt"] },
+                                        "command": { "type": "string", "minLength": 1 },
+                                        "prompt": { "type": "string", "minLength": 1 },
+                                        "timeout": { "type": "integer", "minimum": 1 }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            },
+            "statusLine": {
+                "type": "object",
+                "required": ["type", "command"],
+                "properties": {
+                    "type": { "const": "command" },
+                    "command": { "type": "string", "minLength": 1 },
+                    "padding": { "type": "integer" }
+                }
+            },
+            "apiKeyHelper": { "type": "string" },
+            "cleanupPeriodDays": { "type": "integer", "minimum": 0 },
+            "includeCoAuthoredBy": { "type": "boolean" },
+            "outputStyle": { "type": "string" },
+            "forceLoginMethod": { "enum": ["claudeai", "console"] },
+            "enableAllProjectMcpServers": { "type": "boolean" },
+            "enabledMcpjsonServers": strings,
+            "disabledMcpjsonServers": strings
+        }
+    })
+}
+
+/// Refuse settings Claude could not load at all
+///
+/// The root must be an object and known keys must have the right types.
+/// Unknown keys and values are left to `validate_settings`, which only warns.
+pub(crate) fn check_structure(settings: &serde_json::Value) -> Result<(), String> {
+    if !settings.is_object() {
+        return Err("Settings must be a JSON object".to_string());
+    }
+    serde_json::from_value::<ClaudeSettings>(settings.clone())
+        .map(|_| ())
+        .map_err(|e| format!("Invalid settings: {}", e))
+}
+
+/// Validate settings against the schema and hook rules
+pub fn validate_settings(settings: &serde_json::Value) -> Vec<SettingsDiagnostic> {
+    let schema = settings_schema();
+    let validator = match jsonschema::validator_for(&schema) {
+        Ok(v) => v,
+        Err(e) => {
+            return vec![SettingsDiagnostic {
+                path: String::new(),
+                message: format!("Invalid settings schema: {}", e),
+            }]
+        }
+    };
+
+    let mut diagnostics: Vec<SettingsDiagnostic> = validator
+        .iter_errors(settings)
+        .map(|e| SettingsDiagnostic {
+            path: e.instance_path.to_string(),
+            message: e.to_string(),
+        })
+        .collect();
+    if !diagnostics.is_empty() {
+        return diagnostics;
+    }
+
+    let hooks: HooksConfig = settings
+        .get("hooks")
+        .and_then(|h| serde_json::from_value(h.clone()).ok())
+        .unwrap_or_default();
+    for (name, groups) in &hooks {
+        let path = format!("/hooks/{}", escape_pointer(name));
+        let Some(event) = HookEvent::from_name(name) else {
+            diagnostics.push(SettingsDiagnostic {
+                path,
+                message: format!("Unknown hook event: {}", name),
+            });
+            continue;
+        };
+
+        for (index, group) in groups.iter().enumerate() {
+            let matcher = group.matcher.as_deref().filter(|m| !m.is_empty());
+            for (hook_index, hook) in group.hooks.iter().enumerate() {
+                if let Err(message) = hooks::validate_hook(event, matcher, hook) {
+                    diagnostics.push(SettingsDiagnostic {
+                        path: format!("{}/{}/hooks/{}", path, index, hook_index),
+                        message,
+                    });
+                }
+            }
+        }
+    }
+
+    diagnostics
+}
+
+fn escape_pointer(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// Lists that Claude combines across scopes instead of overriding
+fn is_merged_list(path: &str) -> bool {
+    matches!(
+        path,
+        "/permissions/allow"
+            | "/permissions/deny"
+            | "/permissions/ask"
+            | "/permissions/additionalDirectories"
+    ) || path
+        .strip_prefix("/hooks/")
+        .is_some_and(|event| !event.contains('/'))
+}
+
+/// Attribute every leaf of `value` at `path` to `scope`
+fn record_sources(
+    value: &serde_json::Value,
+    path: &str,
+    scope: SettingsScope,
+    sources: &mut BTreeMap<String, SettingsScope>,
+) {
+    match value {
+        serde_json::Value::Object(map) if !map.is_empty() => {
+            for (key, child) in map {
+                let child_path = format!("{}/{}", path, escape_pointer(key));
+                record_sources(child, &child_path, scope, sources);
+            }
+        }
+        serde_json::Value::Array(items) if is_merged_list(path) => {
+            for index in 0..items.len() {
+                sources.insert(format!("{}/{}", path, index), scope);
+            }
+        }
+        _ => {
+            sources.insert(path.to_string(), scope);
+        }
+    }
+}
+
+/// Merge a higher-precedence scope into `target`
+///
+/// Objects merge key by key, merged lists are concatenated without
+/// duplicates, and anything else is replaced.
+fn merge_scope(
+    target: &mut serde_json::Value,
+    value: &serde_json::Value,
+    path: &str,
+    scope: SettingsScope,
+    sources: &mut BTreeMap<String, SettingsScope>,
+) {
+    match (target, value) {
+        (serde_json::Value::Object(target), serde_json::Value::Object(map)) => {
+            for (key, child) in map {
+                let child_path = format!("{}/{}", path, escape_pointer(key));
+                match target.get_mut(key) {
+                    Some(existing) => merge_scope(existing, child, &child_path, scope, sources),
+                    None => {
+                        record_sources(child, &child_path, scope, sources);
+                        target.insert(key.clone(), child.clone());
+                    }
+                }
+            }
+        }
+        (serde_json::Value::Array(target), serde_json::Value::Array(items))
+            if is_merged_list(path) =>
+        {
+            for item in items {
+                if !target.contains(item) {
+                    sources.insert(format!("{}/{}", path, target.len()), scope);
+                    target.push(item.clone());
+                }
+            }
+        }
+        (target, value) => {
+            let prefix = format!("{}/", path);
+            sources.retain(|key, _| key != path && !key.starts_with(&prefix));
+            record_sources(value, path, scope, sources);
+            *target = value.clone();
+        }
+    }
+}
+
+/// Merge the user, project and local settings in precedence order
+fn effective_settings(project_path: Option<&str>) -> Result<EffectiveSettings, String> {
+    let mut merged = serde_json::json!({});
+    let mut sources = BTreeMap::new();
+    let mut scopes = Vec::new();
+
+    for scope in SettingsScope::ALL {
+        if scope != SettingsScope::User && project_path.is_none() {
+            continue;
+        }
+
+        let path = settings_path(scope, project_path)?;
+        let mut source = ScopeSource {
+            scope,
+            path: path.to_string_lossy().to_string(),
+            exists: path.exists(),
+            error: None,
+        };
+
+        match read_settings(&path) {
+            Ok(value) if value.is_object() => {
+                merge_scope(&mut merged, &value, "", scope, &mut sources)
+            }
+            Ok(_) => source.error = Some("Settings are not a JSON object".to_string()),
+            Err(e) => source.error = Some(e),
+        }
+        scopes.push(source);
+    }
+
+    let settings = serde_json::from_value(merged)
+        .map_err(|e| format!("Merged settings are invalid: {}", e))?;
+    Ok(EffectiveSettings {
+        settings,
+        sources,
+        scopes,
+    })
+}
+
+/// Read the settings file of one scope
+#[command]
+pub async fn read_claude_settings(
+    scope: SettingsScope,
+    project_path: Option<String>,
+) -> CommandResult<ClaudeSettings> {
+    let result = settings_path(scope, project_path.as_deref())
+        .and_then(|path| read_settings(&path))
+        .and_then(|value| {
+            serde_json::from_value(value).map_err(|e| format!("Invalid settings: {}", e))
+        });
+
+    match result {
+        Ok(settings) => CommandResult::ok(settings),
+        Err(e) => CommandResult::err(e),
+    }
+}
+
+/// Validate settings without writing them
+#[command]
+pub async fn validate_claude_settings(
+    settings: serde_json::Value,
+) -> CommandResult<Vec<SettingsDiagnostic>> {
+    CommandResult::ok(validate_settings(&settings))
+}
+
+/// Validate and write the settings file of one scope
+///
+/// Settings with the wrong structure are refused. Other problems, such as
+/// unknown keys or values, are returned as warnings and the file is written.
+#[command]
+pub async fn write_claude_settings(
+    scope: SettingsScope,
+    project_path: Option<String>,
+    settings: serde_json::Value,
+) -> CommandResult<Vec<SettingsDiagnostic>> {
+    if let Err(e) = check_structure(&settings) {
+        return CommandResult::err(e);
+    }
+    let diagnostics = validate_settings(&settings);
+
+    match settings_path(scope, project_path.as_deref())
+        .and_then(|path| write_settings(&path, &settings))
+    {
+        Ok(()) => CommandResult::ok(diagnostics),
+        Err(e) => CommandResult::err(e),
+    }
+}
+
+/// Settings Claude would use in a project, merged across scopes
+#[command]
+pub async fn get_effective_settings(
+    project_path: Option<String>,
+) -> CommandResult<EffectiveSettings> {
+    match effective_settings(project_path.as_deref()) {
+        Ok(effective) => CommandResult::ok(effective),
+        Err(e) => CommandResult::err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_settings_path_scopes() {
+        let project = settings_path(SettingsScope::Project, Some("/repo")).unwrap();
+        assert_eq!(project, PathBuf::from("/repo/.claude/settings.json"));
+
+        let local = settings_path(SettingsScope::Local, Some("/repo")).unwrap();
+        assert_eq!(local, PathBuf::from("/repo/.claude/settings.local.json"));
+
+        assert!(settings_path(SettingsScope::Local, None).is_err());
+        assert!(settings_path(SettingsScope::User, None)
+            .unwrap()
+            .ends_with("settings.json"));
+
+        assert!(is_settings_file(&local));
+        assert!(!is_settings_file(Path::new("/repo/settings.json")));
+    }
+
+    #[test]
+    fn test_settings_round_trip_keeps_unknown_keys() {
+        let value = json!({
+            "model": "opus",
+            "permissions": { "allow": ["Bash(npm run test:*)"], "defaultMode": "acceptEdits" },
+            "feedbackSurveyState": { "lastShown": 1 }
+        });
+
+        let settings: ClaudeSettings = serde_json::from_value(value.clone()).unwrap();
+        let permissions = settings.permissions.as_ref().unwrap();
+        assert_eq!(permissions.default_mode, Some(PermissionMode::AcceptEdits));
+        assert!(settings.extra.contains_key("feedbackSurveyState"));
+        assert_eq!(serde_json::to_value(&settings).unwrap(), value);
+    }
+
+    #[test]
+    fn test_validate_settings() {
+        let valid = json!({
+            "env": { "DEBUG": "1" },
+            "permissions": { "deny": ["Read(./.env)", "WebFetch"] },
+            "hooks": {
+                "PreToolUse": [{ "matcher": "Edit|Write", "hooks": [{ "type": "command", "command": "./fmt.sh" }] }]
+            }
+        });
+        assert!(validate_settings(&valid).is_empty());
+
+        let diagnostics = validate_settings(&json!({ "env": { "DEBUG": 1 } }));
+        assert_eq!(diagnostics[0].path, "/env/DEBUG");
+
+        let diagnostics = validate_settings(&json!({ "permissions": { "allow": ["Bash(ls"] } }));
+        assert_eq!(diagnostics[0].path, "/permissions/allow/0");
+
+        let diagnostics = validate_settings(&json!({
+            "hooks": { "Stop": [{ "matcher": "Bash", "hooks": [{ "type": "command", "command": "x" }] }] }
+        }));
+        assert_eq!(diagnostics[0].path, "/hooks/Stop/0/hooks/0");
+    }
+
+    #[tokio::test]
+    async fn test_write_refuses_structural_errors_only() {
+        let project =
+            std::env::temp_dir().join(format!("codepod-settings-{}", uuid::Uuid::new_v4()));
+        let project_path = Some(project.to_string_lossy().to_string());
+
+        for invalid in [
+            json!("x"),
+            json!([]),
+            json!({ "permissions": { "allow": "Bash" } }),
+            json!({ "env": { "A": 1 } }),
+        ] {
+            let result =
+                write_claude_settings(SettingsScope::Local, project_path.clone(), invalid).await;
+            assert!(result.error.is_some());
+        }
+        assert!(!project.join(".claude/settings.local.json").exists());
+
+        let settings = json!({
+            "permissions": { "allow": ["Bash(ls"], "defaultMode": "dontAsk" },
+            "hooks": { "Stop": [{ "hooks": [{ "type": "prompt", "prompt": "Is the task done?" }] }] },
+            "futureSetting": true
+        });
+        let warnings =
+            write_claude_settings(SettingsScope::Local, project_path.clone(), settings.clone())
+                .await
+                .data
+                .unwrap();
+        assert_eq!(warnings[0].path, "/permissions/allow/0");
+
+        let read = read_claude_settings(SettingsScope::Local, project_path.clone())
+            .await
+            .data
+            .unwrap();
+        let permissions = read.permissions.as_ref().unwrap();
+        assert_eq!(
+            permissions.default_mode,
+            Some(PermissionMode::Other("dontAsk".to_string()))
+        );
+        assert_eq!(serde_json::to_value(&read).unwrap(), settings);
+        assert!(get_effective_settings(project_path).await.data.is_some());
+
+        let _ = fs::remove_dir_all(&project);
+    }
+
+    #[test]
+    fn test_merge_scopes_reports_sources() {
+        let user = json!({
+            "model": "sonnet",
+            "env": { "A": "1", "B": "1" },
+            "permissions": { "allow": ["Read"] }
+        });
+        let local = json!({
+            "model": "opus",
+            "env": { "B": "2" },
+            "permissions": { "allow": ["Read", "Bash(ls)"] }
+        });
+
+        let mut merged = json!({});
+        let mut sources = BTreeMap::new();
+        merge_scope(&mut merged, &user, "", SettingsScope::User, &mut sources);
+        merge_scope(&mut merged, &local, "", SettingsScope::Local, &mut sources);
+
+        assert_eq!(merged["model"], "opus");
+        assert_eq!(merged["env"], json!({ "A": "1", "B": "2" }));
+        assert_eq!(merged["permissions"]["allow"], json!(["Read", "Bash(ls)"]));
+
+        assert_eq!(sources["/model"], SettingsScope::Local);
+        assert_eq!(sources["/env/A"], SettingsScope::User);
+        assert_eq!(sources["/env/B"], SettingsScope::Local);
+        assert_eq!(sources["/permissions/allow/0"], SettingsScope::User);
+        assert_eq!(sources["/permissions/allow/1"], SettingsScope::Local);
+    }
+}
@@ -0,0 +1,329 @@
+//! Skill discovery
+//!
+//! A skill is a folder holding a `SKILL.md` (frontmatter with `name` and
+//! `description`, then instructions) and any scripts or reference files the
+//! instructions point to. Skills live in the user's `~/.claude/skills` and in
+//! a project's `.claude/skills`.
+
+use super::definitions::{
+    definition_sources, resolve_shadowing, DefinitionKind, DefinitionOrigin, DefinitionSource,
+    ListedDefinition,
+};
+use super::frontmatter::{parse_frontmatter, Frontmatter};
+use super::CommandResult;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use tauri::command;
+
+/// Longest skill name Claude accepts
+const MAX_NAME_LEN: usize = 64;
+/// Longest skill description Claude accepts
+const MAX_DESCRIPTION_LEN: usize = 1024;
+/// Resource files listed per skill
+const MAX_RESOURCES: usize = 500;
+/// Folders never listed as resources or bundled
+pub(crate) const SKIPPED_DIRS: &[&str] = &[".git", "node_modules", "__pycache__", ".venv"];
+
+/// A file bundled with a skill
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkillResource {
+    /// Path relative to the skill folder, with `/` separators
+    pub path: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SkillInfo {
+    pub name: String,
+    pub description: Option<String>,
+    /// Body of `SKILL.md`
+    pub content: String,
+    pub enabled: bool,
+    /// Skill folder
+    pub path: String,
+    pub frontmatter: Frontmatter,
+    pub resources: Vec<SkillResource>,
+    pub valid: bool,
+    /// Why the skill is invalid
+    pub issues: Vec<String>,
+    #[serde(flatten)]
+    pub origin: DefinitionOrigin,
+}
+
+impl ListedDefinition for SkillInfo {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn origin(&self) -> &DefinitionOrigin {
+        &self.origin
+    }
+
+    fn origin_mut(&mut self) -> &mut DefinitionOrigin {
+        &mut self.origin
+    }
+}
+
+/// Problems with a skill or agent name
+pub(crate) fn name_issues(name: &str) -> Vec<String> {
+    let mut issues = Vec::new();
+    if name.len() > MAX_NAME_LEN {
+        issues.push(format!("Name is longer than {} characters", MAX_NAME_LEN));
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+    {
+        issues.push("Name may only contain lowercase letters, numbers and hyphens".to_string());
+    }
+    issues
+}
+
+/// Check the frontmatter fields Claude requires of a skill
+pub(crate) fn validate_frontmatter(frontmatter: &Frontmatter) -> Vec<String> {
+    let mut issues = Vec::new();
+
+    match frontmatter.name.as_deref() {
+        None | Some("") => issues.push("SKILL.md frontmatter has no name".to_string()),
+        Some(name) => issues.extend(name_issues(name)),
+    }
+
+    match frontmatter.description.as_deref().map(str::trim) {
+        None | Some("") => issues.push("SKILL.md frontmatter has no description".to_string()),
+        Some(description) if description.chars().count() > MAX_DESCRIPTION_LEN => {
+            issues.push(format!(
+                "Description is longer than {} characters",
+                MAX_DESCRIPTION_LEN
+            ))
+        }
+        Some(_) => {}
+    }
+
+    issues
+}
+
+/// List the files bundled in a skill folder, except `SKILL.md`
+fn list_resources(skill_dir: &Path) -> Vec<SkillResource> {
+    let mut resources = Vec::new();
+    let mut pending = vec![skill_dir.to_path_buf()];
+
+    while let Some(dir) = pending.pop() {
+        if resources.len() >= MAX_RESOURCES {
+            break;
+        }
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+
+            if file_type.is_dir() {
+                let skipped = path
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| SKIPPED_DIRS.contains(&n));
+                if !skipped {
+                    pending.push(path);
+                }
+                continue;
+            }
+
+            let Ok(relative) = path.strip_prefix(skill_dir) else {
+                continue;
+            };
+            let relative = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            if relative == "SKILL.md" {
+                continue;
+            }
+
+            resources.push(SkillResource {
+                path: relative,
+                size: entry.metadata().map(|m| m.len()).unwrap_or(0),
+            });
+            if resources.len() >= MAX_RESOURCES {
+                break;
+            }
+        }
+    }
+
+    resources.sort_by(|a, b| a.path.cmp(&b.path));
+    resources
+}
+
+/// Read one skill folder
+fn read_skill(skill_dir: &Path, source: &DefinitionSource, enabled: bool) -> SkillInfo {
+    let folder_name = skill_dir
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("unknown")
+        .to_string();
+
+    let mut issues = Vec::new();
+    let (frontmatter, content) = match fs::read_to_string(skill_dir.join("SKILL.md")) {
+        Ok(raw) => match parse_frontmatter(&raw) {
+            Ok((frontmatter, body)) => {
+                issues.extend(validate_frontmatter(&frontmatter));
+                (frontmatter, body)
+            }
+            Err(e) => {
+                issues.push(e);
+                (Frontmatter::default(), raw)
+            }
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            issues.push("Folder has no SKILL.md".to_string());
+            (Frontmatter::default(), String::new())
+        }
+        Err(e) => {
+            issues.push(format!("Failed to read SKILL.md: {}", e));
+            (Frontmatter::default(), String::new())
+        }
+    };
+
+    SkillInfo {
+        name: source.qualify(frontmatter.name.clone().unwrap_or(folder_name)),
+        description: frontmatter.description.clone(),
+        content,
+        enabled,
+        path: skill_dir.to_string_lossy().to_string(),
+        frontmatter,
+        resources: list_resources(skill_dir),
+        valid: issues.is_empty(),
+        issues,
+        origin: source.origin(),
+    }
+}
+
+/// Discover the skills in one skills directory
+///
+/// Loose markdown files are reported as invalid, since Claude only loads
+/// skill folders. Hidden entries such as `.git` are skipped.
+fn discover_skills(
+    dir: &Path,
+    source: &DefinitionSource,
+    enabled: bool,
+) -> Result<Vec<SkillInfo>, String> {
+    let entries = match fs::read_dir(dir) {
+        Ok(e) => e,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(format!("Failed to read {}: {}", dir.display(), e)),
+    };
+
+    let mut skills = Vec::new();
+    for entry in entries.flatten() {
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let path = entry.path();
+        if path.is_dir() {
+            skills.push(read_skill(&path, source, enabled));
+        } else if path.extension().is_some_and(|e| e == "md") {
+            let name = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("unknown")
+                .to_string();
+            skills.push(SkillInfo {
+                name: source.qualify(name),
+                description: None,
+                content: fs::read_to_string(&path).unwrap_or_default(),
+                enabled,
+                path: path.to_string_lossy().to_string(),
+                frontmatter: Frontmatter::default(),
+                resources: vec![],
+                valid: false,
+                issues: vec!["Skills must be folders containing a SKILL.md".to_string()],
+                origin: source.origin(),
+            });
+        }
+    }
+
+    skills.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(skills)
+}
+
+/// List skills from the user, the project if given, and installed plugins
+///
+/// Disabled skills are included. Skills sharing a name are flagged, with the
+/// one Claude uses first.
+#[command]
+pub async fn list_skills(project_path: Option<String>) -> CommandResult<Vec<SkillInfo>> {
+    // Skill folders are walked for their resources, so scan off the runtime
+    let result = tokio::task::spawn_blocking(move || {
+        let mut skills = Vec::new();
+        for source in definition_sources(project_path.as_deref()) {
+            for (dir, enabled) in source.dirs(DefinitionKind::Skill) {
+                skills.extend(discover_skills(&dir, &source, enabled)?);
+            }
+        }
+        resolve_shadowing(&mut skills);
+        Ok::<_, String>(skills)
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|skills| skills);
+
+    match result {
+        Ok(skills) => CommandResult::ok(skills),
+        Err(e) => CommandResult::err(format!("Failed to list skills: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discover_skills() {
+        let dir = std::env::temp_dir().join(format!("codepod-skills-{}", uuid::Uuid::new_v4()));
+        let pdf = dir.join("pdf-tools");
+        fs::create_dir_all(pdf.join("scripts")).unwrap();
+        fs::write(
+            pdf.join("SKILL.md"),
+            "---\nname: pdf-tools\ndescription: Fill and merge PDFs\n---\nUse scripts/fill.py",
+        )
+        .unwrap();
+        fs::write(pdf.join("scripts").join("fill.py"), "print('hi')\n").unwrap();
+        fs::write(pdf.join("REFERENCE.md"), "# Ref").unwrap();
+
+        let bad = dir.join("Bad Skill");
+        fs::create_dir_all(&bad).unwrap();
+        fs::write(bad.join("SKILL.md"), "---\nname: Bad Skill\n---\nBody").unwrap();
+
+        fs::create_dir_all(dir.join("empty")).unwrap();
+        fs::write(dir.join("legacy.md"), "Old style").unwrap();
+        fs::create_dir_all(dir.join(".git")).unwrap();
+        fs::create_dir_all(dir.join(".pdf-tools.1234.import")).unwrap();
+
+        let skills = discover_skills(&dir, &DefinitionSource::user(), true).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+
+        let names: Vec<&str> = skills.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["Bad Skill", "empty", "legacy", "pdf-tools"]);
+
+        let pdf = &skills[3];
+        assert!(pdf.valid, "{:?}", pdf.issues);
+        let resources: Vec<(&str, u64)> = pdf
+            .resources
+            .iter()
+            .map(|r| (r.path.as_str(), r.size))
+            .collect();
+        assert_eq!(resources, [("REFERENCE.md", 5), ("scripts/fill.py", 12)]);
+
+        assert_eq!(skills[0].issues.len(), 2);
+        assert_eq!(skills[1].issues, ["Folder has no SKILL.md"]);
+        assert!(!skills[2].valid);
+    }
+}
@@ -0,0 +1,248 @@
+//! Structured output
+//!
+//! Asks Claude for JSON matching a caller-supplied JSON Schema, extracts the
+//! value from the final result text and validates it. Invalid responses can
+//! be repaired by resuming the session with the validation errors.
+
+use super::claude::{self, ClaudeInvocation, ClaudeOptions};
+use super::CommandResult;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle};
+
+/// A single schema violation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidationDiagnostic {
+    /// JSON pointer to the offending value
+    pub path: String,
+    pub message: String,
+}
+
+/// Result of a structured invocation
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StructuredResult {
+    /// Parsed value of the last response, valid or not
+    pub value: Option<serde_json::Value>,
+    pub valid: bool,
+    /// Problems with the last response
+    pub diagnostics: Vec<ValidationDiagnostic>,
+    /// Final result text of the last response
+    pub raw_result: Option<String>,
+    /// One entry per prompt sent, including repair prompts
+    pub invocations: Vec<ClaudeInvocation>,
+}
+
+fn schema_instructions(schema: &serde_json::Value) -> String {
+    format!(
+        "Respond with only a JSON value, without commentary or code fences, that \
+         validates against this JSON Schema:\n{}",
+        serde_json::to_string_pretty(schema).unwrap_or_default()
+    )
+}
+
+fn repair_prompt(schema: &serde_json::Value, diagnostics: &[ValidationDiagnostic]) -> String {
+    let problems: Vec<String> = diagnostics
+        .iter()
+        .map(|d| {
+            if d.path.is_empty() {
+                format!("- {}", d.message)
+            } else {
+                format!("- {}: {}", d.path, d.message)
+            }
+        })
+        .collect();
+
+    format!(
+        "Your previous response was not valid:\n{}\n\n{}",
+        problems.join("\n"),
+        schema_instructions(schema)
+    )
+}
+
+/// Pull a JSON value out of result text
+///
+/// Accepts bare JSON, a fenced code block, or JSON surrounded by prose.
+fn extract_json(text: &str) -> Result<serde_json::Value, String> {
+    let trimmed = text.trim();
+    if let Ok(value) = serde_json::from_str(trimmed) {
+        return Ok(value);
+    }
+
+    if let Some(start) = trimmed.find("```") {
+        let after = &trimmed[start + 3..];
+        let body = after.split_once('\n').map_or(after, |(_, rest)| rest);
+        if let Some(end) = body.find("```") {
+            if let Ok(value) = serde_json::from_str(body[..end].trim()) {
+                return Ok(value);
+            }
+        }
+    }
+
+    let start = trimmed.find(['{', '[']);
+    let end = trimmed.rfind(['}', ']']);
+    if let (Some(start), Some(end)) = (start, end) {
+        if start < end {
+            if let Ok(value) = serde_json::from_str(&trimmed[start..=end]) {
+                return Ok(value);
+            }
+        }
+    }
+
+    Err("Response did not contain valid JSON".to_string())
+}
+
+/// Validate a value, returning every violation found
+fn validate(
+    validator: &jsonschema::Validator,
+    value: &serde_json::Value,
+) -> Vec<ValidationDiagnostic> {
+    validator
+        .iter_errors(value)
+        .map(|e| ValidationDiagnostic {
+            path: e.instance_path.to_string(),
+            message: e.to_string(),
+        })
+        .collect()
+}
+
+/// Invoke Claude and validate its result against a JSON Schema
+///
+/// With `max_repairs` > 0, invalid responses are sent back to the same
+/// session along with the validation errors until one validates.
+#[command]
+pub async fn invoke_claude_structured(
+    app: AppHandle,
+    prompt: String,
+    schema: serde_json::Value,
+    options: Option<ClaudeOptions>,
+    max_repairs: Option<u32>,
+) -> CommandResult<StructuredResult> {
+    let validator = match jsonschema::validator_for(&schema) {
+        Ok(v) => v,
+        Err(e) => return CommandResult::err(format!("Invalid JSON Schema: {}", e)),
+    };
+
+    let mut options = options.unwrap_or_default();
+    let mut next_prompt = format!("{}\n\n{}", prompt, schema_instructions(&schema));
+    let mut result = StructuredResult::default();
+
+    for _ in 0..=max_repairs.unwrap_or(0) {
+        let job_id = uuid::Uuid::new_v4().to_string();
+        let invocation = claude::execute_claude_job(&app, &job_id, &next_prompt, &options).await;
+        let data = invocation.data.clone().unwrap_or_default();
+        result.invocations.push(data.clone());
+
+        if !invocation.success {
+            return CommandResult {
+                success: false,
+                data: Some(result),
+                error: invocation.error,
+            };
+        }
+
+        let output: serde_json::Value = data
+            .output
+            .as_deref()
+            .and_then(|o| serde_json::from_str(o.trim()).ok())
+            .unwrap_or_default();
+        let text = output
+            .get("result")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string();
+
+        result.raw_result = Some(text.clone());
+        match extract_json(&text) {
+            Ok(value) => {
+                result.diagnostics = validate(&validator, &value);
+                result.value = Some(value);
+            }
+            Err(e) => {
+                result.value = None;
+                result.diagnostics = vec![ValidationDiagnostic {
+                    path: String::new(),
+                    message: e,
+                }];
+            }
+        }
+
+        if result.diagnostics.is_empty() {
+            result.valid = true;
+            return CommandResult::ok(result);
+        }
+
+        // Repair within the same conversation so Claude sees its answer
+        match output.get("session_id").and_then(|v| v.as_str()) {
+            Some(session_id) => {
+                options.session_id = Some(session_id.to_string());
+                options.continue_session = false;
+            }
+            None => break,
+        }
+        next_prompt = repair_prompt(&schema, &result.diagnostics);
+    }
+
+    let error = format!(
+        "Response failed schema validation: {}",
+        result
+            .diagnostics
+            .iter()
+            .map(|d| d.message.clone())
+            .collect::<Vec<_>>()
+            .join("; ")
+    );
+    CommandResult {
+        success: false,
+        data: Some(result),
+        error: Some(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_extract_json_variants() {
+        assert_eq!(extract_json(r#"{"a":1}"#).unwrap(), json!({"a": 1}));
+        assert_eq!(
+            extract_json("Here you go:\n```json\n[1, 2]\n```\nDone.").unwrap(),
+            json!([1, 2])
+        );
+        assert_eq!(
+            extract_json(r#"The answer is {"ok": true} as requested."#).unwrap(),
+            json!({"ok": true})
+        );
+        assert!(extract_json("no json here").is_err());
+    }
+
+    #[test]
+    fn test_validate_reports_paths() {
+        let schema = json!({
+            "type": "object",
+            "required": ["name", "count"],
+            "properties": {
+                "name": { "type": "string" },
+                "count": { "type": "integer", "minimum": 0 }
+            }
+        });
+        let validator = jsonschema::validator_for(&schema).unwrap();
+
+        assert!(validate(&validator, &json!({"name": "x", "count": 2})).is_empty());
+
+        let diagnostics = validate(&validator, &json!({"name": "x", "count": -1}));
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].path, "/count");
+    }
+
+    #[test]
+    fn test_repair_prompt_lists_problems() {
+        let diagnostics = vec![ValidationDiagnostic {
+            path: "/count".to_string(),
+            message: "-1 is less than the minimum of 0".to_string(),
+        }];
+        let prompt = repair_prompt(&json!({"type": "object"}), &diagnostics);
+        assert!(prompt.contains("- /count: -1 is less than the minimum of 0"));
+        assert!(prompt.contains("JSON Schema"));
+    }
+}
@@ -0,0 +1,523 @@
+//! Prompt templates
+//!
+//! Reusable prompts stored as JSON under `~/.codepod/templates`. Templates
+//! declare typed variables and may use built-in resolvers that read from the
+//! working directory:
+//!
+//! - `{{git.branch}}` - current branch
+//! - `{{git.diff}}` / `{{git.diff.staged}}` - unstaged / staged diff
+//! - `{{git.status}}` - short status
+//! - `{{file:path}}` - file contents, relative to the working directory
+//! - `{{date}}` / `{{yesterday}}` - today's / yesterday's date
+//! - `{{time}}` - current time (`HH:MM`)
+//! - `{{cwd}}` - the working directory
+//!
+//! Placeholders that are neither declared nor built in are left as written,
+//! and `\{{` gives a literal `{{`, so prompts quoting Handlebars, Jinja or
+//! Vue code survive rendering. Scheduled tasks render their prompts through
+//! the same engine.
+
+use super::claude::{self, ClaudeInvocation, ClaudeOptions};
+use super::{codepod_dir, git, CommandResult};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tauri::{command, AppHandle};
+
+/// Type of a template variable
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VariableKind {
+    #[default]
+    String,
+    Number,
+    Boolean,
+    /// A path that must exist, relative to the working directory
+    Path,
+    /// One of `choices`
+    Choice,
+}
+
+/// A variable declared by a template
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateVariable {
+    pub name: String,
+    #[serde(default)]
+    pub kind: VariableKind,
+    pub description: Option<String>,
+    #[serde(default = "default_required")]
+    pub required: bool,
+    pub default: Option<String>,
+    #[serde(default)]
+    pub choices: Vec<String>,
+}
+
+fn default_required() -> bool {
+    true
+}
+
+/// A stored prompt template
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptTemplate {
+    pub name: String,
+    pub description: Option<String>,
+    pub body: String,
+    #[serde(default)]
+    pub variables: Vec<TemplateVariable>,
+}
+
+/// Where a placeholder's value came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueSource {
+    User,
+    Default,
+    Builtin,
+}
+
+/// A placeholder resolved during rendering
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolvedPlaceholder {
+    pub placeholder: String,
+    pub source: ValueSource,
+    pub length: usize,
+}
+
+/// Rendered prompt preview
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenderedTemplate {
+    pub prompt: String,
+    pub placeholders: Vec<ResolvedPlaceholder>,
+}
+
+fn templates_dir() -> PathBuf {
+    codepod_dir().join("templates")
+}
+
+/// Template names become file names, so keep them to a safe character set
+fn validate_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid template name '{}': use letters, digits, '-' and '_'",
+            name
+        ))
+    }
+}
+
+fn template_path(name: &str) -> Result<PathBuf, String> {
+    validate_name(name)?;
+    Ok(templates_dir().join(format!("{}.json", name)))
+}
+
+fn load_template(name: &str) -> Result<PromptTemplate, String> {
+    let path = template_path(name)?;
+    let content = fs::read_to_string(&path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => format!("Template not found: {}", name),
+        _ => format!("Failed to read template {}: {}", name, e),
+    })?;
+    serde_json::from_str(&content).map_err(|e| format!("Invalid template {}: {}", name, e))
+}
+
+/// Check a user-supplied value against the variable's declared type
+fn validate_value(var: &TemplateVariable, value: &str, cwd: &Path) -> Result<(), String> {
+    let ok = match var.kind {
+        VariableKind::String => true,
+        VariableKind::Number => value.trim().parse::<f64>().is_ok(),
+        VariableKind::Boolean => matches!(value.trim(), "true" | "false"),
+        VariableKind::Path => cwd.join(value).exists(),
+        VariableKind::Choice => var.choices.iter().any(|c| c == value),
+    };
+
+    if ok {
+        Ok(())
+    } else {
+        let expected = match var.kind {
+            VariableKind::Number => "a number".to_string(),
+            VariableKind::Boolean => "true or false".to_string(),
+            VariableKind::Path => "an existing path".to_string(),
+            VariableKind::Choice => format!("one of: {}", var.choices.join(", ")),
+            VariableKind::String => "a string".to_string(),
+        };
+        Err(format!(
+            "Variable '{}' must be {}, got '{}'",
+            var.name, expected, value
+        ))
+    }
+}
+
+fn git_output(cwd: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(cwd)
+        .output()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+/// Resolve a built-in placeholder, or `None` if the name is not a built-in
+fn resolve_builtin(name: &str, cwd: &Path, now: DateTime<Local>) -> Option<Result<String, String>> {
+    if let Some(file) = name.strip_prefix("file:") {
+        let path = cwd.join(file.trim());
+        return Some(
+            fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e)),
+        );
+    }
+
+    let value = match name {
+        "git.branch" => {
+            let result = git::get_git_branch(cwd.to_string_lossy().to_string());
+            match result.data.flatten() {
+                Some(branch) => Ok(branch),
+                None => Err(format!("Not a git repository: {}", cwd.display())),
+            }
+        }
+        "git.diff" => git_output(cwd, &["diff"]),
+        "git.diff.staged" => git_output(cwd, &["diff", "--cached"]),
+        "git.status" => git_output(cwd, &["status", "--short"]),
+        "date" => Ok(now.format("%Y-%m-%d").to_string()),
+        "yesterday" => Ok((now - chrono::Duration::days(1))
+            .format("%Y-%m-%d")
+            .to_string()),
+        "time" => Ok(now.format("%H:%M").to_string()),
+        "cwd" => Ok(cwd.display().to_string()),
+        _ => return None,
+    };
+    Some(value)
+}
+
+/// Render a template body with the given values
+fn render(
+    template: &PromptTemplate,
+    values: &HashMap<String, String>,
+    cwd: &Path,
+) -> Result<RenderedTemplate, String> {
+    render_at(template, values, cwd, Local::now())
+}
+
+/// Render a template, resolving date built-ins relative to `now`
+pub(crate) fn render_at(
+    template: &PromptTemplate,
+    values: &HashMap<String, String>,
+    cwd: &Path,
+    now: DateTime<Local>,
+) -> Result<RenderedTemplate, String> {
+    let mut prompt = String::with_capacity(template.body.len());
+    let mut placeholders = Vec::new();
+    let mut rest = template.body.as_str();
+
+    while let Some(start) = rest.find("{{") {
+        if rest[..start].ends_with('\\') {
+            prompt.push_str(&rest[..start - 1]);
+            prompt.push_str("{{");
+            rest = &rest[start + 2..];
+            continue;
+        }
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        prompt.push_str(&rest[..start]);
+        let placeholder = &rest[start..start + 2 + len + 2];
+        let name = rest[start + 2..start + 2 + len].trim();
+        rest = &rest[start + 2 + len + 2..];
+
+        let (value, source) = match template.variables.iter().find(|v| v.name == name) {
+            Some(var) => match (values.get(name), &var.default) {
+                (Some(value), _) => {
+                    validate_value(var, value, cwd)?;
+                    (value.clone(), ValueSource::User)
+                }
+                (None, Some(default)) => (default.clone(), ValueSource::Default),
+                (None, None) if var.required => {
+                    return Err(format!("Missing value for variable '{}'", name))
+                }
+                (None, None) => (String::new(), ValueSource::Default),
+            },
+            None => match resolve_builtin(name, cwd, now) {
+                Some(value) => (value?, ValueSource::Builtin),
+                None => {
+                    prompt.push_str(placeholder);
+                    continue;
+                }
+            },
+        };
+
+        placeholders.push(ResolvedPlaceholder {
+            placeholder: name.to_string(),
+            source,
+            length: value.len(),
+        });
+        prompt.push_str(&value);
+    }
+    prompt.push_str(rest);
+
+    Ok(RenderedTemplate {
+        prompt,
+        placeholders,
+    })
+}
+
+/// Render on a blocking thread; built-ins run git and read files
+pub(crate) async fn render_blocking(
+    template: PromptTemplate,
+    values: HashMap<String, String>,
+    cwd: PathBuf,
+) -> Result<RenderedTemplate, String> {
+    tokio::task::spawn_blocking(move || render(&template, &values, &cwd))
+        .await
+        .map_err(|e| format!("Failed to render template: {}", e))?
+}
+
+pub(crate) fn working_dir(cwd: Option<&str>) -> PathBuf {
+    cwd.map(PathBuf::from)
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_default()
+}
+
+/// List all prompt templates
+#[command]
+pub async fn list_prompt_templates() -> CommandResult<Vec<PromptTemplate>> {
+    let dir = templates_dir();
+    if !dir.exists() {
+        return CommandResult::ok(vec![]);
+    }
+
+    let entries = match fs::read_dir(&dir) {
+        Ok(e) => e,
+        Err(e) => return CommandResult::err(format!("Failed to list templates: {}", e)),
+    };
+
+    let mut templates = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().is_some_and(|e| e == "json") {
+            match fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|c| serde_json::from_str(&c).map_err(|e| e.to_string()))
+            {
+                Ok(template) => templates.push(template),
+                Err(e) => log::warn!("Skipping template {}: {}", path.display(), e),
+            }
+        }
+    }
+    templates.sort_by(|a: &PromptTemplate, b| a.name.cmp(&b.name));
+
+    CommandResult::ok(templates)
+}
+
+/// Create or replace a prompt template
+#[command]
+pub async fn save_prompt_template(template: PromptTemplate) -> CommandResult<()> {
+    let path = match template_path(&template.name) {
+        Ok(p) => p,
+        Err(e) => return CommandResult::err(e),
+    };
+
+    for var in &template.variables {
+        if var.kind == VariableKind::Choice && var.choices.is_empty() {
+            return CommandResult::err(format!("Variable '{}' has no choices", var.name));
+        }
+    }
+
+    if let Err(e) = fs::create_dir_all(templates_dir()) {
+        return CommandResult::err(format!("Failed to create directory: {}", e));
+    }
+
+    let content = match serde_json::to_string_pretty(&template) {
+        Ok(c) => c,
+        Err(e) => return CommandResult::err(format!("Failed to serialize template: {}", e)),
+    };
+
+    match fs::write(&path, content) {
+        Ok(()) => CommandResult::ok(()),
+        Err(e) => CommandResult::err(format!("Failed to write template: {}", e)),
+    }
+}
+
+/// Delete a prompt template
+#[command]
+pub async fn delete_prompt_template(name: String) -> CommandResult<()> {
+    let path = match template_path(&name) {
+        Ok(p) => p,
+        Err(e) => return CommandResult::err(e),
+    };
+
+    match fs::remove_file(&path) {
+        Ok(()) => CommandResult::ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            CommandResult::err(format!("Template not found: {}", name))
+        }
+        Err(e) => CommandResult::err(format!("Failed to delete template: {}", e)),
+    }
+}
+
+/// Render a template without invoking Claude
+#[command]
+pub async fn render_prompt_template(
+    name: String,
+    values: Option<HashMap<String, String>>,
+    cwd: Option<String>,
+) -> CommandResult<RenderedTemplate> {
+    let template = match load_template(&name) {
+        Ok(t) => t,
+        Err(e) => return CommandResult::err(e),
+    };
+    let cwd = working_dir(cwd.as_deref());
+
+    match render_blocking(template, values.unwrap_or_default(), cwd).await {
+        Ok(rendered) => CommandResult::ok(rendered),
+        Err(e) => CommandResult::err(e),
+    }
+}
+
+/// Render a template and invoke Claude (non-streaming) with the result
+#[command]
+pub async fn invoke_claude_template(
+    app: AppHandle,
+    name: String,
+    values: Option<HashMap<String, String>>,
+    options: Option<ClaudeOptions>,
+) -> CommandResult<ClaudeInvocation> {
+    let options = options.unwrap_or_default();
+    let cwd = working_dir(options.cwd.as_deref());
+
+    let template = match load_template(&name) {
+        Ok(t) => t,
+        Err(e) => return CommandResult::err(e),
+    };
+
+    match render_blocking(template, values.unwrap_or_default(), cwd).await {
+        Ok(rendered) => {
+            let job_id = uuid::Uuid::new_v4().to_string();
+            claude::execute_claude_job(&app, &job_id, &rendered.prompt, &options).await
+        }
+        Err(e) => CommandResult::err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn template(body: &str, variables: Vec<TemplateVariable>) -> PromptTemplate {
+        PromptTemplate {
+            name: "test".to_string(),
+            description: None,
+            body: body.to_string(),
+            variables,
+        }
+    }
+
+    fn var(name: &str, kind: VariableKind) -> TemplateVariable {
+        TemplateVariable {
+            name: name.to_string(),
+            kind,
+            description: None,
+            required: true,
+            default: None,
+            choices: vec![],
+        }
+    }
+
+    fn values(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_render_user_and_default_values() {
+        let mut tone = var("tone", VariableKind::String);
+        tone.default = Some("terse".to_string());
+        let t = template(
+            "Review {{ file }} in a {{tone}} style.",
+            vec![var("file", VariableKind::String), tone],
+        );
+
+        let rendered = render(&t, &values(&[("file", "main.rs")]), Path::new(".")).unwrap();
+        assert_eq!(rendered.prompt, "Review main.rs in a terse style.");
+        assert_eq!(rendered.placeholders[0].source, ValueSource::User);
+        assert_eq!(rendered.placeholders[1].source, ValueSource::Default);
+    }
+
+    #[test]
+    fn test_render_missing_and_unknown() {
+        let t = template("{{name}}", vec![var("name", VariableKind::String)]);
+        assert!(render(&t, &HashMap::new(), Path::new(".")).is_err());
+
+        let t = template("<p>{{ user.name }}</p> {% if x %}{{nope}}", vec![]);
+        let rendered = render(&t, &HashMap::new(), Path::new(".")).unwrap();
+        assert_eq!(rendered.prompt, "<p>{{ user.name }}</p> {% if x %}{{nope}}");
+        assert!(rendered.placeholders.is_empty());
+    }
+
+    #[test]
+    fn test_render_escaped_braces() {
+        let t = template(r"\{{cwd}} is {{cwd}}", vec![]);
+        let rendered = render(&t, &HashMap::new(), Path::new("/repo")).unwrap();
+        assert_eq!(rendered.prompt, "{{cwd}} is /repo");
+    }
+
+    #[test]
+    fn test_render_validates_types() {
+        let mut level = var("level", VariableKind::Choice);
+        level.choices = vec!["low".to_string(), "high".to_string()];
+        let t = template(
+            "{{count}} {{level}}",
+            vec![var("count", VariableKind::Number), level],
+        );
+
+        let ok = render(
+            &t,
+            &values(&[("count", "3"), ("level", "high")]),
+            Path::new("."),
+        );
+        assert_eq!(ok.unwrap().prompt, "3 high");
+
+        let bad = render(
+            &t,
+            &values(&[("count", "x"), ("level", "high")]),
+            Path::new("."),
+        );
+        assert!(bad.is_err());
+    }
+
+    #[test]
+    fn test_render_file_builtin() {
+        let cwd = env::current_dir().unwrap();
+        let t = template("{{file:Cargo.toml}}", vec![]);
+        let rendered = render(&t, &HashMap::new(), &cwd).unwrap();
+        assert!(rendered.prompt.contains("[package]"));
+        assert_eq!(rendered.placeholders[0].source, ValueSource::Builtin);
+    }
+
+    #[test]
+    fn test_render_leaves_unclosed_braces() {
+        let t = template("literal {{ not closed", vec![]);
+        let rendered = render(&t, &HashMap::new(), Path::new(".")).unwrap();
+        assert_eq!(rendered.prompt, "literal {{ not closed");
+    }
+
+    #[test]
+    fn test_validate_name() {
+        assert!(validate_name("code-review_2").is_ok());
+        assert!(validate_name("../etc").is_err());
+        assert!(validate_name("").is_err());
+    }
+}
@@ -0,0 +1,629 @@
+//! Usage ledger
+//!
+//! Persists the cost and token usage reported by Claude `result` messages in
+//! an append-only JSONL file (`~/.codepod/usage.jsonl`) and answers aggregate
+//! queries over it.
+
+use super::jobs::now_millis;
+use super::sessions;
+use super::{codepod_dir, CommandResult};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tauri::command;
+use uuid::Uuid;
+
+// Serializes appends to the ledger file, and transcript imports
+lazy_static::lazy_static! {
+    static ref LEDGER_LOCK: Mutex<()> = Mutex::new(());
+    static ref IMPORT_LOCK: Mutex<()> = Mutex::new(());
+}
+
+/// One usage entry in the ledger
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UsageRecord {
+    pub id: String,
+    pub timestamp: u64,
+    /// Local calendar day, `YYYY-MM-DD`
+    pub day: String,
+    pub project: Option<String>,
+    pub session_id: Option<String>,
+    pub model: Option<String>,
+    /// Where the record came from: `stream`, `invoke` or `transcript`
+    pub source: String,
+    pub cost_usd: f64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_read_input_tokens: u64,
+    pub cache_creation_input_tokens: u64,
+    /// Assistant messages the record covers; a `result` record without any
+    /// stands for its whole session
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub message_ids: Vec<String>,
+}
+
+/// Filter applied before aggregating
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UsageFilter {
+    /// Inclusive start day, `YYYY-MM-DD`
+    pub from_day: Option<String>,
+    /// Inclusive end day, `YYYY-MM-DD`
+    pub to_day: Option<String>,
+    pub project: Option<String>,
+    pub session_id: Option<String>,
+    pub model: Option<String>,
+}
+
+/// Dimension to aggregate usage by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UsageGroupBy {
+    Day,
+    Project,
+    Session,
+    Model,
+}
+
+/// Aggregated usage totals
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageTotals {
+    pub records: u64,
+    pub cost_usd: f64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_read_input_tokens: u64,
+    pub cache_creation_input_tokens: u64,
+}
+
+/// Usage totals for one group key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageGroup {
+    pub key: Option<String>,
+    pub totals: UsageTotals,
+}
+
+impl UsageFilter {
+    fn matches(&self, record: &UsageRecord) -> bool {
+        let eq = |want: &Option<String>, have: &Option<String>| want.is_none() || want == have;
+
+        self.from_day.as_ref().map_or(true, |d| &record.day >= d)
+            && self.to_day.as_ref().map_or(true, |d| &record.day <= d)
+            && eq(&self.project, &record.project)
+            && eq(&self.session_id, &record.session_id)
+            && eq(&self.model, &record.model)
+    }
+}
+
+impl UsageTotals {
+    fn add(&mut self, record: &UsageRecord) {
+        self.records += 1;
+        self.cost_usd += record.cost_usd;
+        self.input_tokens += record.input_tokens;
+        self.output_tokens += record.output_tokens;
+        self.cache_read_input_tokens += record.cache_read_input_tokens;
+        self.cache_creation_input_tokens += record.cache_creation_input_tokens;
+    }
+}
+
+/// Local calendar day for a millisecond timestamp
+fn day_of(timestamp: u64) -> String {
+    chrono::DateTime::from_timestamp_millis(timestamp as i64)
+        .map(|t| {
+            t.with_timezone(&chrono::Local)
+                .format("%Y-%m-%d")
+                .to_string()
+        })
+        .unwrap_or_default()
+}
+
+fn ledger_path() -> PathBuf {
+    codepod_dir().join("usage.jsonl")
+}
+
+fn u64_field(value: &serde_json::Value, key: &str) -> u64 {
+    value.get(key).and_then(|v| v.as_u64()).unwrap_or(0)
+}
+
+/// Build ledger records from a stream-json or json `result` message
+///
+/// When the message breaks usage down per model (`modelUsage`), one record
+/// is produced per model; otherwise a single record uses `model_hint`.
+pub fn records_from_result(
+    result: &serde_json::Value,
+    project: Option<&str>,
+    model_hint: Option<&str>,
+    source: &str,
+) -> Vec<UsageRecord> {
+    if result.get("type").and_then(|v| v.as_str()) != Some("result") {
+        return vec![];
+    }
+
+    let timestamp = now_millis();
+    let base = UsageRecord {
+        id: Uuid::new_v4().to_string(),
+        timestamp,
+        day: day_of(timestamp),
+        project: project.map(String::from),
+        session_id: result
+            .get("session_id")
+            .and_then(|v| v.as_str())
+            .map(String::from),
+        model: model_hint.map(String::from),
+        source: source.to_string(),
+        ..Default::default()
+    };
+
+    let per_model = result
+        .get("modelUsage")
+        .and_then(|v| v.as_object())
+        .filter(|m| !m.is_empty());
+
+    if let Some(models) = per_model {
+        return models
+            .iter()
+            .map(|(model, usage)| UsageRecord {
+                id: format!("{}:{}", base.id, model),
+                model: Some(model.clone()),
+                cost_usd: usage.get("costUSD").and_then(|v| v.as_f64()).unwrap_or(0.0),
+                input_tokens: u64_field(usage, "inputTokens"),
+                output_tokens: u64_field(usage, "outputTokens"),
+                cache_read_input_tokens: u64_field(usage, "cacheReadInputTokens"),
+                cache_creation_input_tokens: u64_field(usage, "cacheCreationInputTokens"),
+                ..base.clone()
+            })
+            .collect();
+    }
+
+    let usage = result.get("usage").cloned().unwrap_or_default();
+    vec![UsageRecord {
+        cost_usd: result
+            .get("total_cost_usd")
+            .or_else(|| result.get("cost_usd"))
+            .and_then(|v| v.as_f64())
+            .unwrap_or(0.0),
+        input_tokens: u64_field(&usage, "input_tokens"),
+        output_tokens: u64_field(&usage, "output_tokens"),
+        cache_read_input_tokens: u64_field(&usage, "cache_read_input_tokens"),
+        cache_creation_input_tokens: u64_field(&usage, "cache_creation_input_tokens"),
+        ..base
+    }]
+}
+
+/// Build ledger records from the assistant messages of a session transcript
+///
+/// Transcripts carry token usage but no cost. Streamed messages repeat the
+/// same message id, so only the last usage seen per id is kept.
+pub fn records_from_transcript(content: &str) -> Vec<UsageRecord> {
+    let mut by_message: BTreeMap<String, UsageRecord> = BTreeMap::new();
+
+    for line in content.lines() {
+        let Ok(entry) = serde_json::from_str::<serde_json::Value>(line) else {
+            continue;
+        };
+        if entry.get("type").and_then(|v| v.as_str()) != Some("assistant") {
+            continue;
+        }
+
+        let message = entry.get("message").cloned().unwrap_or_default();
+        let (Some(message_id), Some(usage)) = (
+            message.get("id").and_then(|v| v.as_str()),
+            message.get("usage"),
+        ) else {
+            continue;
+        };
+
+        let session_id = entry.get("sessionId").and_then(|v| v.as_str());
+        let timestamp = entry
+            .get("timestamp")
+            .and_then(|v| v.as_str())
+            .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+            .map(|t| t.timestamp_millis() as u64)
+            .unwrap_or_else(now_millis);
+
+        by_message.insert(
+            message_id.to_string(),
+            UsageRecord {
+                id: format!(
+                    "transcript:{}:{}",
+                    session_id.unwrap_or_default(),
+                    message_id
+                ),
+                timestamp,
+                day: day_of(timestamp),
+                project: entry.get("cwd").and_then(|v| v.as_str()).map(String::from),
+                session_id: session_id.map(String::from),
+                model: message
+                    .get("model")
+                    .and_then(|v| v.as_str())
+                    .map(String::from),
+                source: "transcript".to_string(),
+                cost_usd: 0.0,
+                input_tokens: u64_field(usage, "input_tokens"),
+                output_tokens: u64_field(usage, "output_tokens"),
+                cache_read_input_tokens: u64_field(usage, "cache_read_input_tokens"),
+                cache_creation_input_tokens: u64_field(usage, "cache_creation_input_tokens"),
+                message_ids: vec![message_id.to_string()],
+            },
+        );
+    }
+
+    by_message.into_values().collect()
+}
+
+fn append_records(path: &Path, records: &[UsageRecord]) -> std::io::Result<()> {
+    if records.is_empty() {
+        return Ok(());
+    }
+
+    let _guard = LEDGER_LOCK.lock();
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut lines = String::new();
+    for record in records {
+        lines.push_str(&serde_json::to_string(record)?);
+        lines.push('\n');
+    }
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(lines.as_bytes())
+}
+
+fn load_records(path: &Path) -> std::io::Result<Vec<UsageRecord>> {
+    let content = match fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e),
+    };
+
+    // Skip lines that fail to parse, e.g. a partial line from a crash
+    Ok(content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Record the usage of a `result` message in the ledger
+///
+/// `message_ids` are the assistant messages of the run, so that importing
+/// the session's transcript later does not count them again.
+pub fn record_result(
+    result: &serde_json::Value,
+    project: Option<&str>,
+    model_hint: Option<&str>,
+    source: &str,
+    message_ids: &[String],
+) {
+    let mut records = records_from_result(result, project, model_hint, source);
+    for record in &mut records {
+        record.message_ids = message_ids.to_vec();
+    }
+    if let Err(e) = append_records(&ledger_path(), &records) {
+        log::error!("Failed to write usage ledger: {}", e);
+    }
+}
+
+/// Sum all ledger records matching the filter
+pub fn usage_totals(filter: &UsageFilter) -> std::io::Result<UsageTotals> {
+    let mut totals = UsageTotals::default();
+    for record in load_records(&ledger_path())? {
+        if filter.matches(&record) {
+            totals.add(&record);
+        }
+    }
+    Ok(totals)
+}
+
+fn group_records(
+    records: &[UsageRecord],
+    filter: &UsageFilter,
+    group_by: UsageGroupBy,
+) -> Vec<UsageGroup> {
+    let mut groups: BTreeMap<Option<String>, UsageTotals> = BTreeMap::new();
+
+    for record in records.iter().filter(|r| filter.matches(r)) {
+        let key = match group_by {
+            UsageGroupBy::Day => Some(record.day.clone()),
+            UsageGroupBy::Project => record.project.clone(),
+            UsageGroupBy::Session => record.session_id.clone(),
+            UsageGroupBy::Model => record.model.clone(),
+        };
+        groups.entry(key).or_default().add(record);
+    }
+
+    groups
+        .into_iter()
+        .map(|(key, totals)| UsageGroup { key, totals })
+        .collect()
+}
+
+/// Aggregate ledger usage grouped by day, project, session or model
+#[command]
+pub async fn query_usage(
+    filter: Option<UsageFilter>,
+    group_by: UsageGroupBy,
+) -> CommandResult<Vec<UsageGroup>> {
+    match load_records(&ledger_path()) {
+        Ok(records) => CommandResult::ok(group_records(
+            &records,
+            &filter.unwrap_or_default(),
+            group_by,
+        )),
+        Err(e) => CommandResult::err(format!("Failed to read usage ledger: {}", e)),
+    }
+}
+
+/// Get overall usage totals for the filter
+#[command]
+pub async fn get_usage_totals(filter: Option<UsageFilter>) -> CommandResult<UsageTotals> {
+    match usage_totals(&filter.unwrap_or_default()) {
+        Ok(totals) => CommandResult::ok(totals),
+        Err(e) => CommandResult::err(format!("Failed to read usage ledger: {}", e)),
+    }
+}
+
+/// Transcript records not yet accounted for in the ledger
+///
+/// Messages are matched by id whatever session they were recorded under, as
+/// a resumed session's transcript repeats the messages of the one before.
+/// `result` records that don't list their messages cover their whole
+/// session, so its transcript messages are skipped.
+fn unrecorded(transcript: Vec<UsageRecord>, ledger: &[UsageRecord]) -> Vec<UsageRecord> {
+    let known: HashSet<&str> = ledger.iter().map(|r| r.id.as_str()).collect();
+    let known_messages: HashSet<&str> = ledger
+        .iter()
+        .flat_map(|r| &r.message_ids)
+        .map(String::as_str)
+        .collect();
+    let recorded_sessions: HashSet<&str> = ledger
+        .iter()
+        .filter(|r| r.source != "transcript" && r.message_ids.is_empty())
+        .filter_map(|r| r.session_id.as_deref())
+        .collect();
+
+    let mut seen = HashSet::new();
+    transcript
+        .into_iter()
+        .filter(|r| !known.contains(r.id.as_str()))
+        .filter(|r| {
+            r.message_ids
+                .iter()
+                .all(|m| !known_messages.contains(m.as_str()) && seen.insert(m.clone()))
+        })
+        .filter(|r| {
+            r.session_id
+                .as_deref()
+                .map_or(true, |s| !recorded_sessions.contains(s))
+        })
+        .collect()
+}
+
+fn import_transcript(path: &Path) -> Result<usize, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    let ledger = ledger_path();
+    // Held across the read so concurrent imports can't both append a message
+    let _guard = IMPORT_LOCK.lock();
+    let existing =
+        load_records(&ledger).map_err(|e| format!("Failed to read usage ledger: {}", e))?;
+    let new_records = unrecorded(records_from_transcript(&content), &existing);
+
+    append_records(&ledger, &new_records)
+        .map_err(|e| format!("Failed to write usage ledger: {}", e))?;
+    Ok(new_records.len())
+}
+
+/// Import the transcript of a session that ran outside the invocation path,
+/// e.g. an interactive terminal session once it exits
+pub(crate) fn import_session_usage(session_id: &str) {
+    let result = sessions::find_transcript(session_id).and_then(|path| import_transcript(&path));
+    if let Err(e) = result {
+        log::warn!("Failed to import usage of session {}: {}", session_id, e);
+    }
+}
+
+/// Import token usage from a session transcript JSONL into the ledger
+///
+/// Returns the number of new records; messages already in the ledger and
+/// sessions CodePod recorded itself are skipped.
+#[command]
+pub async fn import_transcript_usage(path: String) -> CommandResult<usize> {
+    match import_transcript(Path::new(&path)) {
+        Ok(count) => CommandResult::ok(count),
+        Err(e) => CommandResult::err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn record(day: &str, project: &str, model: &str, cost: f64) -> UsageRecord {
+        UsageRecord {
+            id: Uuid::new_v4().to_string(),
+            day: day.to_string(),
+            project: Some(project.to_string()),
+            model: Some(model.to_string()),
+            cost_usd: cost,
+            input_tokens: 10,
+            cache_read_input_tokens: 100,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_records_from_result_single() {
+        let result = serde_json::json!({
+            "type": "result",
+            "session_id": "abc",
+            "total_cost_usd": 0.25,
+            "usage": {
+                "input_tokens": 12,
+                "output_tokens": 34,
+                "cache_read_input_tokens": 56
+            }
+        });
+
+        let records = records_from_result(&result, Some("/repo"), Some("sonnet"), "stream");
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].session_id.as_deref(), Some("abc"));
+        assert_eq!(records[0].model.as_deref(), Some("sonnet"));
+        assert_eq!(records[0].cost_usd, 0.25);
+        assert_eq!(records[0].output_tokens, 34);
+        assert_eq!(records[0].cache_read_input_tokens, 56);
+    }
+
+    #[test]
+    fn test_records_from_result_per_model() {
+        let result = serde_json::json!({
+            "type": "result",
+            "total_cost_usd": 0.3,
+            "modelUsage": {
+                "claude-haiku": { "inputTokens": 5, "costUSD": 0.1 },
+                "claude-sonnet": { "inputTokens": 7, "costUSD": 0.2 }
+            }
+        });
+
+        let records = records_from_result(&result, None, None, "invoke");
+        assert_eq!(records.len(), 2);
+        let total: f64 = records.iter().map(|r| r.cost_usd).sum();
+        assert!((total - 0.3).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_records_from_non_result_message() {
+        let message = serde_json::json!({ "type": "assistant" });
+        assert!(records_from_result(&message, None, None, "stream").is_empty());
+    }
+
+    #[test]
+    fn test_records_from_transcript_dedupes_messages() {
+        let transcript = [
+            r#"{"type":"user","message":{"role":"user"}}"#,
+            r#"{"type":"assistant","sessionId":"s1","cwd":"/repo","timestamp":"2025-01-02T03:04:05Z","message":{"id":"m1","model":"claude-sonnet","usage":{"input_tokens":1,"output_tokens":2}}}"#,
+            r#"{"type":"assistant","sessionId":"s1","cwd":"/repo","timestamp":"2025-01-02T03:04:06Z","message":{"id":"m1","model":"claude-sonnet","usage":{"input_tokens":1,"output_tokens":9}}}"#,
+        ]
+        .join("\n");
+
+        let records = records_from_transcript(&transcript);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].id, "transcript:s1:m1");
+        assert_eq!(records[0].output_tokens, 9);
+        assert_eq!(records[0].project.as_deref(), Some("/repo"));
+    }
+
+    #[test]
+    fn test_unrecorded_skips_sessions_with_results() {
+        let transcript = |session: &str, message: &str| UsageRecord {
+            id: format!("transcript:{}:{}", session, message),
+            session_id: Some(session.to_string()),
+            source: "transcript".to_string(),
+            ..Default::default()
+        };
+        let ledger = [
+            UsageRecord {
+                id: "r1".to_string(),
+                session_id: Some("ran".to_string()),
+                source: "stream".to_string(),
+                ..Default::default()
+            },
+            transcript("cli", "m1"),
+        ];
+
+        let new = unrecorded(
+            vec![
+                transcript("ran", "m1"),
+                transcript("cli", "m1"),
+                transcript("cli", "m2"),
+            ],
+            &ledger,
+        );
+        let ids: Vec<&str> = new.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, ["transcript:cli:m2"]);
+    }
+
+    #[test]
+    fn test_unrecorded_skips_messages_recorded_elsewhere() {
+        let transcript = |session: &str, message: &str| UsageRecord {
+            id: format!("transcript:{}:{}", session, message),
+            session_id: Some(session.to_string()),
+            source: "transcript".to_string(),
+            message_ids: vec![message.to_string()],
+            ..Default::default()
+        };
+        let ledger = [
+            UsageRecord {
+                id: "r1".to_string(),
+                session_id: Some("streamed".to_string()),
+                source: "stream".to_string(),
+                message_ids: vec!["m1".to_string()],
+                ..Default::default()
+            },
+            transcript("parent", "m2"),
+        ];
+
+        // A resumed session repeats its parent's messages
+        let new = unrecorded(
+            vec![
+                transcript("streamed", "m1"),
+                transcript("streamed", "m3"),
+                transcript("child", "m2"),
+                transcript("child", "m4"),
+                transcript("other", "m4"),
+            ],
+            &ledger,
+        );
+        let ids: Vec<&str> = new.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, ["transcript:streamed:m3", "transcript:child:m4"]);
+    }
+
+    #[test]
+    fn test_group_records() {
+        let records = vec![
+            record("2025-01-01", "/a", "sonnet", 1.0),
+            record("2025-01-01", "/b", "opus", 2.0),
+            record("2025-01-02", "/a", "sonnet", 4.0),
+        ];
+
+        let by_day = group_records(&records, &UsageFilter::default(), UsageGroupBy::Day);
+        assert_eq!(by_day.len(), 2);
+        assert_eq!(by_day[0].key.as_deref(), Some("2025-01-01"));
+        assert_eq!(by_day[0].totals.cost_usd, 3.0);
+        assert_eq!(by_day[0].totals.cache_read_input_tokens, 200);
+
+        let filter = UsageFilter {
+            from_day: Some("2025-01-02".to_string()),
+            ..Default::default()
+        };
+        let by_project = group_records(&records, &filter, UsageGroupBy::Project);
+        assert_eq!(by_project.len(), 1);
+        assert_eq!(by_project[0].key.as_deref(), Some("/a"));
+        assert_eq!(by_project[0].totals.records, 1);
+    }
+
+    #[test]
+    fn test_append_and_load_records() {
+        let path = env::temp_dir()
+            .join(format!("codepod-usage-{}", Uuid::new_v4()))
+            .join("usage.jsonl");
+
+        let records = vec![record("2025-01-01", "/a", "sonnet", 1.5)];
+        append_records(&path, &records).unwrap();
+        append_records(&path, &records).unwrap();
+
+        let loaded = load_records(&path).unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[0], records[0]);
+
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+}
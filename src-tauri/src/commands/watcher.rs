@@ -0,0 +1,598 @@
+//! Config file watcher
+//!
+//! Watches the user's Claude config and that of the projects open in the app,
+//! and emits a `config-changed` event for each file that changed once its
+//! events have settled. Only the directories that hold config are watched, so
+//! transcripts and other CLI state under `~/.claude` cost nothing.
+
+use super::mcp::claude_json_path;
+use super::settings::SettingsScope;
+use super::{claude_dir, CommandResult};
+use notify::event::EventKind;
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use parking_lot::Mutex;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+use tauri::{command, AppHandle, Emitter};
+
+/// Quiet period after which pending changes are reported
+const DEBOUNCE: Duration = Duration::from_millis(300);
+/// Longest a change waits while events keep arriving
+const MAX_DELAY: Duration = Duration::from_secs(2);
+/// Directories holding one definition per file or subdirectory
+const DEFINITION_DIRS: [&str; 3] = ["commands", "agents", "skills"];
+
+/// What a changed file configures
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConfigKind {
+    Settings,
+    McpServers,
+    Commands,
+    Agents,
+    Skills,
+    Plugins,
+}
+
+impl ConfigKind {
+    fn from_dir(name: &str) -> Option<Self> {
+        match name {
+            "commands" => Some(Self::Commands),
+            "agents" => Some(Self::Agents),
+            "skills" => Some(Self::Skills),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Created,
+    Modified,
+    Removed,
+}
+
+/// Payload of the `config-changed` event
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConfigChanged {
+    pub path: String,
+    pub scope: SettingsScope,
+    pub kind: ConfigKind,
+    pub change: ChangeKind,
+    /// Project the file belongs to, for project and local scopes
+    pub project_path: Option<String>,
+}
+
+/// Where config lives
+#[derive(Debug, Clone, Default)]
+struct WatchRoots {
+    claude_dir: PathBuf,
+    claude_json: PathBuf,
+    /// Watched projects and how many callers asked for each
+    projects: BTreeMap<PathBuf, usize>,
+}
+
+impl WatchRoots {
+    fn user() -> Self {
+        Self {
+            claude_dir: claude_dir(),
+            claude_json: claude_json_path(),
+            projects: BTreeMap::new(),
+        }
+    }
+
+    /// Directories to watch, whether or not they exist yet
+    ///
+    /// Parents are watched without recursion so that config directories
+    /// created later are noticed and picked up.
+    fn targets(&self) -> BTreeMap<PathBuf, RecursiveMode> {
+        let mut targets = BTreeMap::new();
+        let mut add = |path: PathBuf, mode: RecursiveMode| {
+            let current = targets.entry(path).or_insert(mode);
+            if mode == RecursiveMode::Recursive {
+                *current = mode;
+            }
+        };
+
+        if let Some(parent) = self.claude_json.parent() {
+            add(parent.to_path_buf(), RecursiveMode::NonRecursive);
+        }
+        add(self.claude_dir.join("plugins"), RecursiveMode::NonRecursive);
+
+        let config_dirs = std::iter::once(self.claude_dir.clone())
+            .chain(self.projects.keys().map(|p| p.join(".claude")));
+        for dir in config_dirs {
+            for name in DEFINITION_DIRS {
+                add(dir.join(name), RecursiveMode::Recursive);
+            }
+            add(dir.join(".disabled"), RecursiveMode::Recursive);
+            add(dir, RecursiveMode::NonRecursive);
+        }
+        for project in self.projects.keys() {
+            add(project.clone(), RecursiveMode::NonRecursive);
+        }
+        targets
+    }
+
+    /// Scope, kind and project of a config path; `None` for anything else
+    fn classify(&self, path: &Path) -> Option<(SettingsScope, ConfigKind, Option<&Path>)> {
+        let name = path.file_name()?.to_str()?;
+        if is_scratch_file(name) {
+            return None;
+        }
+
+        if path == self.claude_json {
+            return Some((SettingsScope::User, ConfigKind::McpServers, None));
+        }
+        if let Ok(relative) = path.strip_prefix(&self.claude_dir) {
+            if let Some(kind) = classify_relative(relative, true) {
+                return Some((SettingsScope::User, kind, None));
+            }
+        }
+
+        for project in self.projects.keys() {
+            if path == project.join(".mcp.json") {
+                return Some((
+                    SettingsScope::Project,
+                    ConfigKind::McpServers,
+                    Some(project),
+                ));
+            }
+            let Ok(relative) = path.strip_prefix(project.join(".claude")) else {
+                continue;
+            };
+            if let Some(kind) = classify_relative(relative, false) {
+                let scope = if relative == Path::new("settings.local.json") {
+                    SettingsScope::Local
+                } else {
+                    SettingsScope::Project
+                };
+                return Some((scope, kind, Some(project)));
+            }
+        }
+        None
+    }
+}
+
+/// Kind of a path inside a `.claude` directory
+fn classify_relative(relative: &Path, user: bool) -> Option<ConfigKind> {
+    let parts: Vec<&str> = relative
+        .components()
+        .map(|c| c.as_os_str().to_str())
+        .collect::<Option<_>>()?;
+    match parts.as_slice() {
+        ["settings.json"] | ["settings.local.json"] => Some(ConfigKind::Settings),
+        ["plugins", "installed_plugins.json"] if user => Some(ConfigKind::Plugins),
+        [".disabled", dir, ..] | [dir, ..] => ConfigKind::from_dir(dir),
+        _ => None,
+    }
+}
+
+/// Editor swap files and the temporary files of atomic writes
+fn is_scratch_file(name: &str) -> bool {
+    let hidden = name.starts_with('.') && name != ".mcp.json" && name != ".claude.json";
+    hidden || name.ends_with('~') || name.ends_with(".swp") || name.ends_with(".tmp")
+}
+
+/// A change seen but not reported yet
+struct Pending {
+    scope: SettingsScope,
+    kind: ConfigKind,
+    project_path: Option<String>,
+    /// What the first event in the window did
+    first: ChangeKind,
+}
+
+fn record(pending: &mut BTreeMap<PathBuf, Pending>, event: &Event, roots: &WatchRoots) {
+    let change = match event.kind {
+        EventKind::Access(_) => return,
+        EventKind::Create(_) => ChangeKind::Created,
+        EventKind::Remove(_) => ChangeKind::Removed,
+        _ => ChangeKind::Modified,
+    };
+    for path in &event.paths {
+        if pending.contains_key(path) {
+            continue;
+        }
+        if let Some((scope, kind, project)) = roots.classify(path) {
+            pending.insert(
+                path.clone(),
+                Pending {
+                    scope,
+                    kind,
+                    project_path: project.map(|p| p.to_string_lossy().into_owned()),
+                    first: change,
+                },
+            );
+        }
+    }
+}
+
+/// The MCP server entries of `~/.claude.json`; `None` when it can't be read
+///
+/// The CLI rewrites that file constantly with unrelated state, so only these
+/// entries decide whether a change is reported.
+fn mcp_sections(path: &Path) -> Option<Value> {
+    let content = std::fs::read_to_string(path).ok()?;
+    let json: Value = serde_json::from_str(&content).ok()?;
+    let projects: Map<String, Value> = json
+        .get("projects")
+        .and_then(Value::as_object)
+        .into_iter()
+        .flatten()
+        .filter_map(|(name, project)| Some((name.clone(), project.get("mcpServers")?.clone())))
+        .collect();
+    Some(serde_json::json!({
+        "mcpServers": json.get("mcpServers"),
+        "projects": projects,
+    }))
+}
+
+/// Report pending changes by comparing what was there with what is there now
+///
+/// A file created and removed within one window is not reported, and neither
+/// is a change to `claude_json` that leaves `mcp_seen` as it was.
+fn flush(
+    pending: &mut BTreeMap<PathBuf, Pending>,
+    claude_json: &Path,
+    mcp_seen: &mut Option<Value>,
+) -> Vec<ConfigChanged> {
+    std::mem::take(pending)
+        .into_iter()
+        .filter_map(|(path, p)| {
+            let change = match (p.first, path.exists()) {
+                (ChangeKind::Created, false) => return None,
+                (_, false) => ChangeKind::Removed,
+                (ChangeKind::Created, true) => ChangeKind::Created,
+                (_, true) => ChangeKind::Modified,
+            };
+            if path == claude_json {
+                let current = mcp_sections(&path);
+                if current == *mcp_seen {
+                    return None;
+                }
+                *mcp_seen = current;
+            }
+            Some(ConfigChanged {
+                path: path.to_string_lossy().into_owned(),
+                scope: p.scope,
+                kind: p.kind,
+                change,
+                project_path: p.project_path,
+            })
+        })
+        .collect()
+}
+
+struct ConfigWatcher {
+    watcher: RecommendedWatcher,
+    roots: WatchRoots,
+    watched: BTreeMap<PathBuf, RecursiveMode>,
+}
+
+impl ConfigWatcher {
+    /// Watch the targets that exist and drop the ones that are gone
+    fn refresh(&mut self) {
+        let targets: BTreeMap<PathBuf, RecursiveMode> = self
+            .roots
+            .targets()
+            .into_iter()
+            .filter(|(path, _)| path.is_dir())
+            .collect();
+
+        let stale: Vec<PathBuf> = self
+            .watched
+            .iter()
+            .filter(|(path, mode)| targets.get(*path) != Some(*mode))
+            .map(|(path, _)| path.clone())
+            .collect();
+        for path in stale {
+            // Fails when the directory was deleted, which already ended the watch
+            let _ = self.watcher.unwatch(&path);
+            self.watched.remove(&path);
+        }
+
+        for (path, mode) in targets {
+            if self.watched.contains_key(&path) {
+                continue;
+            }
+            match self.watcher.watch(&path, mode) {
+                Ok(()) => {
+                    self.watched.insert(path, mode);
+                }
+                Err(e) => log::warn!("Failed to watch {}: {}", path.display(), e),
+            }
+        }
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref WATCHER: Mutex<Option<ConfigWatcher>> = Mutex::new(None);
+}
+
+fn current_roots() -> WatchRoots {
+    WATCHER
+        .lock()
+        .as_ref()
+        .map(|w| w.roots.clone())
+        .unwrap_or_default()
+}
+
+fn refresh_watches() {
+    if let Some(watcher) = WATCHER.lock().as_mut() {
+        watcher.refresh();
+    }
+}
+
+/// Collect events until they settle, then report them
+fn debounce(rx: Receiver<notify::Result<Event>>, mut emit: impl FnMut(ConfigChanged)) {
+    let mut pending = BTreeMap::new();
+    let mut since: Option<Instant> = None;
+    let mut mcp_seen = mcp_sections(&current_roots().claude_json);
+
+    loop {
+        let timeout = match since {
+            Some(start) => DEBOUNCE.min(MAX_DELAY.saturating_sub(start.elapsed())),
+            None => Duration::from_secs(3600),
+        };
+        match rx.recv_timeout(timeout) {
+            Ok(Ok(event)) => {
+                record(&mut pending, &event, &current_roots());
+                if !pending.is_empty() {
+                    since.get_or_insert_with(Instant::now);
+                }
+                if !since.is_some_and(|start| start.elapsed() >= MAX_DELAY) {
+                    continue;
+                }
+            }
+            Ok(Err(e)) => {
+                log::warn!("Config watcher error: {}", e);
+                continue;
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+
+        if since.take().is_some() {
+            let claude_json = current_roots().claude_json;
+            for change in flush(&mut pending, &claude_json, &mut mcp_seen) {
+                emit(change);
+            }
+            // Config directories may have appeared or gone away
+            refresh_watches();
+        }
+    }
+}
+
+/// Start watching the user's Claude config
+pub fn start(app: AppHandle) {
+    let (tx, rx) = mpsc::channel();
+    let watcher = match notify::recommended_watcher(move |event| {
+        let _ = tx.send(event);
+    }) {
+        Ok(w) => w,
+        Err(e) => {
+            log::error!("Failed to start the config watcher: {}", e);
+            return;
+        }
+    };
+
+    let mut watcher = ConfigWatcher {
+        watcher,
+        roots: WatchRoots::user(),
+        watched: BTreeMap::new(),
+    };
+    watcher.refresh();
+    *WATCHER.lock() = Some(watcher);
+
+    std::thread::spawn(move || {
+        debounce(rx, |change| {
+            let _ = app.emit("config-changed", change);
+        });
+    });
+}
+
+/// Also watch a project's `.mcp.json` and `.claude` directory
+///
+/// Calls are counted, so each needs a matching `unwatch_project_config`.
+#[command]
+pub async fn watch_project_config(project_path: String) -> CommandResult<()> {
+    let mut guard = WATCHER.lock();
+    let Some(watcher) = guard.as_mut() else {
+        return CommandResult::err("Config watcher is not running");
+    };
+    *watcher
+        .roots
+        .projects
+        .entry(PathBuf::from(project_path))
+        .or_insert(0) += 1;
+    watcher.refresh();
+    CommandResult::ok(())
+}
+
+/// Stop watching a project once every caller has let go of it
+#[command]
+pub async fn unwatch_project_config(project_path: String) -> CommandResult<()> {
+    let mut guard = WATCHER.lock();
+    let Some(watcher) = guard.as_mut() else {
+        return CommandResult::err("Config watcher is not running");
+    };
+    let path = PathBuf::from(project_path);
+    if let Some(count) = watcher.roots.projects.get_mut(&path) {
+        *count -= 1;
+        if *count == 0 {
+            watcher.roots.projects.remove(&path);
+        }
+    }
+    watcher.refresh();
+    CommandResult::ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{CreateKind, DataChange, ModifyKind, RemoveKind};
+    use std::fs;
+
+    fn roots(base: &Path) -> WatchRoots {
+        WatchRoots {
+            claude_dir: base.join("home/.claude"),
+            claude_json: base.join("home/.claude.json"),
+            projects: BTreeMap::from([(base.join("app"), 1)]),
+        }
+    }
+
+    #[test]
+    fn test_classify() {
+        let base = Path::new("/tmp/watch");
+        let roots = roots(base);
+        let project = base.join("app");
+        let classify = |path: &str| {
+            roots
+                .classify(&base.join(path))
+                .map(|(scope, kind, project)| (scope, kind, project.map(Path::to_path_buf)))
+        };
+
+        use ConfigKind::*;
+        use SettingsScope::*;
+        assert_eq!(
+            classify("home/.claude.json"),
+            Some((User, McpServers, None))
+        );
+        assert_eq!(
+            classify("home/.claude/settings.json"),
+            Some((User, Settings, None))
+        );
+        assert_eq!(
+            classify("home/.claude/plugins/installed_plugins.json"),
+            Some((User, Plugins, None))
+        );
+        assert_eq!(
+            classify("home/.claude/commands/git/commit.md"),
+            Some((User, Commands, None))
+        );
+        assert_eq!(
+            classify("home/.claude/.disabled/skills/pdf/SKILL.md"),
+            Some((User, Skills, None))
+        );
+        assert_eq!(
+            classify("app/.mcp.json"),
+            Some((Project, McpServers, Some(project.clone())))
+        );
+        assert_eq!(
+            classify("app/.claude/settings.local.json"),
+            Some((Local, Settings, Some(project.clone())))
+        );
+        assert_eq!(
+            classify("app/.claude/agents/reviewer.md"),
+            Some((Project, Agents, Some(project)))
+        );
+
+        assert_eq!(classify("home/.claude/projects/app/session.jsonl"), None);
+        assert_eq!(classify("home/.claude/.settings.json.1234.tmp"), None);
+        assert_eq!(classify("app/.claude/plugins/installed_plugins.json"), None);
+        assert_eq!(classify("app/src/main.rs"), None);
+    }
+
+    #[test]
+    fn test_targets_cover_definition_dirs() {
+        let roots = roots(Path::new("/tmp/watch"));
+        let targets = roots.targets();
+        assert_eq!(
+            targets.get(Path::new("/tmp/watch/app/.claude/skills")),
+            Some(&RecursiveMode::Recursive)
+        );
+        assert_eq!(
+            targets.get(Path::new("/tmp/watch/home/.claude")),
+            Some(&RecursiveMode::NonRecursive)
+        );
+        assert_eq!(
+            targets.get(Path::new("/tmp/watch/home")),
+            Some(&RecursiveMode::NonRecursive)
+        );
+    }
+
+    #[test]
+    fn test_flush_reports_final_state() {
+        let base = std::env::temp_dir().join(format!("codepod-watch-{}", uuid::Uuid::new_v4()));
+        let roots = roots(&base);
+        let settings = base.join("app/.claude/settings.json");
+        let agent = base.join("app/.claude/agents/reviewer.md");
+        let scratch = base.join("app/.claude/commands/scratch.md");
+        fs::create_dir_all(agent.parent().unwrap()).unwrap();
+        fs::write(&settings, "{}").unwrap();
+        fs::write(&agent, "---\nname: reviewer\n---\n").unwrap();
+
+        let event = |kind: EventKind, path: &Path| Event::new(kind).add_path(path.to_path_buf());
+        let mut pending = BTreeMap::new();
+        for event in [
+            event(EventKind::Create(CreateKind::File), &agent),
+            event(EventKind::Modify(ModifyKind::Data(DataChange::Any)), &agent),
+            event(
+                EventKind::Modify(ModifyKind::Data(DataChange::Any)),
+                &settings,
+            ),
+            event(EventKind::Create(CreateKind::File), &scratch),
+            event(EventKind::Remove(RemoveKind::File), &scratch),
+        ] {
+            record(&mut pending, &event, &roots);
+        }
+        fs::remove_file(&settings).unwrap();
+
+        let changes: Vec<(String, ConfigKind, ChangeKind)> =
+            flush(&mut pending, &roots.claude_json, &mut None)
+                .into_iter()
+                .map(|c| (c.path, c.kind, c.change))
+                .collect();
+        fs::remove_dir_all(&base).unwrap();
+
+        assert_eq!(
+            changes,
+            [
+                (
+                    agent.to_string_lossy().into_owned(),
+                    ConfigKind::Agents,
+                    ChangeKind::Created
+                ),
+                (
+                    settings.to_string_lossy().into_owned(),
+                    ConfigKind::Settings,
+                    ChangeKind::Removed
+                ),
+            ]
+        );
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn test_flush_skips_unrelated_claude_json_changes() {
+        let base = std::env::temp_dir().join(format!("codepod-watch-{}", uuid::Uuid::new_v4()));
+        let roots = roots(&base);
+        fs::create_dir_all(base.join("home")).unwrap();
+        let modified = Event::new(EventKind::Modify(ModifyKind::Data(DataChange::Any)))
+            .add_path(roots.claude_json.clone());
+        let changes_after = |content: &str, seen: &mut Option<Value>| {
+            fs::write(&roots.claude_json, content).unwrap();
+            let mut pending = BTreeMap::new();
+            record(&mut pending, &modified, &roots);
+            flush(&mut pending, &roots.claude_json, seen).len()
+        };
+
+        let mut seen = None;
+        let servers = r#"{"mcpServers":{"a":{"command":"a"}},"numStartups":1}"#;
+        assert_eq!(changes_after(servers, &mut seen), 1);
+        let startups = r#"{"mcpServers":{"a":{"command":"a"}},"numStartups":2}"#;
+        assert_eq!(changes_after(startups, &mut seen), 0);
+        let history = r#"{"mcpServers":{"a":{"command":"a"}},"projects":{"/app":{"history":[]}}}"#;
+        assert_eq!(changes_after(history, &mut seen), 0);
+        let project =
+            r#"{"mcpServers":{"a":{"command":"a"}},"projects":{"/app":{"mcpServers":{}}}}"#;
+        assert_eq!(changes_after(project, &mut seen), 1);
+        fs::remove_dir_all(&base).unwrap();
+    }
+}
@@ -1,7 +1,11 @@
-mod commands;
+pub mod commands;
+mod mcp_server;
 mod pty;
 
-use commands::{claude, config, fs, git};
+use commands::{
+    backups, batch, budget, bundles, capture, claude, config, definitions, fs, git, hooks, jobs,
+    mcp, scheduler, sessions, settings, skills, structured, templates, usage, watcher,
+};
 use pty::{
     close_pty_session, create_claude_pty, create_pty_session, list_pty_sessions, resize_pty,
     write_to_pty,
@@ -16,11 +20,83 @@ pub fn run() {
             claude::get_claude_version,
             claude::invoke_claude,
             claude::invoke_claude_stream,
+            structured::invoke_claude_structured,
+            capture::replay_claude_capture,
+            // Job queue commands
+            jobs::list_claude_jobs,
+            jobs::get_claude_job_limits,
+            jobs::set_claude_job_limits,
+            jobs::cancel_claude_job,
+            // Usage ledger commands
+            usage::query_usage,
+            usage::get_usage_totals,
+            usage::import_transcript_usage,
+            // Budget commands
+            budget::get_budget_settings,
+            budget::set_budget_settings,
+            budget::get_budget_status,
+            // Scheduled task commands
+            scheduler::list_scheduled_tasks,
+            scheduler::create_scheduled_task,
+            scheduler::update_scheduled_task,
+            scheduler::delete_scheduled_task,
+            scheduler::run_scheduled_task_now,
+            scheduler::list_task_runs,
+            // Prompt template commands
+            templates::list_prompt_templates,
+            templates::save_prompt_template,
+            templates::delete_prompt_template,
+            templates::render_prompt_template,
+            templates::invoke_claude_template,
+            // Batch commands
+            batch::run_claude_batch,
+            batch::cancel_claude_batch,
+            // Session fork commands
+            sessions::list_transcript_messages,
+            sessions::fork_claude_session,
+            sessions::get_session_lineage,
             config::read_config_file,
+            config::read_config_file_versioned,
             config::write_config_file,
+            backups::list_config_backups,
+            backups::restore_config_backup,
             config::list_commands,
             config::list_agents,
-            config::list_skills,
+            skills::list_skills,
+            definitions::create_definition,
+            definitions::update_definition,
+            definitions::rename_definition,
+            definitions::delete_definition,
+            definitions::set_definition_enabled,
+            // Settings commands
+            settings::read_claude_settings,
+            settings::validate_claude_settings,
+            settings::write_claude_settings,
+            settings::get_effective_settings,
+            // Hook commands
+            hooks::list_hooks,
+            hooks::add_hook,
+            hooks::update_hook,
+            hooks::remove_hook,
+            hooks::test_hook,
+            // MCP server commands
+            mcp::list_mcp_servers,
+            mcp::add_mcp_server,
+            mcp::update_mcp_server,
+            mcp::remove_mcp_server,
+            mcp::set_mcp_server_enabled,
+            mcp_health::test_mcp_server,
+            // Config bundle commands
+            bundles::export_config_bundle,
+            bundles::preview_config_bundle,
+            bundles::import_config_bundle,
+            // Memory file commands
+            memory::list_memory_files,
+            memory::read_memory_file,
+            memory::write_memory_file,
+            // Config watcher commands
+            watcher::watch_project_config,
+            watcher::unwatch_project_config,
             // PTY commands
             create_pty_session,
             write_to_pty,
@@ -52,6 +128,9 @@ pub fn run() {
                         .build(),
                 )?;
             }
+            scheduler::start(app.handle().clone());
+            mcp_server::start();
+            watcher::start(app.handle().clone());
             Ok(())
         })
         .run(tauri::generate_context!())
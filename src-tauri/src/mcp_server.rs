@@ -0,0 +1,484 @@
+//! Built-in MCP server
+//!
+//! Claude sessions started by CodePod connect to this server over streamable
+//! HTTP on the loopback interface. Its tools let Claude list the other
+//! running sessions, read the git state and list directories. Requests must
+//! carry the bearer token generated at startup, which each session reads from
+//! a private config file rather than its command line.
+
+use crate::commands::{codepod_dir, fs, git, CommandResult};
+use crate::pty;
+use parking_lot::Mutex;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use uuid::Uuid;
+
+/// Name the server is registered under in Claude sessions
+const SERVER_NAME: &str = "codepod";
+/// Header naming the PTY session a request comes from
+const SESSION_HEADER: &str = "x-codepod-session";
+/// Protocol versions understood, newest first
+const PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+/// Largest request body accepted
+const MAX_BODY_BYTES: usize = 4 * 1024 * 1024;
+/// How long a new session waits for the server to start listening
+const START_WAIT: Duration = Duration::from_secs(2);
+
+#[derive(Clone)]
+struct ServerAddress {
+    port: u16,
+    token: String,
+}
+
+enum ServerState {
+    Starting,
+    Listening(ServerAddress),
+    Failed,
+}
+
+lazy_static::lazy_static! {
+    static ref SERVER: Mutex<ServerState> = Mutex::new(ServerState::Starting);
+}
+
+/// Start the server on a free loopback port
+pub fn start() {
+    // Configs of sessions from an earlier run hold a stale token
+    let _ = std::fs::remove_dir_all(session_configs_dir());
+
+    tauri::async_runtime::spawn(async move {
+        let listener = match TcpListener::bind(("127.0.0.1", 0)).await {
+            Ok(l) => l,
+            Err(e) => {
+                log::error!("Failed to start the MCP server: {}", e);
+                *SERVER.lock() = ServerState::Failed;
+                return;
+            }
+        };
+        let token = Uuid::new_v4().simple().to_string();
+        *SERVER.lock() = match listener.local_addr() {
+            Ok(address) => ServerState::Listening(ServerAddress {
+                port: address.port(),
+                token: token.clone(),
+            }),
+            Err(e) => {
+                log::error!("Failed to start the MCP server: {}", e);
+                ServerState::Failed
+            }
+        };
+        serve(listener, token).await;
+    });
+}
+
+fn session_configs_dir() -> PathBuf {
+    codepod_dir().join("mcp-sessions")
+}
+
+fn session_config_path(session_id: &str) -> PathBuf {
+    session_configs_dir().join(format!("{}.json", session_id))
+}
+
+/// The server's address, waiting up to `START_WAIT` while it binds
+async fn listening_server() -> Result<ServerAddress, String> {
+    let deadline = Instant::now() + START_WAIT;
+    loop {
+        match &*SERVER.lock() {
+            ServerState::Listening(server) => return Ok(server.clone()),
+            ServerState::Failed => return Err("the MCP server failed to start".to_string()),
+            ServerState::Starting if Instant::now() >= deadline => {
+                return Err("the MCP server is not listening yet".to_string())
+            }
+            ServerState::Starting => {}
+        }
+        tokio::time::sleep(Duration::from_millis(25)).await;
+    }
+}
+
+/// Write the `--mcp-config` file registering the server for one Claude
+/// session, readable only by the user since it holds the token
+///
+/// Waits briefly for a server that is still starting.
+pub async fn write_session_config(session_id: &str) -> Result<PathBuf, String> {
+    let server = listening_server().await?;
+    let config = server_config(server.port, &server.token, session_id).to_string();
+    let path = session_config_path(session_id);
+    let write_err = |e: std::io::Error| format!("Failed to write {}: {}", path.display(), e);
+
+    let dir = session_configs_dir();
+    let mut builder = std::fs::DirBuilder::new();
+    builder.recursive(true);
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt};
+        builder.mode(0o700);
+        options.mode(0o600);
+    }
+
+    builder.create(&dir).map_err(write_err)?;
+    let mut file = options.open(&path).map_err(write_err)?;
+    file.write_all(config.as_bytes()).map_err(write_err)?;
+    Ok(path)
+}
+
+/// Delete a session's config once its Claude process has exited
+pub fn remove_session_config(session_id: &str) {
+    let _ = std::fs::remove_file(session_config_path(session_id));
+}
+
+fn server_config(port: u16, token: &str, session_id: &str) -> Value {
+    json!({
+        "mcpServers": {
+            SERVER_NAME: {
+                "type": "http",
+                "url": format!("http://127.0.0.1:{}/mcp", port),
+                "headers": {
+                    "Authorization": format!("Bearer {}", token),
+                    SESSION_HEADER: session_id
+                }
+            }
+        }
+    })
+}
+
+async fn serve(listener: TcpListener, token: String) {
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                log::warn!("MCP server failed to accept a connection: {}", e);
+                continue;
+            }
+        };
+        let token = token.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(stream, &token).await {
+                log::debug!("MCP request failed: {}", e);
+            }
+        });
+    }
+}
+
+async fn write_response(
+    stream: &mut TcpStream,
+    status: &str,
+    body: Option<&Value>,
+) -> std::io::Result<()> {
+    let body = body.map(Value::to_string).unwrap_or_default();
+    let mut response = format!(
+        "HTTP/1.1 {}\r\nConnection: close\r\nContent-Length: {}\r\n",
+        status,
+        body.len()
+    );
+    if !body.is_empty() {
+        response.push_str("Content-Type: application/json\r\n");
+    }
+    response.push_str("\r\n");
+    response.push_str(&body);
+    stream.write_all(response.as_bytes()).await
+}
+
+/// Serve one HTTP request; every response closes the connection
+async fn handle_connection(stream: TcpStream, token: &str) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await?;
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = (
+        parts.next().unwrap_or_default().to_string(),
+        parts.next().unwrap_or_default().to_string(),
+    );
+
+    let mut headers = Vec::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 {
+            break;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+        }
+    }
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    };
+
+    let authorized = header("authorization") == Some(format!("Bearer {}", token).as_str());
+    let length: usize = header("content-length")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+    let caller = header(SESSION_HEADER).map(str::to_string);
+
+    let stream = reader.get_mut();
+    if target.split('?').next() != Some("/mcp") {
+        return write_response(stream, "404 Not Found", None).await;
+    }
+    if !authorized {
+        return write_response(stream, "401 Unauthorized", None).await;
+    }
+    // No server-initiated stream is offered, so only POST is supported
+    if method != "POST" {
+        return write_response(stream, "405 Method Not Allowed", None).await;
+    }
+    if length > MAX_BODY_BYTES {
+        return write_response(stream, "413 Payload Too Large", None).await;
+    }
+
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).await?;
+    let stream = reader.get_mut();
+
+    let message: Value = match serde_json::from_slice(&body) {
+        Ok(m) => m,
+        Err(e) => {
+            let error = json!({
+                "jsonrpc": "2.0",
+                "id": null,
+                "error": { "code": -32700, "message": format!("Parse error: {}", e) }
+            });
+            return write_response(stream, "400 Bad Request", Some(&error)).await;
+        }
+    };
+
+    let response = match message {
+        Value::Array(messages) => {
+            let mut responses = Vec::new();
+            for message in &messages {
+                responses.extend(handle_message(message, caller.as_deref()).await);
+            }
+            (!responses.is_empty()).then_some(Value::Array(responses))
+        }
+        message => handle_message(&message, caller.as_deref()).await,
+    };
+
+    match response {
+        Some(response) => write_response(stream, "200 OK", Some(&response)).await,
+        // Notifications and responses get no reply
+        None => write_response(stream, "202 Accepted", None).await,
+    }
+}
+
+/// Answer one JSON-RPC request; notifications return `None`
+async fn handle_message(message: &Value, caller: Option<&str>) -> Option<Value> {
+    let id = message.get("id")?.clone();
+    let method = message.get("method")?.as_str().unwrap_or_default();
+    let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+    let result = match method {
+        "initialize" => Ok(initialize_result(&params)),
+        "ping" => Ok(json!({})),
+        "tools/list" => Ok(json!({ "tools": tool_definitions() })),
+        "tools/call" => Ok(call_tool(&params, caller).await),
+        _ => Err(format!("Method not found: {}", method)),
+    };
+
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(message) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": -32601, "message": message }
+        }),
+    })
+}
+
+fn initialize_result(params: &Value) -> Value {
+    let requested = params.get("protocolVersion").and_then(Value::as_str);
+    let version = requested
+        .filter(|v| PROTOCOL_VERSIONS.contains(v))
+        .unwrap_or(PROTOCOL_VERSIONS[0]);
+
+    json!({
+        "protocolVersion": version,
+        "capabilities": { "tools": {} },
+        "serverInfo": { "name": SERVER_NAME, "version": env!("CARGO_PKG_VERSION") },
+        "instructions": "Tools for the CodePod desktop app this session runs in."
+    })
+}
+
+fn tool_definitions() -> Value {
+    let path =
+        json!({ "type": "string", "description": "Absolute path of the repository or directory" });
+    json!([
+        {
+            "name": "list_sessions",
+            "description": "List the terminal sessions running in CodePod, marking the current one",
+            "inputSchema": { "type": "object", "properties": {} }
+        },
+        {
+            "name": "git_status",
+            "description": "Get the branch and changed files of a git repository",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "path": path },
+                "required": ["path"]
+            }
+        },
+        {
+            "name": "list_directory",
+            "description": "List the entries of a directory",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "path": path },
+                "required": ["path"]
+            }
+        }
+    ])
+}
+
+fn string_arg(args: &Value, key: &str) -> Result<String, String> {
+    args.get(key)
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| format!("Missing string argument '{}'", key))
+}
+
+/// A command's data, or its error
+fn command_data<T>(result: CommandResult<T>) -> Result<T, String> {
+    match result.data {
+        Some(data) if result.success => Ok(data),
+        _ => Err(result.error.unwrap_or_else(|| "Unknown error".to_string())),
+    }
+}
+
+/// Pretty JSON of a command's data, or its error
+fn command_output<T: Serialize>(result: CommandResult<T>) -> Result<String, String> {
+    serde_json::to_string_pretty(&command_data(result)?)
+        .map_err(|e| format!("Failed to serialize result: {}", e))
+}
+
+fn run_tool(name: &str, args: &Value, caller: Option<&str>) -> Result<String, String> {
+    match name {
+        "list_sessions" => {
+            let sessions: Vec<Value> = pty::session_summaries()
+                .into_iter()
+                .map(|s| {
+                    let current = caller == Some(s.session_id.as_str());
+                    let mut value = json!(s);
+                    value["current"] = json!(current);
+                    value
+                })
+                .collect();
+            command_output(CommandResult::ok(sessions))
+        }
+        "git_status" => command_output(git::get_git_status(string_arg(args, "path")?)),
+        "list_directory" => command_output(fs::list_directory(string_arg(args, "path")?)),
+        _ => Err(format!("Unknown tool: {}", name)),
+    }
+}
+
+async fn call_tool(params: &Value, caller: Option<&str>) -> Value {
+    let name = params
+        .get("name")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    let args = params.get("arguments").cloned().unwrap_or(json!({}));
+    let caller = caller.map(str::to_string);
+
+    // Git and file system tools block
+    let outcome = tokio::task::spawn_blocking(move || run_tool(&name, &args, caller.as_deref()))
+        .await
+        .unwrap_or_else(|e| Err(format!("Tool failed: {}", e)));
+
+    match outcome {
+        Ok(text) => json!({ "content": [{ "type": "text", "text": text }] }),
+        Err(error) => json!({ "content": [{ "type": "text", "text": error }], "isError": true }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::mcp::McpServerConfig;
+    use crate::commands::mcp_health::check_server;
+    use std::time::Duration;
+
+    async fn start_test_server() -> (u16, tokio::task::JoinHandle<()>) {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(serve(listener, "secret".to_string()));
+        (port, server)
+    }
+
+    #[tokio::test]
+    async fn test_session_config_passes_check() {
+        let (port, server) = start_test_server().await;
+        let registered = server_config(port, "secret", "pty-1");
+        let config: McpServerConfig =
+            serde_json::from_value(registered["mcpServers"][SERVER_NAME].clone()).unwrap();
+
+        let report = check_server(&config, None, Duration::from_secs(10)).await;
+        server.abort();
+
+        assert!(report.ok, "{:?}", report.error);
+        assert_eq!(
+            report.protocol_version.as_deref(),
+            Some(PROTOCOL_VERSIONS[0])
+        );
+        let tools: Vec<&str> = report.tools.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(tools, ["list_sessions", "git_status", "list_directory"]);
+    }
+
+    #[tokio::test]
+    async fn test_rejects_wrong_token() {
+        let (port, server) = start_test_server().await;
+        let mut config: McpServerConfig = serde_json::from_value(
+            server_config(port, "wrong", "pty-1")["mcpServers"][SERVER_NAME].clone(),
+        )
+        .unwrap();
+        config.headers.remove(SESSION_HEADER);
+
+        let report = check_server(&config, None, Duration::from_secs(10)).await;
+        server.abort();
+
+        assert!(!report.ok);
+        assert!(report.error.unwrap_or_default().contains("401"));
+    }
+
+    #[tokio::test]
+    async fn test_tool_calls() {
+        let call = |name: &str, arguments: Value| {
+            json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "tools/call",
+                "params": { "name": name, "arguments": arguments }
+            })
+        };
+
+        let response = handle_message(&call("git_status", json!({})), None)
+            .await
+            .unwrap();
+        assert_eq!(response["result"]["isError"], json!(true));
+
+        let response = handle_message(
+            &call(
+                "list_directory",
+                json!({ "path": env!("CARGO_MANIFEST_DIR") }),
+            ),
+            None,
+        )
+        .await
+        .unwrap();
+        assert!(response["result"].get("isError").is_none());
+        let text = response["result"]["content"][0]["text"].as_str().unwrap();
+        assert!(text.contains("Cargo.toml"));
+
+        let notification = json!({ "jsonrpc": "2.0", "method": "notifications/initialized" });
+        assert!(handle_message(&notification, None).await.is_none());
+    }
+}
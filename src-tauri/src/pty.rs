@@ -2,6 +2,10 @@
 //!
 //! Manages terminal sessions for running Claude CLI in interactive mode.
 
+use crate::commands::claude::claude_binary;
+use crate::commands::jobs::now_millis;
+use crate::commands::usage;
+use crate::mcp_server;
 use parking_lot::Mutex;
 use portable_pty::{native_pty_system, CommandBuilder, PtyPair, PtySize};
 use std::collections::HashMap;
@@ -15,6 +19,9 @@ use uuid::Uuid;
 struct PtySession {
     pair: PtyPair,
     writer: Box<dyn Write + Send>,
+    command: String,
+    cwd: Option<String>,
+    started_at: u64,
 }
 
 // Global PTY session manager
@@ -37,6 +44,15 @@ pub struct PtyExit {
     pub exit_code: Option<i32>,
 }
 
+/// A running PTY session, as reported to Claude sessions
+#[derive(Clone, serde::Serialize)]
+pub struct PtySessionInfo {
+    pub session_id: String,
+    pub command: String,
+    pub cwd: Option<String>,
+    pub started_at: u64,
+}
+
 /// Create a new PTY session
 #[command]
 pub async fn create_pty_session(
@@ -48,18 +64,38 @@ pub async fn create_pty_session(
     rows: Option<u16>,
 ) -> Result<String, String> {
     let session_id = Uuid::new_v4().to_string();
+    let size = pty_size(cols, rows);
+    open_session(app, session_id.clone(), cwd, command, args, size, None)?;
+    Ok(session_id)
+}
+
+fn pty_size(cols: Option<u16>, rows: Option<u16>) -> PtySize {
+    PtySize {
+        rows: rows.unwrap_or(24),
+        cols: cols.unwrap_or(80),
+        pixel_width: 0,
+        pixel_height: 0,
+    }
+}
 
+/// Spawn a command in a new PTY and stream its output as events
+///
+/// The usage in `claude_session`'s transcript is imported once it exits.
+fn open_session(
+    app: AppHandle,
+    session_id: String,
+    cwd: Option<String>,
+    command: Option<String>,
+    args: Option<Vec<String>>,
+    size: PtySize,
+    claude_session: Option<String>,
+) -> Result<(), String> {
     // Create PTY system
     let pty_system = native_pty_system();
 
     // Create PTY pair with specified size
     let pair = pty_system
-        .openpty(PtySize {
-            rows: rows.unwrap_or(24),
-            cols: cols.unwrap_or(80),
-            pixel_width: 0,
-            pixel_height: 0,
-        })
+        .openpty(size)
         .map_err(|e| format!("Failed to create PTY: {}", e))?;
 
     // Build command
@@ -103,7 +139,16 @@ pub async fn create_pty_session(
     // Store session
     {
         let mut sessions = PTY_SESSIONS.lock();
-        sessions.insert(session_id.clone(), PtySession { pair, writer });
+        sessions.insert(
+            session_id.clone(),
+            PtySession {
+                pair,
+                writer,
+                command: cmd_str.to_string(),
+                cwd,
+                started_at: now_millis(),
+            },
+        );
     }
 
     // Spawn thread to read output and emit events
@@ -149,11 +194,15 @@ pub async fn create_pty_session(
         );
 
         // Clean up session
-        let mut sessions = PTY_SESSIONS.lock();
-        sessions.remove(&session_id_clone);
+        mcp_server::remove_session_config(&session_id_clone);
+        PTY_SESSIONS.lock().remove(&session_id_clone);
+
+        if let Some(claude_session) = claude_session {
+            usage::import_session_usage(&claude_session);
+        }
     });
 
-    Ok(session_id)
+    Ok(())
 }
 
 /// Write input to a PTY session
@@ -217,6 +266,22 @@ pub async fn list_pty_sessions() -> Vec<String> {
     sessions.keys().cloned().collect()
 }
 
+/// Describe the active PTY sessions, oldest first
+pub fn session_summaries() -> Vec<PtySessionInfo> {
+    let sessions = PTY_SESSIONS.lock();
+    let mut summaries: Vec<PtySessionInfo> = sessions
+        .iter()
+        .map(|(id, session)| PtySessionInfo {
+            session_id: id.clone(),
+            command: session.command.clone(),
+            cwd: session.cwd.clone(),
+            started_at: session.started_at,
+        })
+        .collect();
+    summaries.sort_by_key(|s| s.started_at);
+    summaries
+}
+
 /// Create a Claude session with PTY
 #[command]
 pub async fn create_claude_pty(
@@ -227,21 +292,42 @@ pub async fn create_claude_pty(
     rows: Option<u16>,
 ) -> Result<String, String> {
     let mut args = Vec::new();
+    let session_id = Uuid::new_v4().to_string();
 
-    // Add resume flag if session ID provided
-    if let Some(ref session) = resume_session {
-        args.push("--resume".to_string());
-        args.push(session.clone());
+    // Resume the given session or start one under a known id, so its
+    // transcript can be found for the usage ledger
+    let claude_session = match resume_session {
+        Some(session) => {
+            args.push("--resume".to_string());
+            args.push(session.clone());
+            session
+        }
+        None => {
+            args.push("--session-id".to_string());
+            args.push(session_id.clone());
+            session_id.clone()
+        }
+    };
+
+    // Let the session reach CodePod's built-in MCP server
+    match mcp_server::write_session_config(&session_id).await {
+        Ok(config) => {
+            args.push("--mcp-config".to_string());
+            args.push(config.to_string_lossy().to_string());
+        }
+        Err(e) => log::warn!("Starting Claude without the CodePod MCP server: {}", e),
     }
 
     // Create PTY session with claude command
-    create_pty_session(
+    open_session(
         app,
+        session_id.clone(),
         cwd,
-        Some("claude".to_string()),
-        if args.is_empty() { None } else { Some(args) },
-        cols,
-        rows,
+        Some(claude_binary()),
+        Some(args),
+        pty_size(cols, rows),
+        Some(claude_session),
     )
-    .await
+    .inspect_err(|_| mcp_server::remove_session_config(&session_id))?;
+    Ok(session_id)
 }
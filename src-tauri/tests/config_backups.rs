@@ -0,0 +1,52 @@
+//! Backups and restores of config files written through `safe_write`
+
+use app_lib::commands::backups::{
+    file_version, list_config_backups, restore_config_backup, safe_write,
+};
+use std::env;
+use std::fs;
+use std::sync::Once;
+
+static SETUP: Once = Once::new();
+
+/// Keep backups out of the real data directory
+fn use_scratch_data_dir() {
+    SETUP.call_once(|| {
+        let data_dir = env::temp_dir().join(format!("codepod-test-{}", uuid::Uuid::new_v4()));
+        env::set_var("CODEPOD_DATA_DIR", data_dir);
+    });
+}
+
+fn scratch_file() -> std::path::PathBuf {
+    env::temp_dir()
+        .join(format!("codepod-config-{}", uuid::Uuid::new_v4()))
+        .join("settings.json")
+}
+
+#[tokio::test]
+async fn test_backups_rotate_and_restore() {
+    use_scratch_data_dir();
+    let path = scratch_file();
+    let path_str = path.to_string_lossy().to_string();
+
+    for i in 0..15 {
+        safe_write(&path, format!("{{\"v\":{}}}", i).as_bytes(), None).unwrap();
+    }
+
+    let backups = list_config_backups(path_str.clone()).await.data.unwrap();
+    assert_eq!(backups.len(), 10);
+
+    // The newest backup is the version before the last write
+    let restored = restore_config_backup(path_str.clone(), None).await;
+    assert!(restored.success, "{:?}", restored.error);
+    assert_eq!(fs::read_to_string(&path).unwrap(), "{\"v\":13}");
+    assert_eq!(restored.data.unwrap(), file_version(&path).unwrap());
+
+    // Restoring backed up the content it replaced
+    let backups = list_config_backups(path_str.clone()).await.data.unwrap();
+    let undo = restore_config_backup(path_str, Some(backups[0].id.clone())).await;
+    assert!(undo.success);
+    assert_eq!(fs::read_to_string(&path).unwrap(), "{\"v\":14}");
+
+    let _ = fs::remove_dir_all(path.parent().unwrap());
+}
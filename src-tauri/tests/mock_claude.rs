@@ -0,0 +1,211 @@
+//! Offline tests of the Claude invocation paths against `mock-claude`
+
+use app_lib::commands::capture::{load_capture, CaptureRecord};
+use app_lib::commands::claude::{
+    execute_claude, stream_claude, AttemptOutcome, ClaudeOptions, RetryPolicy,
+};
+use app_lib::commands::jobs::JobState;
+use std::env;
+use std::future::pending;
+use std::sync::Once;
+use std::time::Duration;
+
+static SETUP: Once = Once::new();
+
+/// Point the invocation paths at the mock CLI and a scratch data directory
+fn use_mock() {
+    SETUP.call_once(|| {
+        let data_dir = env::temp_dir().join(format!("codepod-test-{}", uuid::Uuid::new_v4()));
+        env::set_var("CODEPOD_CLAUDE_BIN", env!("CARGO_BIN_EXE_mock-claude"));
+        env::set_var("CODEPOD_DATA_DIR", data_dir);
+    });
+}
+
+fn fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+/// Prompts are unique so `fail-first` counters don't leak between runs
+fn prompt(directives: &str) -> String {
+    format!("{} [mock:delay=1] {}", directives, uuid::Uuid::new_v4())
+}
+
+fn fast_retry() -> Option<RetryPolicy> {
+    Some(RetryPolicy {
+        max_attempts: 3,
+        initial_backoff_ms: 10,
+        ..Default::default()
+    })
+}
+
+#[tokio::test]
+async fn test_execute_claude_success() {
+    use_mock();
+    let options = ClaudeOptions {
+        model: Some("claude-test".to_string()),
+        ..Default::default()
+    };
+
+    let result = execute_claude(&prompt(""), &options).await;
+    assert!(result.success, "{:?}", result.error);
+
+    let invocation = result.data.unwrap();
+    assert_eq!(invocation.attempts.len(), 1);
+    assert_eq!(invocation.attempts[0].outcome, AttemptOutcome::Success);
+
+    let output: serde_json::Value =
+        serde_json::from_str(invocation.output.as_deref().unwrap()).unwrap();
+    assert_eq!(output["type"], "result");
+    assert!(output["result"]
+        .as_str()
+        .unwrap()
+        .starts_with("Mock reply to:"));
+}
+
+#[tokio::test]
+async fn test_execute_claude_retries_transient_errors() {
+    use_mock();
+    let options = ClaudeOptions {
+        retry: fast_retry(),
+        ..Default::default()
+    };
+
+    let result = execute_claude(
+        &prompt("[mock:error=overloaded] [mock:fail-first=2]"),
+        &options,
+    )
+    .await;
+    assert!(result.success, "{:?}", result.error);
+
+    let attempts = result.data.unwrap().attempts;
+    assert_eq!(attempts.len(), 3);
+    assert!(attempts[0].transient);
+    assert_eq!(attempts[0].outcome, AttemptOutcome::Error);
+    assert_eq!(attempts[2].outcome, AttemptOutcome::Success);
+}
+
+#[tokio::test]
+async fn test_execute_claude_does_not_retry_permanent_errors() {
+    use_mock();
+    let options = ClaudeOptions {
+        retry: fast_retry(),
+        ..Default::default()
+    };
+
+    let result = execute_claude(&prompt("[mock:error=auth]"), &options).await;
+    assert!(!result.success);
+    assert!(result.error.unwrap().contains("Invalid API key"));
+
+    let attempts = result.data.unwrap().attempts;
+    assert_eq!(attempts.len(), 1);
+    assert!(!attempts[0].transient);
+}
+
+#[tokio::test]
+async fn test_execute_claude_idle_timeout() {
+    use_mock();
+    let options = ClaudeOptions {
+        idle_timeout_secs: Some(1),
+        ..Default::default()
+    };
+
+    let result = execute_claude(&prompt("[mock:hang]"), &options).await;
+    assert!(!result.success);
+    assert_eq!(
+        result.data.unwrap().attempts[0].outcome,
+        AttemptOutcome::IdleTimeout
+    );
+}
+
+#[tokio::test]
+async fn test_stream_claude_replays_fixture() {
+    use_mock();
+    let directives = format!("[mock:fixture={}]", fixture("stream-tool-use.jsonl"));
+
+    let mut messages = Vec::new();
+    let (state, error) = stream_claude(
+        &prompt(&directives),
+        &ClaudeOptions::default(),
+        pending(),
+        |m| messages.push(m),
+    )
+    .await;
+
+    assert_eq!(state, JobState::Completed, "{:?}", error);
+    let types: Vec<&str> = messages
+        .iter()
+        .map(|m| m["type"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        types,
+        ["system", "assistant", "assistant", "user", "assistant", "result"]
+    );
+    assert!(messages.iter().all(|m| m.get("_delay_ms").is_none()));
+    assert_eq!(messages[5]["total_cost_usd"], 0.0081);
+}
+
+#[tokio::test]
+async fn test_stream_claude_reports_crash() {
+    use_mock();
+    let (state, error) = stream_claude(
+        &prompt("[mock:crash]"),
+        &ClaudeOptions::default(),
+        pending(),
+        |_| {},
+    )
+    .await;
+
+    assert_eq!(state, JobState::Failed);
+    assert!(error.unwrap().contains("simulated crash"));
+}
+
+#[tokio::test]
+async fn test_stream_claude_cancel_kills_process() {
+    use_mock();
+    let mut messages = 0;
+    let (state, _) = stream_claude(
+        &prompt("[mock:hang]"),
+        &ClaudeOptions::default(),
+        tokio::time::sleep(Duration::from_millis(300)),
+        |_| messages += 1,
+    )
+    .await;
+
+    assert_eq!(state, JobState::Cancelled);
+    assert_eq!(messages, 1);
+}
+
+#[tokio::test]
+async fn test_stream_claude_writes_capture() {
+    use_mock();
+    let path = env::temp_dir().join(format!("capture-{}.jsonl", uuid::Uuid::new_v4()));
+    let options = ClaudeOptions {
+        capture_path: Some(path.to_string_lossy().to_string()),
+        ..Default::default()
+    };
+
+    let mut messages = Vec::new();
+    let (state, _) = stream_claude(&prompt(""), &options, pending(), |m| messages.push(m)).await;
+    assert_eq!(state, JobState::Completed);
+
+    let records = load_capture(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    assert!(matches!(records[0], CaptureRecord::Start { .. }));
+    let captured: Vec<serde_json::Value> = records
+        .iter()
+        .filter_map(|r| match r {
+            CaptureRecord::Stdout { line, .. } => serde_json::from_str(line).ok(),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(captured, messages);
+    assert!(matches!(
+        records.last(),
+        Some(CaptureRecord::Exit {
+            state: JobState::Completed,
+            error: None,
+            ..
+        })
+    ));
+}